@@ -23,8 +23,13 @@ fn main() -> anyhow::Result<()> {
         anyhow::bail!("No configuration file specified");
     };
     // cfgfifo identifies the format used by the file `cfgpath` based on its
-    // file extension and deserializes it appropriately:
-    let cfg: AppConfig = cfgfifo::load(cfgpath)?;
+    // file extension and deserializes it appropriately.  Any keys that
+    // `AppConfig` doesn't know about (e.g., misspellings like "enalbe_foo")
+    // are returned so that we can warn about them:
+    let (cfg, ignored) = cfgfifo::Cfgfifo::new().load_with_ignored::<AppConfig, _>(cfgpath)?;
+    for key in ignored {
+        eprintln!("Warning: ignoring unknown configuration key {key:?}");
+    }
     println!("You specified the following configuration:");
     println!("{cfg:#?}");
     Ok(())
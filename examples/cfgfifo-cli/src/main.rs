@@ -0,0 +1,252 @@
+use anyhow::Context;
+use cfgfifo::{Cfgfifo, Compression, DeserializeError, Format, TranscodeError};
+use clap::Parser;
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Convert, check, and reformat configuration files
+///
+/// The format of each file is determined from its file name or extension
+/// unless overridden on the command line.  Files whose names end in a
+/// compression extension (`.gz`, `.xz`, or `.zst`) are decompressed when read
+/// and compressed when written.
+#[derive(Clone, Debug, Eq, Parser, PartialEq)]
+#[command(name = "cfgfifo", version)]
+enum Command {
+    /// Convert a file to another format
+    ///
+    /// Comments and formatting in the input are not preserved.
+    Convert {
+        /// Format of the input file
+        #[arg(short, long, value_name = "FORMAT", value_parser = parse_format)]
+        format: Option<Format>,
+
+        /// Format of the output file
+        #[arg(short, long, value_name = "FORMAT", value_parser = parse_format)]
+        to: Option<Format>,
+
+        /// The file to convert
+        input: PathBuf,
+
+        /// The file to write the converted document to
+        output: PathBuf,
+    },
+
+    /// Check that files can be parsed
+    ///
+    /// Errors are reported with a snippet of the offending input, and the
+    /// command exits nonzero if any file fails to parse.
+    Check {
+        /// Format of the files
+        #[arg(short, long, value_name = "FORMAT", value_parser = parse_format)]
+        format: Option<Format>,
+
+        /// The files to check
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+
+    /// Rewrite files in their format's canonical pretty form
+    ///
+    /// Comments and formatting in the input are not preserved.
+    Fmt {
+        /// Format of the files
+        #[arg(short, long, value_name = "FORMAT", value_parser = parse_format)]
+        format: Option<Format>,
+
+        /// Don't rewrite the files; instead, list the files that would be
+        /// changed and exit nonzero if there are any
+        #[arg(long)]
+        check: bool,
+
+        /// The files to format
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
+impl Command {
+    /// Run the command.  Returns `false` if any file failed.
+    fn run(self) -> anyhow::Result<bool> {
+        match self {
+            Command::Convert {
+                format,
+                to,
+                input,
+                output,
+            } => {
+                let (from, content) = read_input(&input, format)?;
+                let to = identify(&output, to)?;
+                let converted = transcode(&input, from, to, &content)?;
+                write_output(&output, &converted)?;
+                Ok(true)
+            }
+            Command::Check { format, files } => Ok(for_each_file(&files, |path| {
+                let (format, content) = read_input(path, format)?;
+                format
+                    .load_from_str::<cfgfifo::Value>(&content)
+                    .map_err(|e| parse_error(path, format, &e, &content))?;
+                Ok(true)
+            })),
+            Command::Fmt {
+                format,
+                check,
+                files,
+            } => Ok(for_each_file(&files, |path| {
+                let (format, content) = read_input(path, format)?;
+                let formatted = transcode(path, format, format, &content)?;
+                if formatted == content {
+                    Ok(true)
+                } else if check {
+                    println!("{}", path.display());
+                    Ok(false)
+                } else {
+                    write_output(path, &formatted)?;
+                    Ok(true)
+                }
+            })),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match Command::parse().run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            report(&e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Call `f` on each path in turn, reporting any errors, and return whether
+/// every call returned `Ok(true)`
+fn for_each_file<F>(paths: &[PathBuf], mut f: F) -> bool
+where
+    F: FnMut(&Path) -> anyhow::Result<bool>,
+{
+    let mut ok = true;
+    for path in paths {
+        match f(path) {
+            Ok(true) => (),
+            Ok(false) => ok = false,
+            Err(e) => {
+                report(&e);
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+fn report(e: &anyhow::Error) {
+    eprintln!("cfgfifo: {e:#}");
+}
+
+/// Parse a `--format` argument, listing the supported formats on failure
+fn parse_format(s: &str) -> Result<Format, String> {
+    s.parse::<Format>().map_err(|_| {
+        let names = Format::iter().map(|f| f.to_string()).collect::<Vec<_>>();
+        format!("unknown format; expected one of: {}", names.join(", "))
+    })
+}
+
+/// Return `format` if set; otherwise, identify the format of `path` from its
+/// file name
+fn identify(path: &Path, format: Option<Format>) -> anyhow::Result<Format> {
+    if let Some(f) = format {
+        return Ok(f);
+    }
+    let f = Cfgfifo::new().identify(path).with_context(|| {
+        format!(
+            "failed to identify file format of {}; use --format to specify it",
+            path.display()
+        )
+    })?;
+    f.builtin()
+        .with_context(|| format!("{}: unsupported format {}", path.display(), f.name()))
+}
+
+/// Identify the format of the file at `path` and read its contents,
+/// decompressing them if the file name ends in a compression extension
+fn read_input(path: &Path, format: Option<Format>) -> anyhow::Result<(Format, String)> {
+    let format = identify(path, format)?;
+    let content =
+        read_decompressed(path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok((format, content))
+}
+
+fn read_decompressed(path: &Path) -> io::Result<String> {
+    let fp = BufReader::new(File::open(path)?);
+    let reader: Box<dyn Read> = match Compression::identify(path) {
+        None => Box::new(fp),
+        Some(Compression::Gzip) => Box::new(flate2::read::MultiGzDecoder::new(fp)),
+        Some(Compression::Xz) => Box::new(liblzma::read::XzDecoder::new_multi_decoder(fp)),
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::with_buffer(fp)?),
+        Some(c) => return Err(unsupported(c)),
+    };
+    io::read_to_string(reader)
+}
+
+/// Write `content` to `path`, compressing it if the file name ends in a
+/// compression extension
+fn write_output(path: &Path, content: &str) -> anyhow::Result<()> {
+    compress(path, content)
+        .and_then(|data| fs::write(path, data))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn compress<'a>(path: &Path, content: &'a str) -> io::Result<Cow<'a, [u8]>> {
+    let data = match Compression::identify(path) {
+        None => return Ok(Cow::Borrowed(content.as_bytes())),
+        Some(Compression::Gzip) => {
+            let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            enc.write_all(content.as_bytes())?;
+            enc.finish()?
+        }
+        Some(Compression::Xz) => {
+            let mut enc = liblzma::write::XzEncoder::new(Vec::new(), 6);
+            enc.write_all(content.as_bytes())?;
+            enc.finish()?
+        }
+        Some(Compression::Zstd) => zstd::encode_all(content.as_bytes(), 0)?,
+        Some(c) => return Err(unsupported(c)),
+    };
+    Ok(Cow::Owned(data))
+}
+
+fn unsupported(c: Compression) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{c} compression is not supported"),
+    )
+}
+
+/// Construct an error for a failure to parse `content` (read from `path`),
+/// including a snippet of the input pointing at the error location
+fn parse_error(path: &Path, format: Format, e: &DeserializeError, content: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{}: failed to parse as {format}: {}",
+        path.display(),
+        e.render(content)
+    )
+}
+
+/// Transcode `content` (read from `path`) into file contents ending in a
+/// newline, rendering deserialization errors with a snippet of the input
+fn transcode(path: &Path, from: Format, to: Format, content: &str) -> anyhow::Result<String> {
+    let mut output = Format::transcode(from, to, content).map_err(|e| match e {
+        TranscodeError::Deserialize { format, source } => {
+            parse_error(path, format, &source, content)
+        }
+        e => anyhow::Error::new(e).context(format!("{}: failed to convert", path.display())),
+    })?;
+    if !output.ends_with('\n') {
+        output.push('\n');
+    }
+    Ok(output)
+}
@@ -0,0 +1,149 @@
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::{self, File};
+use std::io::{Write, read_to_string};
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::tempdir;
+
+fn cfgfifo(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cfgfifo"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("failed to run cfgfifo")
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).expect("stderr should be UTF-8")
+}
+
+#[test]
+fn convert() {
+    let tmpdir = tempdir().unwrap();
+    fs::write(
+        tmpdir.path().join("in.yaml"),
+        "name: app\nserver:\n  port: 8080\n",
+    )
+    .unwrap();
+    let r = cfgfifo(tmpdir.path(), &["convert", "in.yaml", "out.toml"]);
+    assert!(r.status.success(), "{}", stderr(&r));
+    assert_eq!(
+        fs::read_to_string(tmpdir.path().join("out.toml")).unwrap(),
+        "name = \"app\"\n\n[server]\nport = 8080\n"
+    );
+}
+
+#[test]
+fn convert_format_overrides() {
+    let tmpdir = tempdir().unwrap();
+    fs::write(tmpdir.path().join("config"), "{\"ports\": [80, 443]}").unwrap();
+    let r = cfgfifo(
+        tmpdir.path(),
+        &["convert", "-f", "json", "--to", "yaml", "config", "out.txt"],
+    );
+    assert!(r.status.success(), "{}", stderr(&r));
+    assert_eq!(
+        fs::read_to_string(tmpdir.path().join("out.txt")).unwrap(),
+        "ports:\n- 80\n- 443\n"
+    );
+}
+
+#[test]
+fn convert_incompatible() {
+    let tmpdir = tempdir().unwrap();
+    fs::write(tmpdir.path().join("in.json"), "{\"a\": [null]}").unwrap();
+    let r = cfgfifo(tmpdir.path(), &["convert", "in.json", "out.toml"]);
+    assert!(!r.status.success(), "convert succeeded");
+    assert_eq!(
+        stderr(&r),
+        "cfgfifo: in.json: failed to convert: TOML cannot represent null values (at a[0])\n"
+    );
+    assert!(!tmpdir.path().join("out.toml").exists());
+}
+
+#[test]
+fn convert_compressed() {
+    let tmpdir = tempdir().unwrap();
+    let mut enc = GzEncoder::new(
+        File::create(tmpdir.path().join("in.json.gz")).unwrap(),
+        Compression::default(),
+    );
+    enc.write_all(b"{\"name\": \"app\", \"port\": 8080}")
+        .unwrap();
+    enc.finish().unwrap();
+    let r = cfgfifo(tmpdir.path(), &["check", "in.json.gz"]);
+    assert!(r.status.success(), "{}", stderr(&r));
+    let r = cfgfifo(tmpdir.path(), &["convert", "in.json.gz", "out.toml.gz"]);
+    assert!(r.status.success(), "{}", stderr(&r));
+    let out = File::open(tmpdir.path().join("out.toml.gz")).unwrap();
+    assert_eq!(
+        read_to_string(GzDecoder::new(out)).unwrap(),
+        "name = \"app\"\nport = 8080\n"
+    );
+}
+
+#[test]
+fn check() {
+    let tmpdir = tempdir().unwrap();
+    fs::write(tmpdir.path().join("good.toml"), "a = 1\n").unwrap();
+    fs::write(tmpdir.path().join("bad.json"), "{\"a\": [1, 2}").unwrap();
+    fs::write(tmpdir.path().join("good.yml"), "a: 1\n").unwrap();
+    let r = cfgfifo(tmpdir.path(), &["check", "good.toml", "good.yml"]);
+    assert!(r.status.success(), "{}", stderr(&r));
+    assert_eq!(stderr(&r), "");
+    let r = cfgfifo(
+        tmpdir.path(),
+        &["check", "good.toml", "bad.json", "missing.json", "good.yml"],
+    );
+    assert!(!r.status.success(), "check succeeded");
+    let err = stderr(&r);
+    assert!(
+        err.starts_with(concat!(
+            "cfgfifo: bad.json: failed to parse as JSON: ",
+            "a: expected `,` or `]` at line 1 column 12\n",
+            " --> 1:12\n",
+            "  |\n",
+            "1 | {\"a\": [1, 2}\n",
+            "  |            ^\n",
+            "cfgfifo: failed to read missing.json: ",
+        )),
+        "{err}"
+    );
+    assert_eq!(err.lines().count(), 6, "{err}");
+}
+
+#[test]
+fn check_format_override() {
+    let tmpdir = tempdir().unwrap();
+    fs::write(tmpdir.path().join(".apprc"), "a = 1\n").unwrap();
+    let r = cfgfifo(tmpdir.path(), &["check", ".apprc"]);
+    assert!(!r.status.success(), "check succeeded");
+    let r = cfgfifo(tmpdir.path(), &["check", "--format", "TOML", ".apprc"]);
+    assert!(r.status.success(), "{}", stderr(&r));
+    let r = cfgfifo(tmpdir.path(), &["check", "--format", "xml", ".apprc"]);
+    assert_eq!(r.status.code(), Some(2));
+}
+
+#[test]
+fn fmt() {
+    let tmpdir = tempdir().unwrap();
+    fs::write(tmpdir.path().join("a.json"), "{\"b\": [1,2], \"a\": {}}").unwrap();
+    fs::write(tmpdir.path().join("b.yaml"), "x: 1\n").unwrap();
+    let r = cfgfifo(tmpdir.path(), &["fmt", "--check", "a.json", "b.yaml"]);
+    assert!(!r.status.success(), "fmt --check succeeded");
+    assert_eq!(String::from_utf8(r.stdout).unwrap(), "a.json\n");
+    let r = cfgfifo(tmpdir.path(), &["fmt", "a.json", "b.yaml"]);
+    assert!(r.status.success(), "{}", stderr(&r));
+    assert_eq!(
+        fs::read_to_string(tmpdir.path().join("a.json")).unwrap(),
+        "{\n  \"b\": [\n    1,\n    2\n  ],\n  \"a\": {}\n}\n"
+    );
+    assert_eq!(
+        fs::read_to_string(tmpdir.path().join("b.yaml")).unwrap(),
+        "x: 1\n"
+    );
+    let r = cfgfifo(tmpdir.path(), &["fmt", "--check", "a.json", "b.yaml"]);
+    assert!(r.status.success(), "{}", stderr(&r));
+}
@@ -0,0 +1,461 @@
+//! Support for custom file formats
+use crate::track::{Tracker, depath_tracked};
+use crate::{DeserializeError, Format, PathError, SerializeError};
+use serde::{Deserializer, Serialize, Serializer, de::DeserializeOwned, de::Error as _};
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Error type returned by [`Backend`] methods
+pub type BackendError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// A trait for (de)serializing a file format not built into `cfgfifo`.
+///
+/// Types implementing this trait can be registered with a [`Cfgfifo`]
+/// instance via [`Cfgfifo::backend()`], after which the format's file
+/// extensions will be recognized by [`Cfgfifo::identify()`], and files in the
+/// format can be loaded & dumped with [`Cfgfifo::load()`] and
+/// [`Cfgfifo::dump()`].
+///
+/// As `Backend` is used as a trait object, its methods cannot be generic over
+/// the type being (de)serialized.  Instead, deserialization methods are passed
+/// a [`LoadSink`] to which the backend must hand a
+/// [`serde::Deserializer`] for its input, and serialization methods are passed
+/// a [`DumpSource`], which implements [`serde::Serialize`] and can be given
+/// directly to a serializer.
+///
+/// [`Cfgfifo`]: crate::Cfgfifo
+/// [`Cfgfifo::backend()`]: crate::Cfgfifo::backend
+/// [`Cfgfifo::identify()`]: crate::Cfgfifo::identify
+/// [`Cfgfifo::load()`]: crate::Cfgfifo::load
+/// [`Cfgfifo::dump()`]: crate::Cfgfifo::dump
+#[cfg_attr(feature = "json", doc = concat!(
+    "# Example\n",
+    "\n",
+    "```\n",
+    "use cfgfifo::{Backend, BackendError, DumpSource, LoadSink};\n",
+    "\n",
+    "/// JSON with a different file extension\n",
+    "#[derive(Debug)]\n",
+    "struct Jsonish;\n",
+    "\n",
+    "impl Backend for Jsonish {\n",
+    "    fn name(&self) -> &'static str {\n",
+    "        \"JSONISH\"\n",
+    "    }\n",
+    "\n",
+    "    fn extensions(&self) -> &[&str] {\n",
+    "        &[\"jsonish\"]\n",
+    "    }\n",
+    "\n",
+    "    fn load_from_str(&self, s: &str, sink: LoadSink<'_>) -> Result<(), BackendError> {\n",
+    "        let mut de = serde_json::Deserializer::from_str(s);\n",
+    "        sink.deserialize(&mut de)?;\n",
+    "        de.end()?;\n",
+    "        Ok(())\n",
+    "    }\n",
+    "\n",
+    "    fn dump_to_string(&self, value: DumpSource<'_>) -> Result<String, BackendError> {\n",
+    "        Ok(serde_json::to_string(&value)?)\n",
+    "    }\n",
+    "}\n",
+    "\n",
+    "let cfg = cfgfifo::Cfgfifo::new().backend(Jsonish);\n",
+    "assert_eq!(cfg.identify(\"file.jsonish\").unwrap().name(), \"JSONISH\");\n",
+    "```\n",
+))]
+pub trait Backend: fmt::Debug + Send + Sync {
+    /// Returns the name of the format.
+    ///
+    /// By convention, this is in all-uppercase, like the [`Display`] output
+    /// of [`Format`].
+    ///
+    /// [`Display`]: std::fmt::Display
+    fn name(&self) -> &str;
+
+    /// Returns the recognized file extensions for the format.
+    ///
+    /// Each file extension should be lowercase and should not start with a
+    /// period.
+    fn extensions(&self) -> &[&str];
+
+    /// Deserialize a string in this format by constructing a
+    /// [`serde::Deserializer`] for it and passing the deserializer to
+    /// [`LoadSink::deserialize()`].
+    ///
+    /// # Errors
+    ///
+    /// Implementations should return an error if parsing fails or if
+    /// [`LoadSink::deserialize()`] returns an error.
+    fn load_from_str(&self, s: &str, sink: LoadSink<'_>) -> Result<(), BackendError>;
+
+    /// Deserialize a value in this format from a [reader][std::io::Read] by
+    /// constructing a [`serde::Deserializer`] for it and passing the
+    /// deserializer to [`LoadSink::deserialize()`].
+    ///
+    /// The default implementation reads the reader's contents into a string
+    /// and passes them to [`Backend::load_from_str()`].
+    ///
+    /// # Errors
+    ///
+    /// Implementations should return an error if an I/O error occurs, if
+    /// parsing fails, or if [`LoadSink::deserialize()`] returns an error.
+    fn load_from_reader(
+        &self,
+        reader: &mut dyn io::Read,
+        sink: LoadSink<'_>,
+    ) -> Result<(), BackendError> {
+        let s = io::read_to_string(reader)?;
+        self.load_from_str(&s, sink)
+    }
+
+    /// Serialize a value to a string in this format
+    ///
+    /// # Errors
+    ///
+    /// Implementations should return an error if the underlying serializer
+    /// returns an error.
+    fn dump_to_string(&self, value: DumpSource<'_>) -> Result<String, BackendError>;
+
+    /// Serialize a value to a string in this format, preserving the comments
+    /// & layout of `original` (a previous serialization in this format) where
+    /// possible.
+    ///
+    /// This is used by [`Cfgfifo::update()`][crate::Cfgfifo::update].  The
+    /// default implementation ignores `original` and calls
+    /// [`Backend::dump_to_string()`].
+    ///
+    /// # Errors
+    ///
+    /// Implementations should return an error if the underlying serializer
+    /// returns an error.
+    #[allow(unused_variables)]
+    fn update_string(&self, original: &str, value: DumpSource<'_>) -> Result<String, BackendError> {
+        self.dump_to_string(value)
+    }
+
+    /// Serialize a value to a [writer][std::io::Write] in this format.
+    ///
+    /// The default implementation serializes the value with
+    /// [`Backend::dump_to_string()`] and writes the result to the writer,
+    /// appending a newline if the string does not already end in one.
+    ///
+    /// # Errors
+    ///
+    /// Implementations should return an error if an I/O error occurs or if the
+    /// underlying serializer returns an error.
+    fn dump_to_writer(
+        &self,
+        writer: &mut dyn Write,
+        value: DumpSource<'_>,
+    ) -> Result<(), BackendError> {
+        let s = self.dump_to_string(value)?;
+        writer.write_all(s.as_bytes())?;
+        if !s.ends_with('\n') {
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+type LoadFn<'a> =
+    dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> Result<(), erased_serde::Error> + 'a;
+
+/// A handle passed to the deserialization methods of [`Backend`] that
+/// receives the backend's [`serde::Deserializer`]
+pub struct LoadSink<'a> {
+    inner: &'a mut LoadFn<'a>,
+}
+
+impl LoadSink<'_> {
+    /// Deserialize the value being loaded from the given deserializer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the deserializer or the type being deserialized
+    /// returns an error.
+    pub fn deserialize<'de, D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let mut erased = <dyn erased_serde::Deserializer<'de>>::erase(deserializer);
+        (self.inner)(&mut erased).map_err(D::Error::custom)
+    }
+}
+
+impl fmt::Debug for LoadSink<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadSink").finish_non_exhaustive()
+    }
+}
+
+/// A value to serialize, passed to the serialization methods of [`Backend`]
+///
+/// `DumpSource` implements [`serde::Serialize`] and can be passed to any
+/// serializer.
+#[derive(Clone, Copy)]
+pub struct DumpSource<'a> {
+    inner: &'a dyn erased_serde::Serialize,
+}
+
+impl Serialize for DumpSource<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.inner.serialize(serializer)
+    }
+}
+
+impl fmt::Debug for DumpSource<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DumpSource").finish_non_exhaustive()
+    }
+}
+
+/// An error returned by a custom [`Backend`]
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct CustomError(BackendError);
+
+impl CustomError {
+    /// Returns a reference to the underlying error
+    pub fn get_ref(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        &*self.0
+    }
+
+    /// Consumes the `CustomError` and returns the underlying error
+    pub fn into_inner(self) -> BackendError {
+        self.0
+    }
+}
+
+/// A file format supported by a [`Cfgfifo`][crate::Cfgfifo] instance: either
+/// a built-in [`Format`] or a custom [`Backend`].
+///
+/// An `AnyFormat` can be [displayed][std::fmt::Display] as a string containing
+/// its name.  `AnyFormat`s wrapping custom backends are only equal if they
+/// point to the same [`Arc`]; as [`AnyFormat::custom()`] creates a new `Arc`
+/// on each call, wrapping two equal backends with it produces unequal
+/// `AnyFormat`s.  Clone an `AnyFormat` in order to obtain an equal one.
+#[derive(Clone, Debug)]
+pub enum AnyFormat {
+    /// A built-in format
+    Builtin(Format),
+    /// A custom format
+    Custom(Arc<dyn Backend>),
+}
+
+impl AnyFormat {
+    /// Wrap a custom [`Backend`] in an `AnyFormat`
+    pub fn custom<B: Backend + 'static>(backend: B) -> AnyFormat {
+        AnyFormat::Custom(Arc::new(backend))
+    }
+
+    /// Returns the name of the format
+    pub fn name(&self) -> &str {
+        match self {
+            AnyFormat::Builtin(f) => f.into(),
+            AnyFormat::Custom(b) => b.name(),
+        }
+    }
+
+    /// Returns the recognized file extensions for the format
+    pub fn extensions(&self) -> &[&str] {
+        match self {
+            AnyFormat::Builtin(f) => f.extensions(),
+            AnyFormat::Custom(b) => b.extensions(),
+        }
+    }
+
+    /// Test whether a given file extension is associated with the format
+    ///
+    /// The file extension is matched case-insensitively and may optionally
+    /// start with a period.
+    pub fn has_extension(&self, ext: &str) -> bool {
+        let ext = ext.strip_prefix('.').unwrap_or(ext);
+        self.extensions()
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(ext))
+    }
+
+    /// If this is a built-in format, return it
+    pub fn builtin(&self) -> Option<Format> {
+        match *self {
+            AnyFormat::Builtin(f) => Some(f),
+            AnyFormat::Custom(_) => None,
+        }
+    }
+
+    /// Serialize a value to a string in this format
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying serializer returns an error.
+    pub fn dump_to_string<T: Serialize>(&self, value: &T) -> Result<String, SerializeError> {
+        match self {
+            AnyFormat::Builtin(f) => f.dump_to_string(value),
+            AnyFormat::Custom(b) => b
+                .dump_to_string(DumpSource { inner: value })
+                .map_err(|e| SerializeError::Custom(CustomError(e))),
+        }
+    }
+
+    /// Serialize a value to a string in this format, preserving the comments
+    /// & layout of `original` (a previous serialization in this format) where
+    /// possible.
+    ///
+    /// See [`Format::update_string()`] and [`Backend::update_string()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying serializer returns an error.
+    pub fn update_string<T: Serialize>(
+        &self,
+        original: &str,
+        value: &T,
+    ) -> Result<String, SerializeError> {
+        match self {
+            AnyFormat::Builtin(f) => f.update_string(original, value),
+            AnyFormat::Custom(b) => b
+                .update_string(original, DumpSource { inner: value })
+                .map_err(|e| SerializeError::Custom(CustomError(e))),
+        }
+    }
+
+    /// Deserialize a string in this format
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying deserializer returns an error.
+    pub fn load_from_str<T: DeserializeOwned>(&self, s: &str) -> Result<T, DeserializeError> {
+        self.load_from_str_tracked(s, None)
+    }
+
+    /// Like [`AnyFormat::load_from_str()`], but with a [`Tracker`]
+    pub(crate) fn load_from_str_tracked<T: DeserializeOwned>(
+        &self,
+        s: &str,
+        tracker: Option<&mut Tracker>,
+    ) -> Result<T, DeserializeError> {
+        match self {
+            AnyFormat::Builtin(f) => f.load_from_str_tracked(s, tracker),
+            AnyFormat::Custom(b) => load_custom(|sink| b.load_from_str(s, sink), tracker),
+        }
+    }
+
+    /// Serialize a value to a [writer][std::io::Write] in this format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs or if the underlying serializer
+    /// returns an error.
+    pub fn dump_to_writer<W: Write, T: Serialize>(
+        &self,
+        mut writer: W,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        match self {
+            AnyFormat::Builtin(f) => f.dump_to_writer(writer, value),
+            AnyFormat::Custom(b) => b
+                .dump_to_writer(&mut writer, DumpSource { inner: value })
+                .map_err(|e| SerializeError::Custom(CustomError(e))),
+        }
+    }
+
+    /// Deserialize a value in this format from a [reader][std::io::Read].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs or if the underlying
+    /// deserializer returns an error.
+    pub fn load_from_reader<R: io::Read, T: DeserializeOwned>(
+        &self,
+        reader: R,
+    ) -> Result<T, DeserializeError> {
+        self.load_from_reader_tracked(reader, None)
+    }
+
+    /// Like [`AnyFormat::load_from_reader()`], but with a [`Tracker`]
+    pub(crate) fn load_from_reader_tracked<R: io::Read, T: DeserializeOwned>(
+        &self,
+        mut reader: R,
+        tracker: Option<&mut Tracker>,
+    ) -> Result<T, DeserializeError> {
+        match self {
+            AnyFormat::Builtin(f) => f.load_from_reader_tracked(reader, tracker),
+            AnyFormat::Custom(b) => {
+                load_custom(|sink| b.load_from_reader(&mut reader, sink), tracker)
+            }
+        }
+    }
+}
+
+impl From<Format> for AnyFormat {
+    fn from(value: Format) -> AnyFormat {
+        AnyFormat::Builtin(value)
+    }
+}
+
+impl From<Arc<dyn Backend>> for AnyFormat {
+    fn from(value: Arc<dyn Backend>) -> AnyFormat {
+        AnyFormat::Custom(value)
+    }
+}
+
+impl PartialEq for AnyFormat {
+    fn eq(&self, other: &AnyFormat) -> bool {
+        match (self, other) {
+            (AnyFormat::Builtin(f1), AnyFormat::Builtin(f2)) => f1 == f2,
+            (AnyFormat::Custom(b1), AnyFormat::Custom(b2)) => Arc::ptr_eq(b1, b2),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for AnyFormat {}
+
+impl PartialEq<Format> for AnyFormat {
+    fn eq(&self, other: &Format) -> bool {
+        self.builtin().is_some_and(|f| f == *other)
+    }
+}
+
+impl fmt::Display for AnyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Run a custom backend's deserialization method, collecting the deserialized
+/// value and the path to any deserialization error, and tracking the value
+/// with `tracker`, if given
+fn load_custom<T, F>(func: F, mut tracker: Option<&mut Tracker>) -> Result<T, DeserializeError>
+where
+    T: DeserializeOwned,
+    F: FnOnce(LoadSink<'_>) -> Result<(), BackendError>,
+{
+    let mut value = None;
+    let mut path = None;
+    let mut callback = |de: &mut dyn erased_serde::Deserializer<'_>| match depath_tracked::<_, T>(
+        de,
+        tracker.as_deref_mut(),
+    ) {
+        Ok(v) => {
+            value = Some(v);
+            Ok(())
+        }
+        Err(e) => {
+            path = Some(e.path().clone());
+            Err(e.into_inner())
+        }
+    };
+    let r = func(LoadSink {
+        inner: &mut callback,
+    });
+    let path = path.unwrap_or_else(|| serde_path_to_error::Track::new().path());
+    match (r, value) {
+        (Ok(()), Some(value)) => Ok(value),
+        (Ok(()), None) => Err(PathError::new(
+            path,
+            CustomError("backend did not deserialize a value".into()),
+        )
+        .into()),
+        (Err(e), _) => Err(PathError::new(path, CustomError(e)).into()),
+    }
+}
@@ -0,0 +1,214 @@
+//! Transparent (de)compression of files with compound extensions
+use std::borrow::Cow;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use strum::{Display, EnumIter};
+
+/// An enum of compression formats supported by this build of `cfgfifo`.
+///
+/// Each variant is only present if the corresponding Cargo feature of
+/// `cfgfifo` was enabled at compile time.
+///
+/// When a file path passed to [`Cfgfifo`][crate::Cfgfifo] ends in a file
+/// extension for a supported compression format (e.g., `config.json.gz`), the
+/// file's format is identified from the remainder of the path, and the file is
+/// transparently decompressed on loading and compressed on dumping.
+///
+/// A `Compression` can be [displayed][std::fmt::Display] as a string
+/// containing its name in lowercase.
+#[derive(Clone, Copy, Debug, Display, EnumIter, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[strum(serialize_all = "lowercase")]
+#[non_exhaustive]
+pub enum Compression {
+    /// [gzip](https://www.gzip.org) compression, performed with the
+    /// [`flate2`] crate
+    #[cfg(feature = "gzip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+    Gzip,
+
+    /// [xz](https://tukaani.org/xz/) compression, performed with the
+    /// [`liblzma`] crate
+    #[cfg(feature = "xz")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xz")))]
+    Xz,
+
+    /// [Zstandard](https://facebook.github.io/zstd/) compression, performed
+    /// with the [`zstd`] crate
+    #[cfg(feature = "zstd")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zstd")))]
+    Zstd,
+}
+
+impl Compression {
+    /// Returns an iterator over all [`Compression`] variants
+    pub fn iter() -> CompressionIter {
+        // To avoid the need for users to import the trait
+        <Compression as strum::IntoEnumIterator>::iter()
+    }
+
+    /// Returns an array of the recognized file extensions for the compression
+    /// format.
+    ///
+    /// Each returned file extension is lowercase and does not start with a
+    /// period.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => &["gz"],
+            #[cfg(feature = "xz")]
+            Compression::Xz => &["xz"],
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => &["zst"],
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+    }
+
+    /// Test whether a given file extension is associated with the compression
+    /// format
+    ///
+    /// The file extension is matched case-insensitively and may optionally
+    /// start with a period.
+    pub fn has_extension(&self, ext: &str) -> bool {
+        let ext = ext.strip_prefix('.').unwrap_or(ext);
+        self.extensions()
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(ext))
+    }
+
+    /// Converts a file extension to the corresponding [`Compression`]
+    ///
+    /// File extensions are matched case-insensitively and may optionally start
+    /// with a period.  If the given file extension does not correspond to a
+    /// known compression format, `None` is returned.
+    pub fn from_extension(ext: &str) -> Option<Compression> {
+        Compression::iter().find(|c| c.has_extension(ext))
+    }
+
+    /// Determine the [`Compression`] of a file path based on its file
+    /// extension.
+    ///
+    /// Returns `None` if the path does not end in a known compression
+    /// extension.
+    #[cfg_attr(feature = "gzip", doc = concat!(
+        "# Example\n",
+        "\n",
+        "```\n",
+        "use cfgfifo::Compression;\n",
+        "\n",
+        "assert_eq!(Compression::identify(\"rules.json.gz\"), Some(Compression::Gzip));\n",
+        "assert_eq!(Compression::identify(\"rules.json\"), None);\n",
+        "```\n",
+    ))]
+    pub fn identify<P: AsRef<Path>>(path: P) -> Option<Compression> {
+        path.as_ref()
+            .extension()?
+            .to_str()
+            .and_then(Compression::from_extension)
+    }
+
+    /// Split a file path into its compression format (if any) and the path
+    /// with the compression extension removed
+    pub(crate) fn split(path: &Path) -> (Option<Compression>, Cow<'_, Path>) {
+        match Compression::identify(path) {
+            Some(c) => (Some(c), Cow::Owned(path.with_extension(""))),
+            None => (None, Cow::Borrowed(path)),
+        }
+    }
+
+    /// Wrap a reader in a decompressor for this compression format
+    #[allow(unused_variables)]
+    pub(crate) fn decoder<'a, R: Read + 'a>(&self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Ok(Box::new(flate2::read::MultiGzDecoder::new(reader))),
+            #[cfg(feature = "xz")]
+            Compression::Xz => Ok(Box::new(liblzma::read::XzDecoder::new_multi_decoder(
+                reader,
+            ))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(Box::new(zstd::Decoder::new(reader)?)),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+    }
+
+    /// Wrap a writer in a compressor for this compression format
+    #[allow(unused_variables)]
+    pub(crate) fn encoder<W: Write>(&self, writer: W) -> io::Result<Encoder<W>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Ok(Encoder::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            ))),
+            #[cfg(feature = "xz")]
+            Compression::Xz => Ok(Encoder::Xz(liblzma::write::XzEncoder::new(writer, 6))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(Encoder::Zstd(zstd::Encoder::new(writer, 0)?)),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+    }
+
+    /// Read & decompress the entirety of a reader
+    pub(crate) fn decompress<R: Read>(&self, reader: R) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.decoder(reader)?.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+/// A writer that compresses its input before writing it to an inner writer
+pub(crate) enum Encoder<W: Write> {
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "xz")]
+    Xz(liblzma::write::XzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+    #[allow(dead_code)]
+    Never(std::convert::Infallible, std::marker::PhantomData<W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Finish writing the compressed stream and return the inner writer
+    pub(crate) fn finish(self) -> io::Result<W> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(enc) => enc.finish(),
+            #[cfg(feature = "xz")]
+            Encoder::Xz(enc) => enc.finish(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(enc) => enc.finish(),
+            Encoder::Never(never, _) => match never {},
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    #[allow(unused_variables)]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(enc) => enc.write(buf),
+            #[cfg(feature = "xz")]
+            Encoder::Xz(enc) => enc.write(buf),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(enc) => enc.write(buf),
+            Encoder::Never(..) => unreachable!(),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(enc) => enc.flush(),
+            #[cfg(feature = "xz")]
+            Encoder::Xz(enc) => enc.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(enc) => enc.flush(),
+            Encoder::Never(..) => unreachable!(),
+        }
+    }
+}
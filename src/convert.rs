@@ -0,0 +1,240 @@
+//! Conversions between [`Value`] and the value types of the format backends
+#![allow(unused_imports)]
+use crate::value::{Datetime, Value};
+use serde::Deserialize;
+
+/// Convert a JSON value to a [`Value`].  This conversion is lossless.
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Value {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    Value::Integer(i.into())
+                } else if let Some(u) = n.as_u64() {
+                    Value::Integer(u.into())
+                } else {
+                    n.as_f64()
+                        .map_or_else(|| Value::String(n.to_string()), Value::Float)
+                }
+            }
+            serde_json::Value::String(s) => Value::String(s),
+            serde_json::Value::Array(values) => {
+                Value::Seq(values.into_iter().map(Value::from).collect())
+            }
+            serde_json::Value::Object(values) => Value::Map(
+                values
+                    .into_iter()
+                    .map(|(k, v)| (k, Value::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Convert a [`Value`] to a JSON value the same way that
+/// [`Format::Json`][crate::Format::Json] would serialize it: byte strings
+/// become arrays of integers, and datetimes become strings.
+///
+/// # Errors
+///
+/// Fails if the value contains an integer that does not fit in an `i64` or
+/// `u64`.
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+impl TryFrom<Value> for serde_json::Value {
+    type Error = serde_json::Error;
+
+    fn try_from(value: Value) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(value)
+    }
+}
+
+/// Convert a RON value to a [`Value`].  Characters become strings, `Some`
+/// values are unwrapped, and both units and `None` become [`Value::Null`].
+///
+/// # Errors
+///
+/// Fails if the value contains a map with a key that is not a string, number,
+/// boolean, or character.
+#[cfg(feature = "ron")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ron")))]
+impl TryFrom<ron::Value> for Value {
+    type Error = ron::Error;
+
+    fn try_from(value: ron::Value) -> Result<Value, ron::Error> {
+        Value::deserialize(value)
+    }
+}
+
+/// Convert a [`Value`] to a RON value.  [`Value::Null`] becomes a unit, and
+/// datetimes become strings.
+///
+/// # Errors
+///
+/// Fails if the value contains an integer that does not fit in an `i64` or
+/// `u64`.
+#[cfg(feature = "ron")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ron")))]
+impl TryFrom<Value> for ron::Value {
+    type Error = ron::Error;
+
+    fn try_from(value: Value) -> Result<ron::Value, ron::Error> {
+        Ok(match value {
+            Value::Null => ron::Value::Unit,
+            Value::Bool(b) => ron::Value::Bool(b),
+            Value::Integer(i) => {
+                if let Ok(i) = i64::try_from(i) {
+                    ron::Value::from(i)
+                } else if let Ok(u) = u64::try_from(i) {
+                    ron::Value::from(u)
+                } else {
+                    return Err(serde::ser::Error::custom(format!(
+                        "integer {i} is out of range"
+                    )));
+                }
+            }
+            Value::Float(f) => ron::Value::from(f),
+            Value::String(s) => ron::Value::String(s),
+            Value::Bytes(bs) => ron::Value::Bytes(bs),
+            Value::Seq(values) => ron::Value::Seq(
+                values
+                    .into_iter()
+                    .map(ron::Value::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Map(values) => ron::Value::Map(
+                values
+                    .into_iter()
+                    .map(|(k, v)| Ok((ron::Value::String(k), ron::Value::try_from(v)?)))
+                    .collect::<Result<_, ron::Error>>()?,
+            ),
+            Value::Datetime(dt) => ron::Value::String(dt.into()),
+        })
+    }
+}
+
+/// Convert a TOML value to a [`Value`].  This conversion is lossless.
+#[cfg(feature = "toml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
+impl From<toml::Value> for Value {
+    fn from(value: toml::Value) -> Value {
+        match value {
+            toml::Value::String(s) => Value::String(s),
+            toml::Value::Integer(i) => Value::Integer(i.into()),
+            toml::Value::Float(f) => Value::Float(f),
+            toml::Value::Boolean(b) => Value::Bool(b),
+            toml::Value::Datetime(dt) => Value::Datetime(dt.into()),
+            toml::Value::Array(values) => Value::Seq(values.into_iter().map(Value::from).collect()),
+            toml::Value::Table(values) => Value::Map(
+                values
+                    .into_iter()
+                    .map(|(k, v)| (k, Value::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Convert a [`Value`] to a TOML value.  Byte strings become arrays of
+/// integers.
+///
+/// Note that `toml::Value` has an inherent `try_from()` method that shadows
+/// this impl, so call [`TryInto::try_into()`] on the [`Value`] instead.
+///
+/// # Errors
+///
+/// Fails if the value contains a null, an integer that does not fit in an
+/// `i64`, or a datetime that is not a valid TOML datetime.
+#[cfg(feature = "toml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
+impl TryFrom<Value> for toml::Value {
+    type Error = toml::ser::Error;
+
+    fn try_from(value: Value) -> Result<toml::Value, toml::ser::Error> {
+        use serde::ser::Error;
+        Ok(match value {
+            Value::Null => return Err(toml::ser::Error::custom("unsupported unit type")),
+            Value::Bool(b) => toml::Value::Boolean(b),
+            Value::Integer(i) => {
+                toml::Value::Integer(i64::try_from(i).map_err(|_| {
+                    toml::ser::Error::custom(format!("integer {i} is out of range"))
+                })?)
+            }
+            Value::Float(f) => toml::Value::Float(f),
+            Value::String(s) => toml::Value::String(s),
+            Value::Bytes(bs) => toml::Value::Array(
+                bs.into_iter()
+                    .map(|b| toml::Value::Integer(b.into()))
+                    .collect(),
+            ),
+            Value::Seq(values) => toml::Value::Array(
+                values
+                    .into_iter()
+                    .map(Value::try_into)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Map(values) => toml::Value::Table(
+                values
+                    .into_iter()
+                    .map(|(k, v)| Ok((k, v.try_into()?)))
+                    .collect::<Result<_, toml::ser::Error>>()?,
+            ),
+            Value::Datetime(dt) => toml::Value::Datetime(
+                toml::value::Datetime::try_from(dt).map_err(toml::ser::Error::custom)?,
+            ),
+        })
+    }
+}
+
+#[cfg(feature = "toml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
+impl From<toml::value::Datetime> for Datetime {
+    fn from(value: toml::value::Datetime) -> Datetime {
+        Datetime::new(value.to_string())
+    }
+}
+
+/// Parse a [`Datetime`] as a TOML datetime
+#[cfg(feature = "toml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
+impl TryFrom<Datetime> for toml::value::Datetime {
+    type Error = toml::value::DatetimeParseError;
+
+    fn try_from(value: Datetime) -> Result<toml::value::Datetime, Self::Error> {
+        value.as_str().parse()
+    }
+}
+
+/// Convert a YAML value to a [`Value`].  Tagged values become single-entry
+/// maps from the tag (without the leading `!`) to the value.
+///
+/// # Errors
+///
+/// Fails if the value contains a map with a key that is not a string, number,
+/// or boolean.
+#[cfg(feature = "yaml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "yaml")))]
+impl TryFrom<serde_yaml::Value> for Value {
+    type Error = serde_yaml::Error;
+
+    fn try_from(value: serde_yaml::Value) -> Result<Value, serde_yaml::Error> {
+        Value::deserialize(value)
+    }
+}
+
+/// Convert a [`Value`] to a YAML value.  Byte strings become sequences of
+/// integers, and datetimes and integers that do not fit in an `i64` or `u64`
+/// become strings.
+#[cfg(feature = "yaml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "yaml")))]
+impl TryFrom<Value> for serde_yaml::Value {
+    type Error = serde_yaml::Error;
+
+    fn try_from(value: Value) -> Result<serde_yaml::Value, serde_yaml::Error> {
+        serde_yaml::to_value(value)
+    }
+}
@@ -0,0 +1,473 @@
+//! Discovering configuration files in standard locations & parent directories
+use crate::{Cfgfifo, Layers};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+impl Cfgfifo {
+    /// List the paths at which [`Cfgfifo::discover()`] looks for an
+    /// application's configuration file, most preferred first.
+    ///
+    /// For each of the directories returned by [`config_dirs(app)`][config_dirs],
+    /// in order, the candidates are `{basename}.{ext}` for each file extension
+    /// supported by the `Cfgfifo`: first the extensions of each configured
+    /// format (in the order the formats were configured), then any extensions
+    /// added with [`Cfgfifo::extension()`].
+    pub fn candidate_paths(&self, app: &str, basename: &str) -> Vec<PathBuf> {
+        candidates_in(self, &config_dirs(app), basename)
+            .into_iter()
+            .flat_map(|(_, paths)| paths)
+            .collect()
+    }
+
+    /// Find the existing configuration files for an application in the
+    /// standard configuration directories.
+    ///
+    /// The directories returned by [`config_dirs(app)`][config_dirs] are
+    /// searched for files named `{basename}.{ext}`, where `ext` is any file
+    /// extension supported by the `Cfgfifo` (see
+    /// [`Cfgfifo::candidate_paths()`]).  The files found are returned in
+    /// order of preference, i.e., a file in the current directory comes
+    /// before one in the user's configuration directory, which comes before
+    /// one in the system configuration directory.  To use only the most
+    /// preferred file, take the first path; to merge them all, pass them in
+    /// reverse order to [`Layers`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DiscoverError::Ambiguous`] if more than one candidate file
+    /// exists in the same directory (e.g., both `config.toml` and
+    /// `config.yaml`).
+    pub fn discover(&self, app: &str, basename: &str) -> Result<Vec<PathBuf>, DiscoverError> {
+        self.discover_in(config_dirs(app), basename)
+    }
+
+    /// Find the existing configuration files named `{basename}.{ext}` in the
+    /// given directories, where `ext` is any file extension supported by the
+    /// `Cfgfifo`.
+    ///
+    /// This is the same as [`Cfgfifo::discover()`], except that the given
+    /// directories are searched, in order, instead of the standard ones.
+    #[cfg_attr(all(feature = "json", feature = "toml"), doc = concat!(
+        "# Example\n",
+        "\n",
+        "```\n",
+        "use cfgfifo::Cfgfifo;\n",
+        "\n",
+        "let user = tempfile::tempdir().unwrap();\n",
+        "let system = tempfile::tempdir().unwrap();\n",
+        "std::fs::write(user.path().join(\"config.json\"), \"{}\").unwrap();\n",
+        "std::fs::write(system.path().join(\"config.toml\"), \"\").unwrap();\n",
+        "std::fs::write(system.path().join(\"other.toml\"), \"\").unwrap();\n",
+        "\n",
+        "let found = Cfgfifo::new()\n",
+        "    .discover_in([user.path(), system.path()], \"config\")\n",
+        "    .unwrap();\n",
+        "assert_eq!(\n",
+        "    found,\n",
+        "    [user.path().join(\"config.json\"), system.path().join(\"config.toml\")]\n",
+        ");\n",
+        "```\n",
+    ))]
+    /// # Errors
+    ///
+    /// Returns [`DiscoverError::Ambiguous`] if more than one candidate file
+    /// exists in the same directory.
+    pub fn discover_in<I, P>(&self, dirs: I, basename: &str) -> Result<Vec<PathBuf>, DiscoverError>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        let dirs = dirs.into_iter().map(Into::into).collect::<Vec<_>>();
+        let mut found = Vec::new();
+        for (dir, candidates) in candidates_in(self, &dirs, basename) {
+            let mut existing = candidates
+                .into_iter()
+                .filter(|p| p.is_file())
+                .collect::<Vec<_>>();
+            if existing.len() > 1 {
+                return Err(DiscoverError::Ambiguous {
+                    dir: dir.to_owned(),
+                    paths: existing,
+                });
+            }
+            found.append(&mut existing);
+        }
+        Ok(found)
+    }
+
+    /// Return the file extensions supported by the `Cfgfifo`, without
+    /// duplicates
+    fn supported_extensions(&self) -> Vec<&str> {
+        let mut exts = Vec::new();
+        let all = self
+            .formats
+            .iter()
+            .flat_map(|fmt| fmt.extensions().iter().copied())
+            .chain(self.extensions.iter().map(|(ext, _)| ext.as_str()));
+        for ext in all {
+            if !exts.contains(&ext) {
+                exts.push(ext);
+            }
+        }
+        exts
+    }
+}
+
+/// Return the candidate paths in each directory
+fn candidates_in<'a>(
+    cfgfifo: &Cfgfifo,
+    dirs: &'a [PathBuf],
+    basename: &str,
+) -> Vec<(&'a Path, Vec<PathBuf>)> {
+    let exts = cfgfifo.supported_extensions();
+    dirs.iter()
+        .map(|dir| {
+            let paths = exts
+                .iter()
+                .map(|ext| dir.join(format!("{basename}.{ext}")))
+                .collect();
+            (dir.as_path(), paths)
+        })
+        .collect()
+}
+
+/// Return the directories in which to look for the configuration files of the
+/// application `app`, most preferred first.
+///
+/// The directories are:
+///
+/// - the current directory
+/// - `$XDG_CONFIG_HOME/{app}`, if `XDG_CONFIG_HOME` is set to an absolute path
+/// - `~/.config/{app}`
+/// - `/etc/{app}` (on Unix only)
+///
+/// Directories that cannot be determined (e.g., because the home directory is
+/// unknown) are omitted, as are duplicates.  The directories are not checked
+/// for existence.
+pub fn config_dirs(app: &str) -> Vec<PathBuf> {
+    dirs_from(
+        app,
+        std::env::current_dir().ok(),
+        std::env::var_os("XDG_CONFIG_HOME"),
+        std::env::home_dir(),
+    )
+}
+
+fn dirs_from(
+    app: &str,
+    cwd: Option<PathBuf>,
+    xdg_config_home: Option<OsString>,
+    home: Option<PathBuf>,
+) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    dirs.extend(cwd);
+    if let Some(xdg) = xdg_config_home.map(PathBuf::from)
+        && xdg.is_absolute()
+    {
+        dirs.push(xdg.join(app));
+    }
+    if let Some(home) = home.filter(|p| !p.as_os_str().is_empty()) {
+        dirs.push(home.join(".config").join(app));
+    }
+    if cfg!(unix) {
+        dirs.push(Path::new("/etc").join(app));
+    }
+    let mut seen = Vec::with_capacity(dirs.len());
+    dirs.retain(|d| {
+        if seen.contains(d) {
+            false
+        } else {
+            seen.push(d.clone());
+            true
+        }
+    });
+    dirs
+}
+
+/// A search for configuration files in a directory and its ancestors, as used
+/// by tools that look for project configuration files like `.apprc.toml`
+/// starting from the current directory.
+///
+/// Starting from a given directory, each directory up to the root of the
+/// filesystem is searched for a file named `{basename}.{ext}`, where `ext` is
+/// any file extension supported by the configured [`Cfgfifo`] (see
+/// [`Cfgfifo::discover_in()`]).  The search can be made to stop earlier by
+/// setting one or more markers: the search stops after the first directory
+/// that contains an entry with a marker's name, such as `.git` for the root
+/// of a Git repository.
+///
+/// As with [`Cfgfifo`], custom backends are compared by pointer, so two
+/// `WalkUp`s with custom backends are only equal if the backends were created
+/// by the same call to [`AnyFormat::custom()`][crate::AnyFormat::custom].
+#[cfg_attr(all(feature = "json", feature = "toml"), doc = concat!(
+    "# Example\n",
+    "\n",
+    "```\n",
+    "use cfgfifo::WalkUp;\n",
+    "use serde::Deserialize;\n",
+    "use std::fs;\n",
+    "\n",
+    "#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]\n",
+    "struct Config {\n",
+    "    name: String,\n",
+    "    debug: bool,\n",
+    "}\n",
+    "\n",
+    "let tmpdir = tempfile::tempdir().unwrap();\n",
+    "let repo = tmpdir.path().join(\"repo\");\n",
+    "let subdir = repo.join(\"src\");\n",
+    "fs::create_dir_all(repo.join(\".git\")).unwrap();\n",
+    "fs::create_dir_all(&subdir).unwrap();\n",
+    "// Outside the repository, so not found:\n",
+    "fs::write(tmpdir.path().join(\".apprc.toml\"), \"name = 'outer'\\n\").unwrap();\n",
+    "fs::write(repo.join(\".apprc.toml\"), \"name = 'repo'\\ndebug = false\\n\").unwrap();\n",
+    "fs::write(subdir.join(\".apprc.json\"), r#\"{\"debug\": true}\"#).unwrap();\n",
+    "\n",
+    "let walk = WalkUp::new(\".apprc\").marker(\".git\");\n",
+    "let found = walk.search(&subdir).unwrap();\n",
+    "assert_eq!(found.len(), 2);\n",
+    "assert!(found[0].ends_with(\"src/.apprc.json\"));\n",
+    "assert!(found[1].ends_with(\"repo/.apprc.toml\"));\n",
+    "\n",
+    "let cfg: Config = walk.layers(&subdir).unwrap().load().unwrap();\n",
+    "assert_eq!(\n",
+    "    cfg,\n",
+    "    Config {\n",
+    "        name: String::from(\"repo\"),\n",
+    "        debug: true,\n",
+    "    }\n",
+    ");\n",
+    "```\n",
+))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WalkUp {
+    cfgfifo: Cfgfifo,
+    basename: String,
+    markers: Vec<OsString>,
+}
+
+impl WalkUp {
+    /// Create a new `WalkUp` that searches for files with the given base name
+    /// using the default [`Cfgfifo`] and no markers
+    pub fn new<S: Into<String>>(basename: S) -> WalkUp {
+        WalkUp {
+            cfgfifo: Cfgfifo::default(),
+            basename: basename.into(),
+            markers: Vec::new(),
+        }
+    }
+
+    /// Set the [`Cfgfifo`] whose supported file extensions are searched for
+    /// and which is used to load the files found by [`WalkUp::layers()`]
+    pub fn cfgfifo(mut self, cfgfifo: Cfgfifo) -> Self {
+        self.cfgfifo = cfgfifo;
+        self
+    }
+
+    /// Add a marker: the search stops after searching a directory that
+    /// contains a file or directory with the given name
+    pub fn marker<S: Into<OsString>>(mut self, name: S) -> Self {
+        self.markers.push(name.into());
+        self
+    }
+
+    /// Search `start` and its ancestors for configuration files, returning
+    /// the files found, nearest first.
+    ///
+    /// `start` is canonicalized before searching, and so the returned paths
+    /// are absolute.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `start` cannot be canonicalized or if more than
+    /// one candidate file exists in the same directory.
+    pub fn search<P: AsRef<Path>>(&self, start: P) -> Result<Vec<PathBuf>, DiscoverError> {
+        let start = start.as_ref();
+        let start = start
+            .canonicalize()
+            .map_err(|source| DiscoverError::Start {
+                path: start.to_owned(),
+                source,
+            })?;
+        let mut dirs = Vec::new();
+        for dir in start.ancestors() {
+            dirs.push(dir);
+            if self
+                .markers
+                .iter()
+                .any(|m| fs::symlink_metadata(dir.join(m)).is_ok())
+            {
+                break;
+            }
+        }
+        self.cfgfifo.discover_in(dirs, &self.basename)
+    }
+
+    /// Search `start` and its ancestors for configuration files as with
+    /// [`WalkUp::search()`], and return a [`Layers`] that loads the files
+    /// found, with nearer files taking precedence over farther ones
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same circumstances as [`WalkUp::search()`].
+    pub fn layers<P: AsRef<Path>>(&self, start: P) -> Result<Layers, DiscoverError> {
+        let found = self.search(start)?;
+        Ok(found
+            .into_iter()
+            .rev()
+            .fold(Layers::new().cfgfifo(self.cfgfifo.clone()), Layers::file))
+    }
+}
+
+/// Error type returned by [`Cfgfifo::discover()`], [`Cfgfifo::discover_in()`],
+/// and [`WalkUp`]'s methods
+#[derive(Debug, Error)]
+pub enum DiscoverError {
+    /// Returned if more than one candidate configuration file exists in the
+    /// same directory
+    #[error("multiple configuration files found in {}: {}", dir.display(), DisplayPaths(paths))]
+    Ambiguous {
+        /// The directory containing the files
+        dir: PathBuf,
+        /// The paths to the files, in order of file extension preference
+        paths: Vec<PathBuf>,
+    },
+
+    /// Returned if the starting directory of a [`WalkUp`] search could not
+    /// be canonicalized
+    #[error("failed to resolve starting directory {}", path.display())]
+    Start {
+        /// The starting directory
+        path: PathBuf,
+        /// The error that occurred
+        source: io::Error,
+    },
+}
+
+/// Helper for displaying the paths in [`DiscoverError::Ambiguous`]
+struct DisplayPaths<'a>(&'a [PathBuf]);
+
+impl std::fmt::Display for DisplayPaths<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, p) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", p.display())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn dirs() {
+        assert_eq!(
+            dirs_from(
+                "app",
+                Some(PathBuf::from("/work")),
+                Some(OsString::from("/xdg")),
+                Some(PathBuf::from("/home/me")),
+            ),
+            [
+                PathBuf::from("/work"),
+                PathBuf::from("/xdg/app"),
+                PathBuf::from("/home/me/.config/app"),
+                PathBuf::from("/etc/app"),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dirs_dedup_and_missing() {
+        assert_eq!(
+            dirs_from(
+                "app",
+                None,
+                Some(OsString::from("/home/me/.config")),
+                Some(PathBuf::from("/home/me")),
+            ),
+            [
+                PathBuf::from("/home/me/.config/app"),
+                PathBuf::from("/etc/app")
+            ]
+        );
+        assert_eq!(
+            dirs_from("app", None, Some(OsString::from("relative")), None),
+            [PathBuf::from("/etc/app")]
+        );
+    }
+
+    #[cfg(all(feature = "json", feature = "yaml"))]
+    #[test]
+    fn candidates() {
+        let cfg = Cfgfifo::new()
+            .formats([crate::Format::Yaml, crate::Format::Json])
+            .extension("yml", crate::Format::Yaml)
+            .extension("conf", crate::Format::Json);
+        let dirs = [PathBuf::from("a"), PathBuf::from("b")];
+        let paths = candidates_in(&cfg, &dirs, "config")
+            .into_iter()
+            .flat_map(|(_, paths)| paths)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "a/config.yaml",
+                "a/config.yml",
+                "a/config.json",
+                "a/config.conf",
+                "b/config.yaml",
+                "b/config.yml",
+                "b/config.json",
+                "b/config.conf",
+            ]
+            .map(PathBuf::from)
+        );
+    }
+
+    #[cfg(all(feature = "json", feature = "toml"))]
+    #[test]
+    fn ambiguous() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        fs::write(tmpdir.path().join("config.json"), "{}").unwrap();
+        fs::write(tmpdir.path().join("config.toml"), "").unwrap();
+        let cfg = Cfgfifo::new().formats([crate::Format::Json, crate::Format::Toml]);
+        let r = cfg.discover_in([tmpdir.path()], "config");
+        let Err(DiscoverError::Ambiguous { dir, paths }) = r else {
+            panic!("discover_in() did not fail with Ambiguous error: {r:?}");
+        };
+        assert_eq!(dir, tmpdir.path());
+        assert_eq!(
+            paths,
+            [
+                tmpdir.path().join("config.json"),
+                tmpdir.path().join("config.toml")
+            ]
+        );
+        let cfg = Cfgfifo::new().formats([crate::Format::Json]);
+        assert_eq!(
+            cfg.discover_in([tmpdir.path()], "config").unwrap(),
+            [tmpdir.path().join("config.json")]
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn directories_not_files() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        fs::create_dir(tmpdir.path().join("config.toml")).unwrap();
+        let found = Cfgfifo::new()
+            .discover_in([tmpdir.path()], "config")
+            .unwrap();
+        assert!(found.is_empty());
+    }
+}
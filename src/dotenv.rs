@@ -0,0 +1,449 @@
+//! Support for the dotenv (`.env`) format
+use crate::backend::{Backend, BackendError, DumpSource, LoadSink};
+use crate::keyvalue::{Entry, Node, NodeDeserializer, Shape, flatten};
+use crate::locate::{LocatedError, Location};
+use crate::track::{Tracker, depath_tracked};
+use crate::{DeserializeError, SerializeError};
+use indexmap::IndexMap;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_path_to_error::{Error as PathError, Track};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Options for (de)serializing dotenv (`.env`) files.
+///
+/// [`Format::Dotenv`][crate::Format::Dotenv] uses the default options.  To
+/// load & dump dotenv files with different options, call
+/// [`DotenvOptions::load_from_str()`] and [`DotenvOptions::dump_to_string()`]
+/// directly, or register the options with a [`Cfgfifo`][crate::Cfgfifo] as a
+/// custom [`Backend`].
+///
+/// # Syntax
+///
+/// A dotenv document consists of `KEY=value` lines, each optionally preceded
+/// by `export`; whitespace is permitted around the `=`.  Keys may contain
+/// ASCII letters, digits, underscores, periods, and hyphens.  Lines starting
+/// with `#` are comments, and blank lines are ignored.  If a key occurs more
+/// than once, the last value is used.
+///
+/// Values may be written in one of three ways:
+///
+/// - Unquoted values extend to the end of the line or to a `#` preceded by
+///   whitespace, which starts a comment.  Surrounding whitespace is trimmed.
+///
+/// - Single-quoted values (`'...'`) are used verbatim and may span multiple
+///   lines.
+///
+/// - Double-quoted values (`"..."`) may span multiple lines and support the
+///   escape sequences `\n`, `\r`, `\t`, `\"`, `\\`, and `\$`.
+///
+/// Unless disabled with [`DotenvOptions::expand()`], `${VAR}` in unquoted &
+/// double-quoted values is replaced with the value of `VAR`, which is looked
+/// up first among the keys defined earlier in the document and then in the
+/// process's environment.  `${VAR:-default}` uses `default` if `VAR` is
+/// unset or empty; otherwise, unset variables expand to the empty string.
+///
+/// # Deserialization
+///
+/// A document is deserialized as a flat map from keys to values.  Keys are
+/// used verbatim, so structs whose fields are named in lowercase will usually
+/// want `#[serde(rename_all = "SCREAMING_SNAKE_CASE")]`.
+///
+/// Values are strings, but they are coerced to whatever type is requested:
+/// numbers are parsed, booleans may be written as `true`/`false`,
+/// `yes`/`no`, `on`/`off`, or `1`/`0` (case-insensitively), empty values
+/// deserialize as `None`, and sequences are split on commas.  When
+/// deserializing a self-describing type like [`Value`][crate::Value], values
+/// that look like booleans & numbers are parsed as such.
+///
+/// # Serialization
+///
+/// Only maps & structs whose values are scalars or sequences of scalars can
+/// be serialized as dotenv documents.  Each entry is written as a `KEY=value`
+/// line, with the value double-quoted if it contains anything other than
+/// letters, digits, and `_-./:,+@%`.  Sequences of scalars are written as
+/// comma-separated values, `None` and unit values are omitted, and enums are
+/// written as their variant names.
+#[cfg_attr(feature = "dotenv", doc = concat!(
+    "# Example\n",
+    "\n",
+    "```\n",
+    "use cfgfifo::DotenvOptions;\n",
+    "use serde::Deserialize;\n",
+    "\n",
+    "#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]\n",
+    "#[serde(rename_all = \"SCREAMING_SNAKE_CASE\")]\n",
+    "struct Config {\n",
+    "    host: String,\n",
+    "    url: String,\n",
+    "}\n",
+    "\n",
+    "let s = \"HOST=localhost\\nURL=http://${HOST}:8080\\n\";\n",
+    "\n",
+    "let cfg: Config = DotenvOptions::new().load_from_str(s).unwrap();\n",
+    "assert_eq!(cfg.url, \"http://localhost:8080\");\n",
+    "\n",
+    "let cfg: Config = DotenvOptions::new().expand(false).load_from_str(s).unwrap();\n",
+    "assert_eq!(cfg.url, \"http://${HOST}:8080\");\n",
+    "```\n",
+))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DotenvOptions {
+    expand: bool,
+}
+
+impl DotenvOptions {
+    /// Create a new `DotenvOptions` with the default settings
+    pub fn new() -> DotenvOptions {
+        DotenvOptions { expand: true }
+    }
+
+    /// Enable or disable expanding `${VAR}` references in unquoted &
+    /// double-quoted values when deserializing.
+    ///
+    /// When disabled, such references are kept verbatim.  Expansion is
+    /// enabled by default.
+    pub fn expand(mut self, expand: bool) -> Self {
+        self.expand = expand;
+        self
+    }
+
+    /// Deserialize a dotenv string with these options
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the string is not valid dotenv syntax or if
+    /// deserializing the value fails.
+    pub fn load_from_str<T: DeserializeOwned>(&self, s: &str) -> Result<T, DeserializeError> {
+        self.load_from_str_tracked(s, None)
+    }
+
+    /// Like [`DotenvOptions::load_from_str()`], but with a [`Tracker`]
+    pub(crate) fn load_from_str_tracked<T: DeserializeOwned>(
+        &self,
+        s: &str,
+        tracker: Option<&mut Tracker>,
+    ) -> Result<T, DeserializeError> {
+        let de = self.deserializer(s).map_err(DeserializeError::Dotenv)?;
+        depath_tracked(de, tracker).map_err(DeserializeError::Dotenv)
+    }
+
+    /// Parse a dotenv string and return a deserializer for it
+    pub(crate) fn deserializer(
+        &self,
+        s: &str,
+    ) -> Result<NodeDeserializer<DotenvError>, PathError<DotenvError>> {
+        let node = Parser::new(s, self.expand)
+            .parse()
+            .map_err(|e| PathError::new(Track::new().path(), e))?;
+        Ok(NodeDeserializer::new(node, true))
+    }
+
+    /// Serialize a value to a dotenv string with these options
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value cannot be represented in dotenv syntax.
+    pub fn dump_to_string<T: Serialize>(&self, value: &T) -> Result<String, SerializeError> {
+        self.dump(value).map_err(SerializeError::Dotenv)
+    }
+
+    fn dump<T: Serialize>(&self, value: &T) -> Result<String, PathError<DotenvError>> {
+        let shape = Shape {
+            max_depth: 1,
+            root_scalars: true,
+            multiline: true,
+            indexed_seqs: false,
+            check_key,
+        };
+        flatten(value, &shape).map(render)
+    }
+}
+
+impl Default for DotenvOptions {
+    /// Same as [`DotenvOptions::new()`]
+    fn default() -> DotenvOptions {
+        DotenvOptions::new()
+    }
+}
+
+impl Backend for DotenvOptions {
+    fn name(&self) -> &'static str {
+        "DOTENV"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["env"]
+    }
+
+    fn load_from_str(&self, s: &str, sink: LoadSink<'_>) -> Result<(), BackendError> {
+        let de = self.deserializer(s).map_err(PathError::into_inner)?;
+        sink.deserialize(de)?;
+        Ok(())
+    }
+
+    fn dump_to_string(&self, value: DumpSource<'_>) -> Result<String, BackendError> {
+        Ok(self.dump(&value)?)
+    }
+}
+
+/// Error type for dotenv (de)serialization; an alias of [`LocatedError`]
+pub type DotenvError = LocatedError;
+
+fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-')
+}
+
+/// Returns whether `c` is whitespace within a line
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r')
+}
+
+/// A parser for dotenv documents that tracks the location of each character
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+    expand: bool,
+    entries: IndexMap<String, Node>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str, expand: bool) -> Parser<'a> {
+        let s = s.strip_prefix('\u{FEFF}').unwrap_or(s);
+        Parser {
+            chars: s.chars().peekable(),
+            line: 1,
+            column: 1,
+            expand,
+            entries: IndexMap::new(),
+        }
+    }
+
+    fn location(&self) -> Location {
+        Location::new(self.line, self.column)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(is_space) {
+            self.next();
+        }
+    }
+
+    /// Skip the rest of the current line, including the line break
+    fn skip_line(&mut self) {
+        while self.next().is_some_and(|c| c != '\n') {}
+    }
+
+    fn parse(mut self) -> Result<Node, DotenvError> {
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                None => break,
+                Some('\n' | '#') => self.skip_line(),
+                Some(_) => self.parse_entry()?,
+            }
+        }
+        Ok(Node::Map(self.entries, None))
+    }
+
+    fn parse_key(&mut self) -> Result<String, DotenvError> {
+        let loc = self.location();
+        let mut key = String::new();
+        while let Some(c) = self.peek().filter(|&c| is_key_char(c)) {
+            key.push(c);
+            self.next();
+        }
+        if key.is_empty() {
+            Err(DotenvError::new("expected a key", loc))
+        } else {
+            Ok(key)
+        }
+    }
+
+    fn parse_entry(&mut self) -> Result<(), DotenvError> {
+        let mut key = self.parse_key()?;
+        if key == "export" && self.peek().is_some_and(is_space) {
+            self.skip_spaces();
+            key = self.parse_key()?;
+        }
+        self.skip_spaces();
+        if self.peek() != Some('=') {
+            return Err(DotenvError::new("expected `=` after key", self.location()));
+        }
+        self.next();
+        let spaced = self.peek().is_some_and(is_space);
+        self.skip_spaces();
+        let loc = self.location();
+        let value = match self.peek() {
+            Some(quote @ ('\'' | '"')) => {
+                self.next();
+                let value = self.parse_quoted(quote, loc)?;
+                self.skip_spaces();
+                match self.peek() {
+                    None | Some('\n' | '#') => self.skip_line(),
+                    Some(_) => {
+                        return Err(DotenvError::new(
+                            "unexpected characters after quoted value",
+                            self.location(),
+                        ));
+                    }
+                }
+                value
+            }
+            _ => self.parse_unquoted(spaced)?,
+        };
+        self.entries.insert(key, Node::Scalar(value, loc));
+        Ok(())
+    }
+
+    /// Parse the rest of a value that started with `quote` at `start`
+    fn parse_quoted(&mut self, quote: char, start: Location) -> Result<String, DotenvError> {
+        let mut value = String::new();
+        loop {
+            let loc = self.location();
+            match self.next() {
+                None => return Err(DotenvError::new("unterminated quoted value", start)),
+                Some(c) if c == quote => return Ok(value),
+                Some('\\') if quote == '"' => match self.next() {
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some('t') => value.push('\t'),
+                    Some(c @ ('"' | '\\' | '$')) => value.push(c),
+                    Some(c) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => return Err(DotenvError::new("unterminated quoted value", start)),
+                },
+                Some('$') if quote == '"' && self.expand && self.peek() == Some('{') => {
+                    value.push_str(&self.parse_reference(loc)?);
+                }
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    /// Parse an unquoted value.  `spaced` is true if the value was preceded
+    /// by whitespace, in which case a leading `#` starts a comment.
+    fn parse_unquoted(&mut self, spaced: bool) -> Result<String, DotenvError> {
+        let mut value = String::new();
+        loop {
+            let loc = self.location();
+            match self.peek() {
+                None | Some('\n') => break,
+                Some('#') if value.ends_with(is_space) || (value.is_empty() && spaced) => break,
+                Some('$') if self.expand => {
+                    self.next();
+                    if self.peek() == Some('{') {
+                        value.push_str(&self.parse_reference(loc)?);
+                    } else {
+                        value.push('$');
+                    }
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.next();
+                }
+            }
+        }
+        self.skip_line();
+        value.truncate(value.trim_end_matches(is_space).len());
+        Ok(value)
+    }
+
+    /// Parse the rest of a `${VAR}` reference whose `$` was at `start` and
+    /// return its expansion
+    fn parse_reference(&mut self, start: Location) -> Result<String, DotenvError> {
+        self.next();
+        let mut inner = String::new();
+        loop {
+            match self.next() {
+                Some('}') => break,
+                None | Some('\n') => {
+                    return Err(DotenvError::new("unterminated variable reference", start));
+                }
+                Some(c) => inner.push(c),
+            }
+        }
+        let (name, default) = match inner.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (inner.as_str(), None),
+        };
+        if name.is_empty() || !name.chars().all(is_key_char) {
+            return Err(DotenvError::new(
+                format!("invalid variable name {name:?}"),
+                start,
+            ));
+        }
+        let value = match self.entries.get(name) {
+            Some(Node::Scalar(value, _)) => Some(value.clone()),
+            _ => std::env::var(name).ok(),
+        };
+        Ok(value
+            .filter(|v| !v.is_empty())
+            .or_else(|| default.map(String::from))
+            .unwrap_or_default())
+    }
+}
+
+/// Check whether `key` can be used as a dotenv key
+fn check_key(key: &str, _depth: usize) -> Result<(), String> {
+    if key.is_empty() {
+        Err(String::from("keys cannot be empty"))
+    } else if !key.chars().all(is_key_char) {
+        Err(format!("key {key:?} is not a valid dotenv key"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Render flattened entries as a dotenv document
+fn render(entries: Vec<Entry>) -> String {
+    let mut out = String::new();
+    for Entry { path, value } in entries {
+        let Some(value) = value else {
+            continue;
+        };
+        out.push_str(&path.join("."));
+        out.push('=');
+        if value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./:,+@%".contains(c))
+        {
+            out.push_str(&value);
+        } else {
+            out.push('"');
+            for c in value.chars() {
+                match c {
+                    '\n' => out.push_str("\\n"),
+                    '\r' => out.push_str("\\r"),
+                    '\t' => out.push_str("\\t"),
+                    '"' | '\\' | '$' => {
+                        out.push('\\');
+                        out.push(c);
+                    }
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+        }
+        out.push('\n');
+    }
+    out
+}
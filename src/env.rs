@@ -0,0 +1,685 @@
+//! Overlaying environment variables on top of loaded configuration
+use crate::locate::{Segment, display_path};
+use crate::scalar::ScalarDeserializer;
+use crate::value::{KeyDeserializer, Map, Value, ValueError};
+use serde::de::{self, Deserializer, IntoDeserializer, Visitor};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use thiserror::Error;
+
+/// A source of configuration values read from environment variables.
+///
+/// Each environment variable whose name starts with the configured prefix is
+/// mapped to a value in the configuration tree by stripping the prefix,
+/// splitting the remainder of the name on the configured separator (`__` by
+/// default), and lowercasing each resulting segment.  For example, with a
+/// prefix of `APP_`, the variable `APP_SERVER__PORT` sets the `port` field of
+/// the `server` table.  Variables whose names do not start with the prefix,
+/// or whose names produce an empty segment, are ignored.
+///
+/// Each variable's value is kept as a string and converted to whatever type
+/// is requested when deserializing: numbers are parsed, booleans may be
+/// written as `true`/`false`, `yes`/`no`, `on`/`off`, or `1`/`0`
+/// (case-insensitively), empty values deserialize as `None`, and values
+/// deserialized as sequences are split on commas.  Thus, `APP_NAME=123` can
+/// set a string field while `APP_PORT=8080` sets an integer field.  When
+/// deserializing a self-describing type like [`Value`], values that look like
+/// booleans & numbers are parsed as such unless disabled with
+/// [`EnvSource::parse_values()`].  If a list separator is set with
+/// [`EnvSource::list_separator()`], values containing the separator are
+/// additionally split on it into lists of values.
+///
+/// An `EnvSource` can be overlaid on top of the files loaded by a [`Cfgfifo`]
+/// via [`Cfgfifo::env()`] or added as a layer to a [`Layers`] via
+/// [`Layers::env()`].
+///
+/// [`Cfgfifo`]: crate::Cfgfifo
+/// [`Cfgfifo::env()`]: crate::Cfgfifo::env
+/// [`Layers`]: crate::Layers
+/// [`Layers::env()`]: crate::Layers::env
+#[cfg_attr(feature = "toml", doc = concat!(
+    "# Example\n",
+    "\n",
+    "```\n",
+    "use cfgfifo::{Cfgfifo, EnvSource};\n",
+    "use serde::Deserialize;\n",
+    "\n",
+    "#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]\n",
+    "struct Config {\n",
+    "    name: String,\n",
+    "    server: Server,\n",
+    "}\n",
+    "\n",
+    "#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]\n",
+    "struct Server {\n",
+    "    host: String,\n",
+    "    port: u16,\n",
+    "}\n",
+    "\n",
+    "let tmpdir = tempfile::tempdir().unwrap();\n",
+    "let path = tmpdir.path().join(\"config.toml\");\n",
+    "std::fs::write(\n",
+    "    &path,\n",
+    "    \"name = 'app'\\n\\n[server]\\nhost = 'localhost'\\nport = 8080\\n\",\n",
+    ").unwrap();\n",
+    "\n",
+    "// `vars()` is used here in place of the process's actual environment\n",
+    "let env = EnvSource::new(\"APP_\").vars([(\"APP_SERVER__PORT\", \"9000\")]);\n",
+    "let cfg: Config = Cfgfifo::new().env(env).load(&path).unwrap();\n",
+    "assert_eq!(cfg.server.port, 9000);\n",
+    "assert_eq!(cfg.server.host, \"localhost\");\n",
+    "```\n",
+))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnvSource {
+    prefix: String,
+    separator: String,
+    list_separator: Option<String>,
+    parse_values: bool,
+    vars: Option<Vec<(OsString, OsString)>>,
+}
+
+impl EnvSource {
+    /// Create a new `EnvSource` that reads the environment variables whose
+    /// names start with `prefix`
+    pub fn new<S: Into<String>>(prefix: S) -> EnvSource {
+        EnvSource {
+            prefix: prefix.into(),
+            separator: String::from("__"),
+            list_separator: None,
+            parse_values: true,
+            vars: None,
+        }
+    }
+
+    /// Set the separator between the segments of a variable's name that
+    /// denote nested keys.
+    ///
+    /// The default separator is `__`.
+    pub fn separator<S: Into<String>>(mut self, separator: S) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Set a separator on which to split variables' values into lists.
+    ///
+    /// Each element of a list is trimmed of surrounding whitespace and then
+    /// converted like any other value.  By default, values are not split.
+    pub fn list_separator<S: Into<String>>(mut self, separator: S) -> Self {
+        self.list_separator = Some(separator.into());
+        self
+    }
+
+    /// Enable or disable parsing values as booleans & numbers when they are
+    /// deserialized as any type, as when deserializing a [`Value`].
+    ///
+    /// When disabled, such values are strings.  Values deserialized as
+    /// specific types are converted to those types either way.  Parsing is
+    /// enabled by default.
+    pub fn parse_values(mut self, parse: bool) -> Self {
+        self.parse_values = parse;
+        self
+    }
+
+    /// Read variables from the given name-value pairs instead of from the
+    /// environment of the current process
+    pub fn vars<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<OsString>,
+        V: Into<OsString>,
+    {
+        self.vars = Some(
+            vars.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Read the matching environment variables into a tree of values
+    pub(crate) fn load(&self) -> Result<EnvTree, EnvError> {
+        let mut vars = match &self.vars {
+            Some(vars) => vars.clone(),
+            None => std::env::vars_os().collect(),
+        };
+        // Sort so that the results are deterministic and so that variables
+        // for nested keys are applied after variables for their parents
+        vars.sort();
+        let mut tree = EnvTree {
+            value: Value::Map(Map::new()),
+            vars: Vec::new(),
+            strings: Vec::new(),
+            parse_values: self.parse_values,
+        };
+        for (name, value) in vars {
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            let Some(path) = self.key_path(name) else {
+                continue;
+            };
+            let Ok(value) = value.into_string() else {
+                return Err(EnvError::NotUnicode {
+                    var: name.to_owned(),
+                });
+            };
+            let value = self.split(&value);
+            if let Value::Seq(values) = &value {
+                for i in 0..values.len() {
+                    tree.strings
+                        .push([&path[..], &[Segment::Index(i)]].concat());
+                }
+            } else {
+                tree.strings.push(path.clone());
+            }
+            insert(&mut tree.value, &path, value);
+            tree.vars.push((display_path(&path), name.to_owned()));
+        }
+        Ok(tree)
+    }
+
+    /// Convert a variable name to a path of keys, or return `None` if the
+    /// variable should be ignored
+    fn key_path(&self, name: &str) -> Option<Vec<Segment>> {
+        let rest = name.strip_prefix(&self.prefix)?;
+        if rest.is_empty() {
+            return None;
+        }
+        let mut path = Vec::new();
+        for segment in rest.split(&*self.separator) {
+            if segment.is_empty() {
+                return None;
+            }
+            path.push(Segment::Key(segment.to_lowercase()));
+        }
+        Some(path)
+    }
+
+    /// Split a value into a list of strings if it contains the list
+    /// separator, and otherwise return it as a string
+    fn split(&self, value: &str) -> Value {
+        match &self.list_separator {
+            Some(sep) if value.contains(&**sep) => Value::Seq(
+                value
+                    .split(&**sep)
+                    .map(|elem| Value::from(elem.trim()))
+                    .collect(),
+            ),
+            _ => Value::from(value),
+        }
+    }
+}
+
+/// Set the value at `path` within `tree` to `value`, replacing any non-map
+/// values along the way with maps
+fn insert(tree: &mut Value, path: &[Segment], value: Value) {
+    let Some((Segment::Key(key), rest)) = path.split_first() else {
+        *tree = value;
+        return;
+    };
+    if !matches!(tree, Value::Map(_)) {
+        *tree = Value::Map(Map::new());
+    }
+    let Value::Map(map) = tree else {
+        unreachable!("tree should have just been made a map");
+    };
+    insert(map.entry(key.clone()).or_default(), rest, value);
+}
+
+/// The values read from environment variables by an [`EnvSource`]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct EnvTree {
+    /// The tree of values
+    pub(crate) value: Value,
+
+    /// The displayed path set by each variable, paired with the variable's
+    /// name
+    pub(crate) vars: Vec<(String, String)>,
+
+    /// The paths of the strings set by the variables, i.e., of each
+    /// variable's value or, for values split into lists, of each element
+    pub(crate) strings: Vec<Vec<Segment>>,
+
+    /// Whether the strings are parsed when deserialized as any type
+    pub(crate) parse_values: bool,
+}
+
+impl EnvTree {
+    /// Merge the values on top of `tree`: maps are merged recursively, and
+    /// all other values are replaced
+    pub(crate) fn overlay(&self, tree: &mut Value) {
+        fn merge(lower: &mut Value, upper: &Value) {
+            match (lower, upper) {
+                (Value::Map(lower), Value::Map(upper)) => {
+                    for (key, value) in upper {
+                        merge(lower.entry(key.clone()).or_default(), value);
+                    }
+                }
+                (lower, upper) => *lower = upper.clone(),
+            }
+        }
+
+        if let Value::Map(map) = &self.value
+            && !map.is_empty()
+        {
+            if *tree == Value::Null {
+                *tree = Value::Map(Map::new());
+            }
+            merge(tree, &self.value);
+        }
+    }
+
+    /// Return the paths of the strings set by the variables, for use with an
+    /// [`OverlayDeserializer`] once the values have been overlaid
+    pub(crate) fn string_paths(&self) -> EnvStrings {
+        self.strings
+            .iter()
+            .map(|path| (path.clone(), self.parse_values))
+            .collect()
+    }
+
+    /// Return the name of the variable that set the value at `path` or one
+    /// of its ancestors, if any
+    pub(crate) fn var_for(&self, path: &str) -> Option<&str> {
+        // Search in reverse so that variables for nested keys take precedence
+        // over variables for their parents that they overwrote
+        self.vars.iter().rev().find_map(|(p, var)| {
+            let under = path
+                .strip_prefix(p.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']));
+            under.then_some(var.as_str())
+        })
+    }
+}
+
+/// The paths within a tree of values at which the strings were set by
+/// environment variables, each paired with whether to parse the string when
+/// it is deserialized as any type
+pub(crate) type EnvStrings = HashMap<Vec<Segment>, bool>;
+
+/// A [`Deserializer`] for a tree of values with environment variables
+/// overlaid on it.  Strings that were set by environment variables are
+/// converted to whatever types are requested by a [`ScalarDeserializer`];
+/// all other values are deserialized the same way as by [`Value`]'s own
+/// `Deserializer` implementation.
+pub(crate) struct OverlayDeserializer<'a> {
+    value: Value,
+    path: Vec<Segment>,
+    env: &'a EnvStrings,
+}
+
+impl<'a> OverlayDeserializer<'a> {
+    pub(crate) fn new(value: Value, env: &'a EnvStrings) -> Self {
+        OverlayDeserializer {
+            value,
+            path: Vec::new(),
+            env,
+        }
+    }
+
+    fn child(&self, seg: Segment, value: Value) -> OverlayDeserializer<'a> {
+        let mut path = self.path.clone();
+        path.push(seg);
+        OverlayDeserializer {
+            value,
+            path,
+            env: self.env,
+        }
+    }
+
+    /// If the value is a string set by an environment variable, return a
+    /// [`ScalarDeserializer`] for it; otherwise, return `self`
+    fn scalar(self) -> Result<ScalarDeserializer<ValueError>, Self> {
+        match (self.value, self.env.get(&self.path)) {
+            (Value::String(s), Some(&parse_values)) => {
+                Ok(ScalarDeserializer::new(s, None, parse_values))
+            }
+            (value, _) => Err(OverlayDeserializer { value, ..self }),
+        }
+    }
+
+    /// Return a deserializer for the entries of `map`, which is the value of
+    /// `self`
+    fn map_deserializer(
+        self,
+        map: Map,
+    ) -> de::value::MapDeserializer<
+        'static,
+        impl Iterator<Item = (KeyDeserializer, OverlayDeserializer<'a>)>,
+        ValueError,
+    > {
+        de::value::MapDeserializer::new(
+            map.into_iter()
+                .map(move |(k, v)| (KeyDeserializer(k.clone()), self.child(Segment::Key(k), v))),
+        )
+    }
+}
+
+macro_rules! forward_to_scalar {
+    ($($method:ident($($arg:ident: $ty:ty),*)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(
+                self,
+                $($arg: $ty,)*
+                visitor: V,
+            ) -> Result<V::Value, ValueError> {
+                match self.scalar() {
+                    Ok(scalar) => scalar.$method($($arg,)* visitor),
+                    Err(this @ OverlayDeserializer {
+                        value: Value::Seq(_) | Value::Map(_),
+                        ..
+                    }) => this.deserialize_any(visitor),
+                    Err(this) => this.value.$method($($arg,)* visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for OverlayDeserializer<'_> {
+    type Error = ValueError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        match self.scalar() {
+            Ok(scalar) => scalar.deserialize_any(visitor),
+            Err(mut this) => match std::mem::take(&mut this.value) {
+                Value::Seq(values) => {
+                    let mut deserializer = de::value::SeqDeserializer::new(
+                        values
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, v)| this.child(Segment::Index(i), v))
+                            .collect::<Vec<_>>()
+                            .into_iter(),
+                    );
+                    let seq = visitor.visit_seq(&mut deserializer)?;
+                    deserializer.end()?;
+                    Ok(seq)
+                }
+                Value::Map(map) => {
+                    let mut deserializer = this.map_deserializer(map);
+                    let map = visitor.visit_map(&mut deserializer)?;
+                    deserializer.end()?;
+                    Ok(map)
+                }
+                value => value.deserialize_any(visitor),
+            },
+        }
+    }
+
+    forward_to_scalar! {
+        deserialize_bool(),
+        deserialize_i8(),
+        deserialize_i16(),
+        deserialize_i32(),
+        deserialize_i64(),
+        deserialize_i128(),
+        deserialize_u8(),
+        deserialize_u16(),
+        deserialize_u32(),
+        deserialize_u64(),
+        deserialize_u128(),
+        deserialize_f32(),
+        deserialize_f64(),
+        deserialize_char(),
+        deserialize_str(),
+        deserialize_string(),
+        deserialize_bytes(),
+        deserialize_byte_buf(),
+        deserialize_unit(),
+        deserialize_unit_struct(name: &'static str),
+        deserialize_seq(),
+        deserialize_tuple(len: usize),
+        deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_map(),
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+        deserialize_identifier(),
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        match self.scalar() {
+            Ok(scalar) => scalar.deserialize_option(visitor),
+            Err(this) if this.value.is_null() => visitor.visit_none(),
+            Err(this) => visitor.visit_some(this),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        match self.scalar() {
+            Ok(scalar) => scalar.deserialize_newtype_struct(name, visitor),
+            Err(this) => visitor.visit_newtype_struct(this),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        match self.scalar() {
+            Ok(scalar) => scalar.deserialize_enum(name, variants, visitor),
+            Err(mut this) => match std::mem::take(&mut this.value) {
+                Value::Map(map) if map.len() == 1 => visitor.visit_enum(
+                    de::value::MapAccessDeserializer::new(this.map_deserializer(map)),
+                ),
+                value => value.deserialize_enum(name, variants, visitor),
+            },
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        visitor.visit_unit()
+    }
+}
+
+impl<'a> IntoDeserializer<'_, ValueError> for OverlayDeserializer<'a> {
+    type Deserializer = OverlayDeserializer<'a>;
+
+    fn into_deserializer(self) -> OverlayDeserializer<'a> {
+        self
+    }
+}
+
+/// Error type for reading configuration from environment variables
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum EnvError {
+    /// Returned if the value of a matching environment variable is not valid
+    /// Unicode
+    #[error("value of environment variable {var} is not valid Unicode")]
+    NotUnicode {
+        /// The name of the variable
+        var: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect::<Map>(),
+        )
+    }
+
+    /// Deserialize the values in `tree` as any type
+    fn parsed(tree: &EnvTree) -> Value {
+        let strings = tree.string_paths();
+        Value::deserialize(OverlayDeserializer::new(tree.value.clone(), &strings)).unwrap()
+    }
+
+    #[test]
+    fn load_vars() {
+        let env = EnvSource::new("APP_").vars([
+            ("APP_SERVER__PORT", "9000"),
+            ("APP_SERVER__HOST", "example.com"),
+            ("APP_DEBUG", "true"),
+            ("APP_RATIO", "0.5"),
+            ("APP_", "ignored"),
+            ("APP_BAD____KEY", "ignored"),
+            ("OTHER_NAME", "ignored"),
+        ]);
+        let tree = env.load().unwrap();
+        assert_eq!(
+            tree.value,
+            map([
+                ("debug", Value::from("true")),
+                ("ratio", Value::from("0.5")),
+                (
+                    "server",
+                    map([
+                        ("host", Value::from("example.com")),
+                        ("port", Value::from("9000")),
+                    ])
+                ),
+            ])
+        );
+        assert_eq!(
+            parsed(&tree),
+            map([
+                ("debug", Value::Bool(true)),
+                ("ratio", Value::Float(0.5)),
+                (
+                    "server",
+                    map([
+                        ("host", Value::from("example.com")),
+                        ("port", Value::Integer(9000)),
+                    ])
+                ),
+            ])
+        );
+        assert_eq!(tree.var_for("server.port"), Some("APP_SERVER__PORT"));
+        assert_eq!(tree.var_for("debug"), Some("APP_DEBUG"));
+        assert_eq!(tree.var_for("server"), None);
+    }
+
+    #[test]
+    fn nested_replaces_parent() {
+        let env = EnvSource::new("APP_").vars([("APP_SERVER__PORT", "1"), ("APP_SERVER", "x")]);
+        let tree = env.load().unwrap();
+        assert_eq!(
+            parsed(&tree),
+            map([("server", map([("port", Value::Integer(1))]))])
+        );
+        assert_eq!(tree.var_for("server.port"), Some("APP_SERVER__PORT"));
+    }
+
+    #[test]
+    fn custom_separators() {
+        let env = EnvSource::new("APP_")
+            .separator("_")
+            .list_separator(",")
+            .vars([("APP_SERVER_HOSTS", "a, 2 ,true"), ("APP_NAME", "x")]);
+        let tree = env.load().unwrap();
+        assert_eq!(
+            parsed(&tree),
+            map([
+                ("name", Value::String(String::from("x"))),
+                (
+                    "server",
+                    map([(
+                        "hosts",
+                        Value::Seq(vec![
+                            Value::String(String::from("a")),
+                            Value::Integer(2),
+                            Value::Bool(true),
+                        ])
+                    )])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn no_parsing() {
+        let env = EnvSource::new("APP_")
+            .parse_values(false)
+            .vars([("APP_PORT", "9000"), ("APP_DEBUG", "true")]);
+        let tree = env.load().unwrap();
+        assert_eq!(
+            parsed(&tree),
+            map([
+                ("debug", Value::String(String::from("true"))),
+                ("port", Value::String(String::from("9000"))),
+            ])
+        );
+    }
+
+    #[test]
+    fn non_numeric_floats_are_strings() {
+        let env =
+            EnvSource::new("APP_").vars([("APP_A", "inf"), ("APP_B", "NaN"), ("APP_C", "1e3")]);
+        let tree = env.load().unwrap();
+        assert_eq!(
+            parsed(&tree),
+            map([
+                ("a", Value::String(String::from("inf"))),
+                ("b", Value::String(String::from("NaN"))),
+                ("c", Value::Float(1000.0)),
+            ])
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn not_unicode() {
+        use std::os::unix::ffi::OsStringExt;
+        let env = EnvSource::new("APP_").vars([(
+            OsString::from("APP_NAME"),
+            OsString::from_vec(b"\xFF".to_vec()),
+        )]);
+        assert_eq!(
+            env.load(),
+            Err(EnvError::NotUnicode {
+                var: String::from("APP_NAME")
+            })
+        );
+    }
+
+    #[test]
+    fn overlay() {
+        let env = EnvSource::new("APP_").vars([("APP_SERVER__PORT", "9000")]);
+        let tree = env.load().unwrap();
+        let mut value = map([
+            ("name", Value::String(String::from("app"))),
+            (
+                "server",
+                map([
+                    ("host", Value::String(String::from("localhost"))),
+                    ("port", Value::Integer(8080)),
+                ]),
+            ),
+        ]);
+        tree.overlay(&mut value);
+        assert_eq!(
+            value,
+            map([
+                ("name", Value::String(String::from("app"))),
+                (
+                    "server",
+                    map([
+                        ("host", Value::String(String::from("localhost"))),
+                        ("port", Value::from("9000")),
+                    ])
+                ),
+            ])
+        );
+        let mut null = Value::Null;
+        tree.overlay(&mut null);
+        assert_eq!(
+            null,
+            map([("server", map([("port", Value::from("9000"))]))])
+        );
+    }
+}
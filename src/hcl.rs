@@ -0,0 +1,106 @@
+//! Support for HCL, whose documents are bodies of attributes & blocks
+use ::hcl::format::{Format as _, Formatter};
+use ::hcl::{Attribute, Block, BlockLabel, Body, Error, Identifier, Map, Structure, Value};
+use serde::ser::{self, Serialize, Serializer};
+use serde_path_to_error::{Error as PathError, Track};
+use std::cell::RefCell;
+
+/// Parse `s` as an HCL body and return a deserializer for it
+pub(crate) fn deserializer(s: &str) -> Result<::hcl::de::Deserializer, PathError<Error>> {
+    ::hcl::de::Deserializer::from_str(s).map_err(|e| PathError::new(Track::new().path(), e))
+}
+
+/// Serialize `value` as an HCL body.
+///
+/// The value is first converted to an [`hcl::Value`][Value], which must be an
+/// object whose keys are valid identifiers.  Each entry then becomes a block
+/// if its value is an object whose keys are all valid identifiers and an
+/// attribute otherwise, and blocks whose bodies consist only of blocks are
+/// folded into labeled blocks, so that `{"resource": {"aws_instance": {"web":
+/// {...}}}}` is written as `resource "aws_instance" "web" { ... }`.  Loading
+/// the output back in produces the original value.
+pub(crate) fn to_string<T: Serialize>(value: &T) -> Result<String, PathError<Error>> {
+    let tracked = Tracked {
+        value,
+        track: RefCell::new(Track::new()),
+    };
+    let value = match ::hcl::to_value(&tracked) {
+        Ok(value) => value,
+        Err(e) => return Err(PathError::new(tracked.track.into_inner().path(), e)),
+    };
+    let Value::Object(map) = value else {
+        return Err(PathError::new(
+            Track::new().path(),
+            Error::Message(String::from("top-level value must be a map")),
+        ));
+    };
+    let body = Body(structures(map).map_err(|e| PathError::new(Track::new().path(), e))?);
+    let mut formatter = Formatter::builder().prefer_ident_keys(true).build_vec();
+    body.format_string(&mut formatter)
+        .map_err(|e| PathError::new(Track::new().path(), e))
+}
+
+/// Convert the entries of an object to the structures of an HCL body
+fn structures(map: Map<String, Value>) -> Result<Vec<Structure>, Error> {
+    let mut structures = Vec::with_capacity(map.len());
+    for (key, value) in map {
+        let Ok(ident) = Identifier::new(key.as_str()) else {
+            return Err(Error::Message(format!(
+                "key {key:?} is not a valid HCL identifier"
+            )));
+        };
+        match value {
+            Value::Object(map) if map.keys().all(|k| Identifier::new(k.as_str()).is_ok()) => {
+                structures.extend(blocks(ident, map)?.into_iter().map(Structure::Block));
+            }
+            value => structures.push(Structure::Attribute(Attribute::new(ident, value))),
+        }
+    }
+    Ok(structures)
+}
+
+/// Convert an object to one or more blocks with the given identifier.  If
+/// the object's entries all become blocks, they are turned into labeled
+/// blocks instead.
+fn blocks(ident: Identifier, map: Map<String, Value>) -> Result<Vec<Block>, Error> {
+    let body = structures(map)?;
+    if body.is_empty() || !body.iter().all(Structure::is_block) {
+        return Ok(vec![Block {
+            identifier: ident,
+            labels: Vec::new(),
+            body: Body(body),
+        }]);
+    }
+    Ok(body
+        .into_iter()
+        .filter_map(Structure::into_block)
+        .map(|inner| {
+            let mut labels = Vec::with_capacity(inner.labels.len() + 1);
+            labels.push(BlockLabel::from(inner.identifier.into_inner()));
+            labels.extend(inner.labels);
+            Block {
+                identifier: ident.clone(),
+                labels,
+                body: inner.body,
+            }
+        })
+        .collect())
+}
+
+/// A wrapper around a value that tracks the path to the value being
+/// serialized, so that errors from [`hcl::to_value()`][::hcl::to_value] can
+/// be reported with their location
+struct Tracked<'a, T> {
+    value: &'a T,
+    track: RefCell<Track>,
+}
+
+impl<T: Serialize> Serialize for Tracked<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Ok(mut track) = self.track.try_borrow_mut() else {
+            return Err(ser::Error::custom("value serialized recursively"));
+        };
+        self.value
+            .serialize(serde_path_to_error::Serializer::new(serializer, &mut track))
+    }
+}
@@ -0,0 +1,362 @@
+//! Support for the INI format
+use crate::backend::{Backend, BackendError, DumpSource, LoadSink};
+use crate::keyvalue::{Entry, Node, NodeDeserializer, Shape, flatten};
+use crate::locate::{LocatedError, Location};
+use crate::track::{Tracker, depath_tracked};
+use crate::{DeserializeError, SerializeError};
+use indexmap::IndexMap;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_path_to_error::{Error as PathError, Track};
+
+/// Options for (de)serializing INI files.
+///
+/// [`Format::Ini`][crate::Format::Ini] uses the default options.  To load &
+/// dump INI files with different options, call [`IniOptions::load_from_str()`]
+/// and [`IniOptions::dump_to_string()`] directly, or register the options
+/// with a [`Cfgfifo`][crate::Cfgfifo] as a custom [`Backend`].
+///
+/// # Syntax
+///
+/// An INI document consists of `key = value` (or `key: value`) pairs grouped
+/// into sections by `[section]` headers.  Lines starting with `;` or `#` are
+/// comments, and blank lines are ignored.  Keys, values, and section names
+/// are trimmed of surrounding whitespace and are otherwise used verbatim;
+/// there is no quoting or escaping, and a value cannot span multiple lines.
+/// (As a result, leading & trailing whitespace in serialized strings is not
+/// preserved.)  If a key occurs more than once in a section, the last value is
+/// used, and if a section header occurs more than once, the sections are
+/// combined.
+///
+/// # Deserialization
+///
+/// A document is deserialized as a map from section names to maps of the
+/// sections' keys & values.  Keys that appear before the first section header
+/// (the "global section") are handled as set by
+/// [`IniOptions::global_section()`].
+///
+/// Values are strings, but they are coerced to whatever type is requested:
+/// numbers are parsed, booleans may be written as `true`/`false`,
+/// `yes`/`no`, `on`/`off`, or `1`/`0` (case-insensitively), empty values
+/// deserialize as `None`, and sequences are split on commas.  When
+/// deserializing a self-describing type like [`Value`][crate::Value], values
+/// that look like booleans & numbers are parsed as such unless disabled with
+/// [`IniOptions::parse_values()`].
+///
+/// # Serialization
+///
+/// Only maps & structs can be serialized as INI documents.  Each field of the
+/// top-level value that is a map or struct becomes a section, and all other
+/// fields become keys in the global section, which is written before any
+/// section headers.  Maps & structs cannot be nested any further than that.
+/// Sequences of scalars are written as comma-separated values, `None` and
+/// unit values are omitted, and enums are written as their variant names.
+#[cfg_attr(feature = "ini", doc = concat!(
+    "# Example\n",
+    "\n",
+    "```\n",
+    "use cfgfifo::{AnyFormat, Cfgfifo, GlobalSection, IniOptions};\n",
+    "use serde::Deserialize;\n",
+    "\n",
+    "#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]\n",
+    "struct Config {\n",
+    "    general: General,\n",
+    "}\n",
+    "\n",
+    "#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]\n",
+    "struct General {\n",
+    "    name: String,\n",
+    "    verbose: bool,\n",
+    "}\n",
+    "\n",
+    "let options = IniOptions::new().global_section(GlobalSection::Named(String::from(\"general\")));\n",
+    "\n",
+    "let cfg: Config = options.load_from_str(\"name = app\\nverbose = yes\\n\").unwrap();\n",
+    "assert_eq!(cfg.general.name, \"app\");\n",
+    "assert!(cfg.general.verbose);\n",
+    "\n",
+    "// Use the options for all `.ini` files loaded by a `Cfgfifo`:\n",
+    "let cfgfifo = Cfgfifo::new().extension(\"ini\", AnyFormat::custom(options));\n",
+    "assert_eq!(cfgfifo.identify(\"config.ini\").unwrap().name(), \"INI\");\n",
+    "```\n",
+))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IniOptions {
+    global_section: GlobalSection,
+    parse_values: bool,
+}
+
+impl IniOptions {
+    /// Create a new `IniOptions` with the default settings
+    pub fn new() -> IniOptions {
+        IniOptions {
+            global_section: GlobalSection::Root,
+            parse_values: true,
+        }
+    }
+
+    /// Set how to handle keys that appear before the first section header.
+    ///
+    /// The default is [`GlobalSection::Root`].
+    pub fn global_section(mut self, global: GlobalSection) -> Self {
+        self.global_section = global;
+        self
+    }
+
+    /// Enable or disable parsing values as booleans & numbers when
+    /// deserializing a type that does not request a specific type for them.
+    ///
+    /// When disabled, such values are deserialized as strings.  Values are
+    /// always coerced to the types requested by types like structs with typed
+    /// fields.  Parsing is enabled by default.
+    pub fn parse_values(mut self, parse: bool) -> Self {
+        self.parse_values = parse;
+        self
+    }
+
+    /// Deserialize an INI string with these options
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the string is not valid INI or if deserializing the
+    /// value fails.
+    pub fn load_from_str<T: DeserializeOwned>(&self, s: &str) -> Result<T, DeserializeError> {
+        self.load_from_str_tracked(s, None)
+    }
+
+    /// Like [`IniOptions::load_from_str()`], but with a [`Tracker`]
+    pub(crate) fn load_from_str_tracked<T: DeserializeOwned>(
+        &self,
+        s: &str,
+        tracker: Option<&mut Tracker>,
+    ) -> Result<T, DeserializeError> {
+        let de = self.deserializer(s).map_err(DeserializeError::Ini)?;
+        depath_tracked(de, tracker).map_err(DeserializeError::Ini)
+    }
+
+    /// Parse an INI string and return a deserializer for it
+    pub(crate) fn deserializer(
+        &self,
+        s: &str,
+    ) -> Result<NodeDeserializer<IniError>, PathError<IniError>> {
+        let node =
+            parse(s, &self.global_section).map_err(|e| PathError::new(Track::new().path(), e))?;
+        Ok(NodeDeserializer::new(node, self.parse_values))
+    }
+
+    /// Serialize a value to an INI string with these options
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value cannot be represented in INI.
+    pub fn dump_to_string<T: Serialize>(&self, value: &T) -> Result<String, SerializeError> {
+        self.dump(value).map_err(SerializeError::Ini)
+    }
+
+    fn dump<T: Serialize>(&self, value: &T) -> Result<String, PathError<IniError>> {
+        let shape = Shape {
+            max_depth: 2,
+            root_scalars: self.global_section == GlobalSection::Root,
+            multiline: false,
+            indexed_seqs: false,
+            check_key,
+        };
+        flatten(value, &shape).map(render)
+    }
+}
+
+impl Default for IniOptions {
+    /// Same as [`IniOptions::new()`]
+    fn default() -> IniOptions {
+        IniOptions::new()
+    }
+}
+
+impl Backend for IniOptions {
+    fn name(&self) -> &'static str {
+        "INI"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ini"]
+    }
+
+    fn load_from_str(&self, s: &str, sink: LoadSink<'_>) -> Result<(), BackendError> {
+        let de = self.deserializer(s).map_err(PathError::into_inner)?;
+        sink.deserialize(de)?;
+        Ok(())
+    }
+
+    fn dump_to_string(&self, value: DumpSource<'_>) -> Result<String, BackendError> {
+        Ok(self.dump(&value)?)
+    }
+}
+
+/// How to handle the keys in an INI document that appear before the first
+/// section header
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum GlobalSection {
+    /// The keys are placed at the top level of the document alongside the
+    /// sections, and top-level non-map values are serialized as such keys.
+    #[default]
+    Root,
+
+    /// The keys are placed in the section with the given name, which is
+    /// combined with any section of that name that appears explicitly in the
+    /// document.  All top-level values must be maps when serializing.
+    Named(String),
+
+    /// Keys before the first section header are an error, as are top-level
+    /// non-map values when serializing.
+    Reject,
+}
+
+/// Error type for INI (de)serialization; an alias of [`LocatedError`]
+pub type IniError = LocatedError;
+
+/// Return the location of the byte offset `offset` within `line`, which is
+/// line number `lineno`
+fn location(lineno: usize, line: &str, offset: usize) -> Location {
+    Location::new(lineno, line[..offset].chars().count() + 1)
+}
+
+/// Parse an INI document into a [`Node`]
+fn parse(s: &str, global: &GlobalSection) -> Result<Node, IniError> {
+    let s = s.strip_prefix('\u{FEFF}').unwrap_or(s);
+    let mut root = IndexMap::new();
+    let mut section: Option<String> = match global {
+        GlobalSection::Named(name) => Some(name.clone()),
+        GlobalSection::Root | GlobalSection::Reject => None,
+    };
+    for (lineno, line) in (1..).zip(s.lines()) {
+        let content = line.trim();
+        if content.is_empty() || content.starts_with([';', '#']) {
+            continue;
+        }
+        let start = line.len() - line.trim_start().len();
+        if let Some(header) = content.strip_prefix('[') {
+            let loc = location(lineno, line, start);
+            let Some(name) = header.strip_suffix(']') else {
+                return Err(IniError::new("unterminated section header", loc));
+            };
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(IniError::new("empty section name", loc));
+            }
+            match root.get(name) {
+                Some(Node::Scalar(..)) => {
+                    return Err(IniError::new(
+                        format!("section {name:?} has the same name as a global key"),
+                        loc,
+                    ));
+                }
+                Some(Node::Map(..)) => (),
+                None => {
+                    root.insert(name.to_owned(), Node::Map(IndexMap::new(), Some(loc)));
+                }
+            }
+            section = Some(name.to_owned());
+            continue;
+        }
+        let Some(sep) = content.find(['=', ':']) else {
+            return Err(IniError::new(
+                "expected `key = value` or `[section]`",
+                location(lineno, line, start),
+            ));
+        };
+        let key = content[..sep].trim_end();
+        if key.is_empty() {
+            return Err(IniError::new("empty key", location(lineno, line, start)));
+        }
+        let after = &content[sep + 1..];
+        let value = after.trim_start();
+        let value_loc = location(lineno, line, start + sep + 1 + (after.len() - value.len()));
+        let node = Node::Scalar(value.to_owned(), value_loc);
+        match &section {
+            Some(name) => {
+                let entry = root
+                    .entry(name.clone())
+                    .or_insert_with(|| Node::Map(IndexMap::new(), None));
+                if let Node::Map(entries, _) = entry {
+                    entries.insert(key.to_owned(), node);
+                }
+            }
+            None if *global == GlobalSection::Reject => {
+                return Err(IniError::new(
+                    "key outside of any section",
+                    location(lineno, line, start),
+                ));
+            }
+            None => {
+                root.insert(key.to_owned(), node);
+            }
+        }
+    }
+    Ok(Node::Map(root, None))
+}
+
+/// Check whether `key` can be used as a global key or section name (at depth
+/// 0) or as a key within a section (at depth 1) in an INI document
+fn check_key(key: &str, depth: usize) -> Result<(), String> {
+    if key.is_empty() {
+        Err(String::from("keys cannot be empty"))
+    } else if key.trim() != key {
+        Err(format!("key {key:?} cannot start or end with whitespace"))
+    } else if key.contains(['\n', '\r']) {
+        Err(format!("key {key:?} cannot contain line breaks"))
+    } else if key.starts_with([';', '#', '[']) {
+        Err(format!("key {key:?} cannot start with {:?}", &key[..1]))
+    } else if key.contains(['=', ':']) {
+        Err(format!("key {key:?} cannot contain \"=\" or \":\""))
+    } else if depth == 0 && key.contains(']') {
+        Err(format!("section name {key:?} cannot contain \"]\""))
+    } else {
+        Ok(())
+    }
+}
+
+/// Render flattened entries as an INI document, with the global keys first
+fn render(entries: Vec<Entry>) -> String {
+    let mut globals = Vec::new();
+    let mut sections: IndexMap<String, Vec<(String, String)>> = IndexMap::new();
+    for Entry { mut path, value } in entries {
+        match (path.len(), value) {
+            (1, Some(value)) => globals.push((path.remove(0), value)),
+            (1, None) => {
+                sections.entry(path.remove(0)).or_default();
+            }
+            (_, Some(value)) => {
+                let key = path.pop().unwrap_or_default();
+                let name = path.remove(0);
+                sections.entry(name).or_default().push((key, value));
+            }
+            (_, None) => (),
+        }
+    }
+    let mut out = String::new();
+    for (key, value) in globals {
+        push_pair(&mut out, &key, &value);
+    }
+    for (name, pairs) in sections {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push('[');
+        out.push_str(&name);
+        out.push_str("]\n");
+        for (key, value) in pairs {
+            push_pair(&mut out, &key, &value);
+        }
+    }
+    out
+}
+
+fn push_pair(out: &mut String, key: &str, value: &str) {
+    out.push_str(key);
+    if value.is_empty() {
+        out.push_str(" =\n");
+    } else {
+        out.push_str(" = ");
+        out.push_str(value);
+        out.push('\n');
+    }
+}
@@ -0,0 +1,2048 @@
+//! Support for KDL, whose documents are trees of nodes with arguments,
+//! properties, & children
+use crate::locate::{LocatedError, Location};
+use indexmap::IndexMap;
+use serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::ser::{self, Impossible, Serialize, Serializer};
+use serde_path_to_error::{Error as PathError, Track, serialize as serpath};
+use std::fmt::{self, Write};
+
+/// Parse `s` as a KDL document and return a deserializer for it
+pub(crate) fn deserializer(s: &str) -> Result<NodeDeserializer, PathError<KdlError>> {
+    let nodes = Parser::new(s)
+        .parse()
+        .map_err(|e| PathError::new(Track::new().path(), e))?;
+    let root = Node {
+        name: String::new(),
+        span: None,
+        arguments: Vec::new(),
+        properties: IndexMap::new(),
+        children: Some(nodes),
+    };
+    NodeDeserializer::new(root).map_err(|e| PathError::new(Track::new().path(), e))
+}
+
+/// Serialize `value` as a KDL document.
+///
+/// The value must serialize to a map, each entry of which becomes a node.
+/// Scalars & sequences of scalars are written as node arguments, other
+/// sequences as children named `-`, and maps as children, except that maps of
+/// scalars within sequences are written as properties of their `-` nodes.
+/// Non-empty maps whose keys are all `-` are rejected, as their children would
+/// be read back as a sequence.
+pub(crate) fn to_string<T: Serialize>(value: &T) -> Result<String, PathError<KdlError>> {
+    match serpath(value, TreeSerializer)? {
+        Tree::Map(entries) => {
+            let mut out = String::new();
+            for (name, value) in entries {
+                write_node(&mut out, 0, &name, value);
+            }
+            Ok(out)
+        }
+        _ => Err(PathError::new(
+            Track::new().path(),
+            ser::Error::custom("top-level value must be a map"),
+        )),
+    }
+}
+
+/// Error type for KDL (de)serialization; an alias of [`LocatedError`]
+pub type KdlError = LocatedError;
+
+/// A region of the input: the position at which it starts, along with its
+/// byte range
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Span {
+    location: Location,
+    start: usize,
+    end: usize,
+}
+
+impl Span {
+    /// Attach this span to `e` if it does not already have a location
+    fn attach(self, e: KdlError) -> KdlError {
+        e.at_span(self.location, self.start..self.end)
+    }
+
+    /// Create an error with the given message at this span
+    fn error<S: fmt::Display>(self, msg: S) -> KdlError {
+        self.attach(de::Error::custom(msg))
+    }
+}
+
+/// Attach `span` (if any) to the error in `r`
+fn at<T>(r: Result<T, KdlError>, span: Option<Span>) -> Result<T, KdlError> {
+    match span {
+        Some(span) => r.map_err(|e| span.attach(e)),
+        None => r,
+    }
+}
+
+/// A parsed KDL node
+#[derive(Clone, Debug, PartialEq)]
+struct Node {
+    name: String,
+
+    /// The region of the input covered by the node, or `None` for the
+    /// document itself
+    span: Option<Span>,
+
+    arguments: Vec<Scalar>,
+
+    /// The node's properties, along with the spans of their keys.  If a
+    /// property occurs more than once, the last value is used.
+    properties: IndexMap<String, (Span, Scalar)>,
+
+    children: Option<Vec<Node>>,
+}
+
+impl Node {
+    /// Test whether the node's value is a map, i.e., whether it has
+    /// properties or children that are not all named `-`
+    fn is_map(&self) -> bool {
+        !self.properties.is_empty() || self.children.as_deref().is_some_and(|ch| !is_items(ch))
+    }
+}
+
+/// Test whether a list of child nodes represents a sequence, i.e., whether
+/// they are all named `-`
+fn is_items(children: &[Node]) -> bool {
+    !children.is_empty() && children.iter().all(|n| n.name == "-")
+}
+
+/// A KDL value and the region of the input that it covers
+#[derive(Clone, Debug, PartialEq)]
+struct Scalar {
+    value: ScalarValue,
+    span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum ScalarValue {
+    Null,
+    Bool(bool),
+    Integer(i128),
+    Float(f64),
+    String(String),
+}
+
+/// The value of a node, classified by what the node contains
+enum Content {
+    /// A node with no arguments, properties, or children
+    Empty,
+
+    /// A node with a single argument
+    Scalar(Scalar),
+
+    /// A node with multiple arguments
+    Arguments(Vec<Scalar>),
+
+    /// A node whose children are all named `-`
+    Items(Vec<Node>),
+
+    /// A node with properties and/or children, the latter grouped by name
+    Map(
+        IndexMap<String, (Span, Scalar)>,
+        IndexMap<String, Vec<Node>>,
+    ),
+}
+
+/// A [`Deserializer`] for the value of a KDL node
+pub(crate) struct NodeDeserializer {
+    content: Content,
+    span: Option<Span>,
+}
+
+impl NodeDeserializer {
+    fn new(node: Node) -> Result<NodeDeserializer, KdlError> {
+        let Node {
+            span,
+            mut arguments,
+            properties,
+            children,
+            ..
+        } = node;
+        let content = match children {
+            None if properties.is_empty() => match arguments.len() {
+                0 => Content::Empty,
+                1 => Content::Scalar(arguments.remove(0)),
+                _ => Content::Arguments(arguments),
+            },
+            Some(children) if properties.is_empty() && is_items(&children) => {
+                if !arguments.is_empty() {
+                    return at(Err(mixed_node()), span);
+                }
+                Content::Items(children)
+            }
+            children => {
+                if !arguments.is_empty() {
+                    return at(Err(mixed_node()), span);
+                }
+                let mut groups = IndexMap::<String, Vec<Node>>::new();
+                for child in children.into_iter().flatten() {
+                    groups.entry(child.name.clone()).or_default().push(child);
+                }
+                Content::Map(properties, groups)
+            }
+        };
+        Ok(NodeDeserializer { content, span })
+    }
+
+    fn visit<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        match self.content {
+            Content::Empty => at(visitor.visit_unit(), self.span),
+            Content::Scalar(scalar) => ScalarDeserializer(scalar).deserialize_any(visitor),
+            Content::Arguments(arguments) => at(
+                visitor.visit_seq(ScalarSeqAccess(arguments.into_iter())),
+                self.span,
+            ),
+            Content::Items(children) => at(
+                visitor.visit_seq(NodeSeqAccess(children.into_iter())),
+                self.span,
+            ),
+            Content::Map(properties, children) => at(
+                visitor.visit_map(NodeMapAccess {
+                    properties: properties.into_iter(),
+                    children: children.into_iter(),
+                    value: None,
+                }),
+                self.span,
+            ),
+        }
+    }
+}
+
+fn mixed_node() -> KdlError {
+    de::Error::custom("node cannot have both arguments and properties or children")
+}
+
+macro_rules! forward_to_scalar {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+                match self.content {
+                    Content::Scalar(scalar) => ScalarDeserializer(scalar).$method(visitor),
+                    _ => self.visit(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for NodeDeserializer {
+    type Error = KdlError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        self.visit(visitor)
+    }
+
+    forward_to_scalar! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_i128 deserialize_u8 deserialize_u16
+        deserialize_u32 deserialize_u64 deserialize_u128 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_identifier
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        match self.content {
+            Content::Empty
+            | Content::Scalar(Scalar {
+                value: ScalarValue::Null,
+                ..
+            }) => at(visitor.visit_none(), self.span),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        match self.content {
+            Content::Scalar(scalar) => ScalarDeserializer(scalar).deserialize_unit(visitor),
+            _ => self.visit(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        match self.content {
+            Content::Empty => at(
+                visitor.visit_seq(ScalarSeqAccess(Vec::new().into_iter())),
+                self.span,
+            ),
+            Content::Scalar(scalar) => {
+                let span = Some(scalar.span);
+                at(
+                    visitor.visit_seq(ScalarSeqAccess(vec![scalar].into_iter())),
+                    span,
+                )
+            }
+            _ => self.visit(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        match self.content {
+            Content::Empty => at(
+                visitor.visit_map(NodeMapAccess {
+                    properties: IndexMap::new().into_iter(),
+                    children: IndexMap::new().into_iter(),
+                    value: None,
+                }),
+                self.span,
+            ),
+            _ => self.visit(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        match self.content {
+            Content::Scalar(scalar) => {
+                ScalarDeserializer(scalar).deserialize_enum(name, variants, visitor)
+            }
+            Content::Map(properties, children) => {
+                let mut entries = properties
+                    .into_iter()
+                    .map(|(key, (span, scalar))| (key, Some(span), Pending::Property(scalar)))
+                    .chain(children.into_iter().map(|(key, nodes)| {
+                        let span = nodes.first().and_then(|n| n.span);
+                        (key, span, Pending::Children(nodes))
+                    }));
+                let r = match (entries.next(), entries.next()) {
+                    (Some((key, span, value)), None) => {
+                        visitor.visit_enum(NodeEnumAccess { key, span, value })
+                    }
+                    _ => Err(de::Error::custom(
+                        "expected a string or a node with a single property or child",
+                    )),
+                };
+                at(r, self.span)
+            }
+            _ => self.visit(visitor),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        visitor.visit_unit()
+    }
+}
+
+/// A value that has been paired with a key in a [`MapAccess`] but not yet
+/// deserialized
+enum Pending {
+    Property(Scalar),
+    Children(Vec<Node>),
+}
+
+impl Pending {
+    fn deserialize<'de, T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, KdlError> {
+        match self {
+            Pending::Property(scalar) => seed.deserialize(ScalarDeserializer(scalar)),
+            Pending::Children(nodes) => seed.deserialize(GroupDeserializer(nodes)),
+        }
+    }
+}
+
+struct NodeMapAccess {
+    properties: indexmap::map::IntoIter<String, (Span, Scalar)>,
+    children: indexmap::map::IntoIter<String, Vec<Node>>,
+    value: Option<Pending>,
+}
+
+impl<'de> MapAccess<'de> for NodeMapAccess {
+    type Error = KdlError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, KdlError> {
+        let (key, span) = if let Some((key, (span, scalar))) = self.properties.next() {
+            self.value = Some(Pending::Property(scalar));
+            (key, Some(span))
+        } else if let Some((key, nodes)) = self.children.next() {
+            let span = nodes.first().and_then(|n| n.span);
+            self.value = Some(Pending::Children(nodes));
+            (key, span)
+        } else {
+            return Ok(None);
+        };
+        at(seed.deserialize(key.into_deserializer()).map(Some), span)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, KdlError> {
+        match self.value.take() {
+            Some(value) => value.deserialize(seed),
+            None => Err(de::Error::custom("value requested before key")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.properties.len() + self.children.len())
+    }
+}
+
+struct NodeEnumAccess {
+    key: String,
+    span: Option<Span>,
+    value: Pending,
+}
+
+impl<'de> EnumAccess<'de> for NodeEnumAccess {
+    type Error = KdlError;
+    type Variant = Pending;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Pending), KdlError> {
+        let variant = at(seed.deserialize(self.key.into_deserializer()), self.span)?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Pending {
+    type Error = KdlError;
+
+    fn unit_variant(self) -> Result<(), KdlError> {
+        self.deserialize(std::marker::PhantomData::<()>)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, KdlError> {
+        self.deserialize(seed)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, KdlError> {
+        match self {
+            Pending::Property(scalar) => ScalarDeserializer(scalar).deserialize_seq(visitor),
+            Pending::Children(nodes) => GroupDeserializer(nodes).deserialize_seq(visitor),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        match self {
+            Pending::Property(scalar) => ScalarDeserializer(scalar).deserialize_map(visitor),
+            Pending::Children(nodes) => GroupDeserializer(nodes).deserialize_map(visitor),
+        }
+    }
+}
+
+/// A [`Deserializer`] for the one or more sibling nodes that share a name.
+///
+/// Multiple nodes deserialize as a sequence of their values.  A single node
+/// deserializes as its value, except that, when a sequence is requested, a
+/// node whose value is a map deserializes as a sequence of one map.
+struct GroupDeserializer(Vec<Node>);
+
+impl GroupDeserializer {
+    /// Return a deserializer for the group's only node, failing if there is
+    /// more than one
+    fn single(mut self) -> Result<NodeDeserializer, KdlError> {
+        if self.0.len() > 1 {
+            let node = self.0.swap_remove(1);
+            return at(
+                Err(de::Error::custom(format_args!(
+                    "duplicate node {:?}",
+                    node.name
+                ))),
+                node.span,
+            );
+        }
+        match self.0.pop() {
+            Some(node) => NodeDeserializer::new(node),
+            None => Err(de::Error::custom("empty group of nodes")),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for GroupDeserializer {
+    type Error = KdlError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        if self.0.len() > 1 {
+            let span = self.0.first().and_then(|n| n.span);
+            at(visitor.visit_seq(NodeSeqAccess(self.0.into_iter())), span)
+        } else {
+            self.single()?.deserialize_any(visitor)
+        }
+    }
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_i128 deserialize_u8 deserialize_u16
+        deserialize_u32 deserialize_u64 deserialize_u128 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_unit
+        deserialize_map deserialize_identifier
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        if self.0.len() > 1 {
+            visitor.visit_some(self)
+        } else {
+            self.single()?.deserialize_option(visitor)
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        self.single()?.deserialize_unit(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        if self.0.len() > 1 || self.0.first().is_some_and(Node::is_map) {
+            let span = self.0.first().and_then(|n| n.span);
+            at(visitor.visit_seq(NodeSeqAccess(self.0.into_iter())), span)
+        } else {
+            self.single()?.deserialize_seq(visitor)
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        self.single()?.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+}
+
+struct NodeSeqAccess(std::vec::IntoIter<Node>);
+
+impl<'de> SeqAccess<'de> for NodeSeqAccess {
+    type Error = KdlError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, KdlError> {
+        match self.0.next() {
+            Some(node) => seed.deserialize(NodeDeserializer::new(node)?).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct ScalarSeqAccess(std::vec::IntoIter<Scalar>);
+
+impl<'de> SeqAccess<'de> for ScalarSeqAccess {
+    type Error = KdlError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, KdlError> {
+        match self.0.next() {
+            Some(scalar) => seed.deserialize(ScalarDeserializer(scalar)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// A [`Deserializer`] for a single KDL value
+struct ScalarDeserializer(Scalar);
+
+impl<'de> Deserializer<'de> for ScalarDeserializer {
+    type Error = KdlError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        let Scalar { value, span } = self.0;
+        let r = match value {
+            ScalarValue::Null => visitor.visit_unit(),
+            ScalarValue::Bool(b) => visitor.visit_bool(b),
+            ScalarValue::Integer(i) => {
+                if let Ok(i) = i64::try_from(i) {
+                    visitor.visit_i64(i)
+                } else if let Ok(u) = u64::try_from(i) {
+                    visitor.visit_u64(u)
+                } else {
+                    visitor.visit_i128(i)
+                }
+            }
+            ScalarValue::Float(f) => visitor.visit_f64(f),
+            ScalarValue::String(s) => visitor.visit_string(s),
+        };
+        at(r, Some(span))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        if self.0.value == ScalarValue::Null {
+            at(visitor.visit_none(), Some(self.0.span))
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, KdlError> {
+        match self.0.value {
+            ScalarValue::String(s) => {
+                let span = self.0.span;
+                at(visitor.visit_enum(s.into_deserializer()), Some(span))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, KdlError> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier
+    }
+}
+
+/// A parser for KDL documents that tracks the location of each character
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+
+    /// The byte offset of the current character in the original input
+    offset: usize,
+}
+
+impl Parser {
+    fn new(s: &str) -> Parser {
+        let body = s.strip_prefix('\u{FEFF}').unwrap_or(s);
+        Parser {
+            chars: body.chars().collect(),
+            pos: 0,
+            line: 1,
+            column: 1,
+            offset: s.len() - body.len(),
+        }
+    }
+
+    /// Return an empty span at the current position
+    fn mark(&self) -> Span {
+        Span {
+            location: Location::new(self.line, self.column),
+            start: self.offset,
+            end: self.offset,
+        }
+    }
+
+    /// Return the span from `start` up to the current position
+    fn span_from(&self, start: Span) -> Span {
+        Span {
+            end: self.offset,
+            ..start
+        }
+    }
+
+    /// Create an error with the given message for the input from `start` up
+    /// to the current position or, if that is empty, for the current
+    /// character
+    fn error_at<S: fmt::Display>(&self, start: Span, msg: S) -> KdlError {
+        let mut span = self.span_from(start);
+        if span.start == span.end {
+            span.end += self.peek().map_or(0, char::len_utf8);
+        }
+        span.error(msg)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.chars.get(self.pos + n).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Consume `s` if the input continues with it
+    fn eat(&mut self, s: &str) -> bool {
+        let matches = s
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_nth(i) == Some(c));
+        if matches {
+            for _ in s.chars() {
+                self.next();
+            }
+        }
+        matches
+    }
+
+    fn parse(mut self) -> Result<Vec<Node>, KdlError> {
+        self.parse_nodes(None)
+    }
+
+    /// Parse nodes up to the end of the input or, if `block` is the position
+    /// of the opening brace of a children block, up to & including the
+    /// closing brace
+    fn parse_nodes(&mut self, block: Option<Span>) -> Result<Vec<Node>, KdlError> {
+        let mut nodes = Vec::new();
+        loop {
+            self.skip_line_space()?;
+            match (self.peek(), block) {
+                (None, None) => return Ok(nodes),
+                (None, Some(start)) => {
+                    return Err(self.error_at(start, "unterminated children block"));
+                }
+                (Some('}'), Some(_)) => {
+                    self.next();
+                    return Ok(nodes);
+                }
+                (Some('}'), None) => return Err(self.error_at(self.mark(), "unexpected `}`")),
+                (Some(';'), _) => {
+                    self.next();
+                }
+                _ => {
+                    let discard = self.eat_slashdash()?;
+                    let node = self.parse_node()?;
+                    if !discard {
+                        nodes.push(node);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Consume a `/-` comment marker and any following whitespace, returning
+    /// whether one was present
+    fn eat_slashdash(&mut self) -> Result<bool, KdlError> {
+        if self.eat("/-") {
+            self.skip_line_space()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn parse_node(&mut self) -> Result<Node, KdlError> {
+        let start = self.mark();
+        self.skip_type_annotation()?;
+        let Token::String(name) = self.parse_value()? else {
+            return Err(self.error_at(start, "expected a node name"));
+        };
+        let mut node = Node {
+            name,
+            span: None,
+            arguments: Vec::new(),
+            properties: IndexMap::new(),
+            children: None,
+        };
+        // The offset of the end of the node's last entry or children block
+        let mut end = self.offset;
+        loop {
+            let spaced = self.skip_node_space()?;
+            match self.peek() {
+                None | Some('}') => break,
+                Some(';') => {
+                    self.next();
+                    break;
+                }
+                Some(c) if is_newline(c) => {
+                    self.next();
+                    break;
+                }
+                Some('/') if self.peek_nth(1) == Some('/') => {
+                    self.skip_line_comment();
+                    break;
+                }
+                Some('/') if self.peek_nth(1) == Some('-') => {
+                    self.eat_slashdash()?;
+                    if self.peek() == Some('{') {
+                        let start = self.mark();
+                        self.next();
+                        self.parse_nodes(Some(start))?;
+                    } else if node.children.is_some() {
+                        return Err(self
+                            .error_at(self.mark(), "node entries cannot follow a children block"));
+                    } else {
+                        self.parse_entry()?;
+                    }
+                }
+                Some('{') => {
+                    if node.children.is_some() {
+                        return Err(self.error_at(
+                            self.mark(),
+                            "node cannot have more than one children block",
+                        ));
+                    }
+                    let start = self.mark();
+                    self.next();
+                    node.children = Some(self.parse_nodes(Some(start))?);
+                    end = self.offset;
+                }
+                Some(_) => {
+                    if node.children.is_some() {
+                        return Err(self
+                            .error_at(self.mark(), "node entries cannot follow a children block"));
+                    }
+                    if !spaced {
+                        return Err(
+                            self.error_at(self.mark(), "expected whitespace before node entry")
+                        );
+                    }
+                    match self.parse_entry()? {
+                        Entry::Argument(scalar) => node.arguments.push(scalar),
+                        Entry::Property(key, span, scalar) => {
+                            node.properties.shift_remove(&key);
+                            node.properties.insert(key, (span, scalar));
+                        }
+                    }
+                    end = self.offset;
+                }
+            }
+        }
+        node.span = Some(Span { end, ..start });
+        Ok(node)
+    }
+
+    /// Parse a node argument or property
+    fn parse_entry(&mut self) -> Result<Entry, KdlError> {
+        let start = self.mark();
+        let annotated = self.skip_type_annotation()?;
+        let value_start = self.mark();
+        let token = self.parse_value()?;
+        if self.peek() == Some('=') {
+            let Token::String(key) = token else {
+                return Err(self.error_at(start, "property keys must be strings"));
+            };
+            if annotated {
+                return Err(self.error_at(start, "property keys cannot have type annotations"));
+            }
+            let key_span = self.span_from(start);
+            self.next();
+            let value_start = self.mark();
+            self.skip_type_annotation()?;
+            let value = self.parse_value()?.into_scalar(self.span_from(value_start));
+            Ok(Entry::Property(key, key_span, value))
+        } else {
+            Ok(Entry::Argument(
+                token.into_scalar(self.span_from(value_start)),
+            ))
+        }
+    }
+
+    /// Skip a `(type)` annotation and any whitespace after it, returning
+    /// whether one was present
+    fn skip_type_annotation(&mut self) -> Result<bool, KdlError> {
+        if self.peek() != Some('(') {
+            return Ok(false);
+        }
+        let start = self.mark();
+        self.next();
+        self.skip_node_space()?;
+        if !matches!(self.parse_value()?, Token::String(_)) {
+            return Err(self.error_at(start, "type annotations must be strings"));
+        }
+        self.skip_node_space()?;
+        if self.next() != Some(')') {
+            return Err(self.error_at(start, "unterminated type annotation"));
+        }
+        self.skip_node_space()?;
+        Ok(true)
+    }
+
+    /// Parse a string, number, or keyword
+    fn parse_value(&mut self) -> Result<Token, KdlError> {
+        let start = self.mark();
+        match self.peek() {
+            Some('"') => self.parse_quoted(start).map(Token::String),
+            Some('#') if matches!(self.peek_nth(1), Some('"' | '#')) => {
+                self.parse_raw(start).map(Token::String)
+            }
+            Some('#') => {
+                self.next();
+                match self.parse_bare().as_str() {
+                    "true" => Ok(Token::Bool(true)),
+                    "false" => Ok(Token::Bool(false)),
+                    "null" => Ok(Token::Null),
+                    "inf" => Ok(Token::Float(f64::INFINITY)),
+                    "-inf" => Ok(Token::Float(f64::NEG_INFINITY)),
+                    "nan" => Ok(Token::Float(f64::NAN)),
+                    word => Err(self.error_at(start, format!("unknown keyword `#{word}`"))),
+                }
+            }
+            _ => {
+                let word = self.parse_bare();
+                if word.is_empty() {
+                    let msg = match self.peek() {
+                        Some(c) => format!("unexpected character {c:?}"),
+                        None => String::from("unexpected end of input"),
+                    };
+                    Err(self.error_at(start, msg))
+                } else if is_number_start(&word) {
+                    parse_number(&word).ok_or_else(|| self.error_at(start, "invalid number"))
+                } else {
+                    // KDL v1 keywords are accepted for compatibility
+                    match word.as_str() {
+                        "true" => Ok(Token::Bool(true)),
+                        "false" => Ok(Token::Bool(false)),
+                        "null" => Ok(Token::Null),
+                        "inf" | "-inf" | "nan" => Err(self.error_at(
+                            start,
+                            format!("keyword `{word}` must be written as `#{word}`"),
+                        )),
+                        _ => Ok(Token::String(word)),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Parse a run of characters that can appear in an identifier string
+    fn parse_bare(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek().filter(|&c| is_identifier_char(c)) {
+            word.push(c);
+            self.next();
+        }
+        word
+    }
+
+    /// Parse a quoted string starting at `start`
+    fn parse_quoted(&mut self, start: Span) -> Result<String, KdlError> {
+        if self.eat("\"\"\"") {
+            let raw = self.parse_multiline(start, "\"\"\"", true)?;
+            return unescape(&raw, self.span_from(start));
+        }
+        self.next();
+        let mut raw = String::new();
+        loop {
+            // Peek so that an unterminated string's span stops before the
+            // newline
+            match self.peek() {
+                Some('"') => {
+                    self.next();
+                    return unescape(&raw, self.span_from(start));
+                }
+                Some('\\') => {
+                    self.next();
+                    raw.push('\\');
+                    match self.next() {
+                        Some(c) => raw.push(c),
+                        None => return Err(self.error_at(start, "unterminated string")),
+                    }
+                }
+                Some(c) if !is_newline(c) => {
+                    self.next();
+                    raw.push(c);
+                }
+                _ => return Err(self.error_at(start, "unterminated string")),
+            }
+        }
+    }
+
+    /// Parse a raw string starting at `start`
+    fn parse_raw(&mut self, start: Span) -> Result<String, KdlError> {
+        let mut hashes = 0;
+        while self.peek() == Some('#') {
+            self.next();
+            hashes += 1;
+        }
+        let close = "#".repeat(hashes);
+        if self.eat("\"\"\"") {
+            return self.parse_multiline(start, &format!("\"\"\"{close}"), false);
+        }
+        if self.next() != Some('"') {
+            return Err(self.error_at(start, "expected `\"` after `#`"));
+        }
+        let close = format!("\"{close}");
+        let mut s = String::new();
+        loop {
+            if self.eat(&close) {
+                return Ok(s);
+            }
+            match self.peek() {
+                Some(c) if !is_newline(c) => {
+                    self.next();
+                    s.push(c);
+                }
+                _ => return Err(self.error_at(start, "unterminated string")),
+            }
+        }
+    }
+
+    /// Parse the body of a multi-line string whose opening quotes have been
+    /// consumed, up to & including `close`.  If `escapes` is true, escape
+    /// sequences are skipped over (but left in the result) when looking for
+    /// `close`.  The returned string has the whitespace prefix of the closing
+    /// line removed from each line.
+    fn parse_multiline(
+        &mut self,
+        start: Span,
+        close: &str,
+        escapes: bool,
+    ) -> Result<String, KdlError> {
+        self.skip_whitespace();
+        if !self.peek().is_some_and(is_newline) {
+            return Err(self.error_at(start, "multi-line strings must start with a newline"));
+        }
+        self.next_newline();
+        let mut body = String::new();
+        loop {
+            if self.eat(close) {
+                break;
+            }
+            match self.peek() {
+                Some('\\') if escapes => {
+                    self.next();
+                    body.push('\\');
+                    if let Some(c) = self.peek().filter(|&c| !is_newline(c)) {
+                        self.next();
+                        body.push(c);
+                    }
+                }
+                Some(c) if is_newline(c) => {
+                    self.next_newline();
+                    body.push('\n');
+                }
+                Some(c) => {
+                    self.next();
+                    body.push(c);
+                }
+                None => return Err(self.error_at(start, "unterminated string")),
+            }
+        }
+        let (lines, prefix) = body.rsplit_once('\n').unwrap_or(("", body.as_str()));
+        if !prefix.chars().all(is_whitespace) {
+            return Err(self.error_at(
+                start,
+                "the closing quotes of a multi-line string must be on their own line",
+            ));
+        }
+        let mut out = String::new();
+        for (i, line) in lines.split('\n').enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            if line.chars().all(is_whitespace) {
+                continue;
+            }
+            match line.strip_prefix(prefix) {
+                Some(line) => out.push_str(line),
+                None => {
+                    return Err(self.error_at(
+                        start,
+                        "multi-line string lines must start with the closing line's indentation",
+                    ));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Consume a newline, treating CRLF as a single newline
+    fn next_newline(&mut self) {
+        if self.next() == Some('\r') && self.peek() == Some('\n') {
+            self.next();
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(is_whitespace) {
+            self.next();
+        }
+        self.pos > start
+    }
+
+    /// Skip whitespace, block comments, and line continuations within a
+    /// node, returning whether anything was skipped
+    fn skip_node_space(&mut self) -> Result<bool, KdlError> {
+        let start = self.pos;
+        loop {
+            if self.skip_whitespace() {
+                continue;
+            }
+            match (self.peek(), self.peek_nth(1)) {
+                (Some('/'), Some('*')) => self.skip_block_comment()?,
+                (Some('\\'), _) => {
+                    let start = self.mark();
+                    self.next();
+                    self.skip_whitespace();
+                    if self.peek() == Some('/') && self.peek_nth(1) == Some('/') {
+                        self.skip_line_comment();
+                    } else if self.peek().is_some_and(is_newline) {
+                        self.next_newline();
+                    } else if self.peek().is_some() {
+                        return Err(
+                            self.error_at(start, "expected a newline after line continuation")
+                        );
+                    }
+                }
+                _ => return Ok(self.pos > start),
+            }
+        }
+    }
+
+    /// Skip whitespace, newlines, and comments between nodes
+    fn skip_line_space(&mut self) -> Result<(), KdlError> {
+        loop {
+            self.skip_node_space()?;
+            match (self.peek(), self.peek_nth(1)) {
+                (Some('/'), Some('/')) => self.skip_line_comment(),
+                (Some(c), _) if is_newline(c) => {
+                    self.next();
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Skip a `//` comment and the newline that ends it
+    fn skip_line_comment(&mut self) {
+        while let Some(c) = self.next() {
+            if is_newline(c) {
+                if c == '\r' && self.peek() == Some('\n') {
+                    self.next();
+                }
+                break;
+            }
+        }
+    }
+
+    /// Skip a possibly-nested `/* */` comment
+    fn skip_block_comment(&mut self) -> Result<(), KdlError> {
+        let start = self.mark();
+        let mut depth = 0usize;
+        loop {
+            if self.eat("/*") {
+                depth += 1;
+            } else if self.eat("*/") {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if self.next().is_none() {
+                return Err(self.error_at(start, "unterminated block comment"));
+            }
+        }
+    }
+}
+
+/// A node argument or property
+enum Entry {
+    Argument(Scalar),
+    Property(String, Span, Scalar),
+}
+
+/// A parsed string, number, or keyword
+enum Token {
+    Null,
+    Bool(bool),
+    Integer(i128),
+    Float(f64),
+    String(String),
+}
+
+impl Token {
+    fn into_scalar(self, span: Span) -> Scalar {
+        let value = match self {
+            Token::Null => ScalarValue::Null,
+            Token::Bool(b) => ScalarValue::Bool(b),
+            Token::Integer(i) => ScalarValue::Integer(i),
+            Token::Float(f) => ScalarValue::Float(f),
+            Token::String(s) => ScalarValue::String(s),
+        };
+        Scalar { value, span }
+    }
+}
+
+/// Process the escape sequences in the contents of a quoted string that
+/// starts at `start`
+fn unescape(raw: &str, start: Span) -> Result<String, KdlError> {
+    let mut s = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            s.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => s.push('\n'),
+            Some('r') => s.push('\r'),
+            Some('t') => s.push('\t'),
+            Some('\\') => s.push('\\'),
+            Some('"') => s.push('"'),
+            Some('b') => s.push('\u{8}'),
+            Some('f') => s.push('\u{C}'),
+            Some('s') => s.push(' '),
+            Some('u') => {
+                let mut hex = String::new();
+                if chars.next() == Some('{') {
+                    while let Some(c) = chars.next_if(char::is_ascii_hexdigit) {
+                        hex.push(c);
+                    }
+                }
+                let c = (chars.next() == Some('}') && (1..=6).contains(&hex.len()))
+                    .then(|| u32::from_str_radix(&hex, 16).ok())
+                    .flatten()
+                    .and_then(char::from_u32);
+                match c {
+                    Some(c) => s.push(c),
+                    None => return Err(start.error("invalid unicode escape")),
+                }
+            }
+            Some(c) if is_whitespace(c) || is_newline(c) => {
+                while chars
+                    .next_if(|&c| is_whitespace(c) || is_newline(c))
+                    .is_some()
+                {}
+            }
+            _ => return Err(start.error("invalid escape sequence")),
+        }
+    }
+    Ok(s)
+}
+
+/// Test whether a bare word should be parsed as a number
+fn is_number_start(word: &str) -> bool {
+    let digits = word.strip_prefix(['+', '-']).unwrap_or(word);
+    let digits = digits.strip_prefix('.').unwrap_or(digits);
+    digits.starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn parse_number(word: &str) -> Option<Token> {
+    let (negative, unsigned) = match word.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, word.strip_prefix('+').unwrap_or(word)),
+    };
+    let radix = [("0x", 16), ("0o", 8), ("0b", 2)]
+        .into_iter()
+        .find_map(|(prefix, radix)| unsigned.strip_prefix(prefix).map(|rest| (rest, radix)));
+    if let Some((digits, radix)) = radix {
+        if !digits.starts_with(|c: char| c.is_digit(radix)) {
+            return None;
+        }
+        let digits = digits.replace('_', "");
+        let sign = if negative { "-" } else { "" };
+        return i128::from_str_radix(&format!("{sign}{digits}"), radix)
+            .ok()
+            .map(Token::Integer);
+    }
+    if !unsigned.starts_with(|c: char| c.is_ascii_digit())
+        || unsigned.split(['.', 'e', 'E']).skip(1).any(|part| {
+            !part
+                .trim_start_matches(['+', '-'])
+                .starts_with(|c: char| c.is_ascii_digit())
+        })
+    {
+        return None;
+    }
+    let number = word.replace('_', "");
+    if unsigned.contains(['.', 'e', 'E']) {
+        number.parse::<f64>().ok().map(Token::Float)
+    } else {
+        number.parse::<i128>().ok().map(Token::Integer)
+    }
+}
+
+/// Returns whether `c` is non-newline whitespace
+fn is_whitespace(c: char) -> bool {
+    matches!(
+        c,
+        '\t' | ' ' | '\u{A0}' | '\u{1680}' | '\u{2000}'
+            ..='\u{200A}' | '\u{202F}' | '\u{205F}' | '\u{3000}'
+    )
+}
+
+fn is_newline(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\u{B}' | '\u{C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+fn is_identifier_char(c: char) -> bool {
+    !(is_whitespace(c)
+        || is_newline(c)
+        || c.is_control()
+        || matches!(
+            c,
+            '\\' | '/' | '(' | ')' | '{' | '}' | ';' | '[' | ']' | '"' | '#' | '='
+        ))
+}
+
+/// A value being serialized as KDL
+enum Tree {
+    /// A value written as a single KDL value, e.g. `#true` or `"foo"`
+    Scalar(String),
+    Seq(Vec<Tree>),
+    Map(Vec<(String, Tree)>),
+}
+
+impl Tree {
+    fn is_scalar(&self) -> bool {
+        matches!(self, Tree::Scalar(_))
+    }
+}
+
+/// Write a node named `name` with the given value at the given depth
+fn write_node(out: &mut String, depth: usize, name: &str, value: Tree) {
+    indent(out, depth);
+    write_string(out, name);
+    match value {
+        Tree::Scalar(s) => {
+            out.push(' ');
+            out.push_str(&s);
+            out.push('\n');
+        }
+        Tree::Seq(items) if items.iter().all(Tree::is_scalar) => {
+            for item in items {
+                if let Tree::Scalar(s) = item {
+                    out.push(' ');
+                    out.push_str(&s);
+                }
+            }
+            out.push('\n');
+        }
+        Tree::Seq(items) => {
+            out.push_str(" {\n");
+            for item in items {
+                write_item(out, depth + 1, item);
+            }
+            indent(out, depth);
+            out.push_str("}\n");
+        }
+        Tree::Map(entries) if entries.is_empty() => out.push_str(" {}\n"),
+        Tree::Map(entries) => {
+            out.push_str(" {\n");
+            for (key, value) in entries {
+                write_node(out, depth + 1, &key, value);
+            }
+            indent(out, depth);
+            out.push_str("}\n");
+        }
+    }
+}
+
+/// Write an element of a sequence as a node named `-`.  Non-empty maps of
+/// scalars are written as properties.
+fn write_item(out: &mut String, depth: usize, value: Tree) {
+    match value {
+        Tree::Map(entries) if !entries.is_empty() && entries.iter().all(|(_, v)| v.is_scalar()) => {
+            indent(out, depth);
+            out.push('-');
+            for (key, value) in entries {
+                if let Tree::Scalar(s) = value {
+                    out.push(' ');
+                    write_string(out, &key);
+                    out.push('=');
+                    out.push_str(&s);
+                }
+            }
+            out.push('\n');
+        }
+        value => write_node(out, depth, "-", value),
+    }
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("    ");
+    }
+}
+
+/// Write a string as an identifier string if possible and as a quoted string
+/// otherwise
+fn write_string(out: &mut String, s: &str) {
+    if s == "-" || is_plain_identifier(s) {
+        out.push_str(s);
+        return;
+    }
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{C}' => out.push_str("\\f"),
+            c if c.is_control()
+                || is_newline(c)
+                || matches!(
+                    c,
+                    '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' | '\u{FEFF}'
+                ) =>
+            {
+                // Writing to a `String` cannot fail
+                let _ = write!(out, "\\u{{{:x}}}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Test whether `s` can be written as an identifier string without being
+/// mistaken for anything else.  This is deliberately more restrictive than
+/// the KDL grammar.
+fn is_plain_identifier(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !matches!(s, "true" | "false" | "null" | "inf" | "nan")
+}
+
+fn string_scalar(s: &str) -> Tree {
+    let mut out = String::new();
+    write_string(&mut out, s);
+    Tree::Scalar(out)
+}
+
+/// Return the scalar for a float whose `Debug` representation is `text`
+fn float_scalar(text: String, value: f64) -> Tree {
+    Tree::Scalar(if value.is_finite() {
+        text
+    } else if value.is_nan() {
+        String::from("#nan")
+    } else if value > 0.0 {
+        String::from("#inf")
+    } else {
+        String::from("#-inf")
+    })
+}
+
+/// A [`Serializer`] that converts values to [`Tree`]s
+struct TreeSerializer;
+
+impl Serializer for TreeSerializer {
+    type Ok = Tree;
+    type Error = KdlError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(String::from(if v {
+            "#true"
+        } else {
+            "#false"
+        })))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(v.to_string()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(v.to_string()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(v.to_string()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(v.to_string()))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(v.to_string()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(v.to_string()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(v.to_string()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(v.to_string()))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Tree, KdlError> {
+        if i128::try_from(v).is_err() {
+            return Err(ser::Error::custom("integer is too large for KDL"));
+        }
+        Ok(Tree::Scalar(v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Tree, KdlError> {
+        Ok(float_scalar(format!("{v:?}"), f64::from(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Tree, KdlError> {
+        Ok(float_scalar(format!("{v:?}"), v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Tree, KdlError> {
+        Ok(string_scalar(v.encode_utf8(&mut [0; 4])))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Tree, KdlError> {
+        Ok(string_scalar(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Tree, KdlError> {
+        Ok(Tree::Seq(
+            v.iter().map(|b| Tree::Scalar(b.to_string())).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(String::from("#null")))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Tree, KdlError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Tree, KdlError> {
+        Ok(Tree::Scalar(String::from("#null")))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Tree, KdlError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Tree, KdlError> {
+        Ok(string_scalar(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Tree, KdlError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Tree, KdlError> {
+        Ok(Tree::Map(vec![(
+            variant.to_owned(),
+            value.serialize(TreeSerializer)?,
+        )]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, KdlError> {
+        Ok(SeqSerializer {
+            items: Vec::with_capacity(len.unwrap_or_default()),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, KdlError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, KdlError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, KdlError> {
+        Ok(SeqSerializer {
+            items: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, KdlError> {
+        Ok(MapSerializer {
+            entries: Vec::with_capacity(len.unwrap_or_default()),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer, KdlError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<MapSerializer, KdlError> {
+        Ok(MapSerializer {
+            entries: Vec::with_capacity(len),
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+/// Wrap `value` in a single-entry map if it is the content of an enum
+/// variant
+fn wrap_variant(variant: Option<&'static str>, value: Tree) -> Tree {
+    match variant {
+        Some(variant) => Tree::Map(vec![(variant.to_owned(), value)]),
+        None => value,
+    }
+}
+
+/// Serializer for the elements of a sequence, tuple, or tuple variant
+struct SeqSerializer {
+    items: Vec<Tree>,
+    variant: Option<&'static str>,
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Tree;
+    type Error = KdlError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), KdlError> {
+        self.items.push(value.serialize(TreeSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Tree, KdlError> {
+        Ok(wrap_variant(self.variant, Tree::Seq(self.items)))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Tree;
+    type Error = KdlError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), KdlError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Tree, KdlError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Tree;
+    type Error = KdlError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), KdlError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Tree, KdlError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Tree;
+    type Error = KdlError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), KdlError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Tree, KdlError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Serializer for the entries of a map, struct, or struct variant
+struct MapSerializer {
+    entries: Vec<(String, Tree)>,
+    key: Option<String>,
+    variant: Option<&'static str>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Tree;
+    type Error = KdlError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), KdlError> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), KdlError> {
+        let Some(key) = self.key.take() else {
+            return Err(ser::Error::custom("value serialized before key"));
+        };
+        self.entries.push((key, value.serialize(TreeSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Tree, KdlError> {
+        if !self.entries.is_empty() && self.entries.iter().all(|(key, _)| key == "-") {
+            return Err(ser::Error::custom(
+                "maps whose keys are all \"-\" cannot be represented in KDL",
+            ));
+        }
+        Ok(wrap_variant(self.variant, Tree::Map(self.entries)))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Tree;
+    type Error = KdlError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), KdlError> {
+        self.entries
+            .push((key.to_owned(), value.serialize(TreeSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Tree, KdlError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Tree;
+    type Error = KdlError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), KdlError> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Tree, KdlError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// A [`Serializer`] for map keys, which must be strings, characters,
+/// integers, or unit variants
+struct KeySerializer;
+
+impl KeySerializer {
+    fn error() -> KdlError {
+        ser::Error::custom("map keys must be strings")
+    }
+}
+
+impl Serializer for KeySerializer {
+    type Ok = String;
+    type Error = KdlError;
+    type SerializeSeq = Impossible<String, KdlError>;
+    type SerializeTuple = Impossible<String, KdlError>;
+    type SerializeTupleStruct = Impossible<String, KdlError>;
+    type SerializeTupleVariant = Impossible<String, KdlError>;
+    type SerializeMap = Impossible<String, KdlError>;
+    type SerializeStruct = Impossible<String, KdlError>;
+    type SerializeStructVariant = Impossible<String, KdlError>;
+
+    fn serialize_bool(self, _v: bool) -> Result<String, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String, KdlError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String, KdlError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String, KdlError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String, KdlError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<String, KdlError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String, KdlError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String, KdlError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String, KdlError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String, KdlError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<String, KdlError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, KdlError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, KdlError> {
+        Ok(v.to_owned())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_none(self) -> Result<String, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<String, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_unit(self) -> Result<String, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, KdlError> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, KdlError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, KdlError> {
+        Err(Self::error())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, KdlError> {
+        Err(Self::error())
+    }
+}
@@ -0,0 +1,984 @@
+//! Support shared by the formats whose documents are trees of keys with
+//! untyped string values, such as INI
+use crate::locate::{LocatedError, Location};
+use crate::scalar::{ScalarDeserializer, ScalarError, at};
+use indexmap::IndexMap;
+use serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    Unexpected, VariantAccess, Visitor,
+};
+use serde::ser::{self, Impossible, Serialize, Serializer};
+use serde_path_to_error::{Error as PathError, serialize as serpath};
+use std::marker::PhantomData;
+
+/// Trait for the error types of key-value formats
+pub(crate) trait KeyValueError: ScalarError + ser::Error {}
+
+impl KeyValueError for LocatedError {}
+
+/// A parsed key-value document
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Node {
+    /// A string value and the position at which it starts
+    Scalar(String, Location),
+
+    /// A map of keys to values and the position of the map's header, if it
+    /// has one
+    Map(IndexMap<String, Node>, Option<Location>),
+}
+
+/// A [`Deserializer`] for a [`Node`] that coerces string values to whatever
+/// types are requested.
+///
+/// When asked to deserialize a string value as any type, the value is parsed
+/// as a boolean, integer, or float if possible and `parse_values` is true.
+/// Booleans may also be written as `yes`/`no`, `on`/`off`, or `1`/`0`; empty
+/// strings deserialize as `None` and `()`; and strings deserialized as
+/// sequences are split on commas.
+pub(crate) struct NodeDeserializer<E> {
+    node: Node,
+    parse_values: bool,
+    error: PhantomData<E>,
+}
+
+impl<E> NodeDeserializer<E> {
+    pub(crate) fn new(node: Node, parse_values: bool) -> Self {
+        NodeDeserializer {
+            node,
+            parse_values,
+            error: PhantomData,
+        }
+    }
+}
+
+macro_rules! forward_to_scalar {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+                match self.node {
+                    Node::Scalar(value, location) => {
+                        ScalarDeserializer::new(value, Some(location), self.parse_values)
+                            .$method(visitor)
+                    }
+                    Node::Map(..) => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de, E: KeyValueError> Deserializer<'de> for NodeDeserializer<E> {
+    type Error = E;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        match self.node {
+            Node::Scalar(value, location) => {
+                ScalarDeserializer::new(value, Some(location), self.parse_values)
+                    .deserialize_any(visitor)
+            }
+            Node::Map(entries, location) => at(
+                visitor.visit_map(NodeMapAccess {
+                    iter: entries.into_iter(),
+                    value: None,
+                    parse_values: self.parse_values,
+                    error: PhantomData,
+                }),
+                location,
+            ),
+        }
+    }
+
+    forward_to_scalar! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_i128 deserialize_u8 deserialize_u16
+        deserialize_u32 deserialize_u64 deserialize_u128 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_unit
+        deserialize_map deserialize_identifier
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        match self.node {
+            Node::Scalar(value, location) => {
+                ScalarDeserializer::new(value, Some(location), self.parse_values)
+                    .deserialize_seq(visitor)
+            }
+            Node::Map(entries, location) => {
+                // A map whose keys are all indices is a sequence of its values
+                let mut items = Vec::with_capacity(entries.len());
+                for (key, node) in entries {
+                    let Ok(i) = key.parse::<usize>() else {
+                        return at(
+                            Err(de::Error::invalid_type(Unexpected::Map, &visitor)),
+                            location,
+                        );
+                    };
+                    items.push((i, node));
+                }
+                items.sort_by_key(|&(i, _)| i);
+                at(
+                    visitor.visit_seq(NodeSeqAccess {
+                        iter: items.into_iter(),
+                        parse_values: self.parse_values,
+                        error: PhantomData,
+                    }),
+                    location,
+                )
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        match self.node {
+            Node::Scalar(value, location) => {
+                ScalarDeserializer::new(value, Some(location), self.parse_values)
+                    .deserialize_option(visitor)
+            }
+            Node::Map(..) => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, E> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        match self.node {
+            Node::Scalar(value, location) => {
+                ScalarDeserializer::new(value, Some(location), self.parse_values)
+                    .deserialize_enum(name, variants, visitor)
+            }
+            Node::Map(entries, location) => {
+                if entries.len() != 1 {
+                    return at(
+                        Err(de::Error::invalid_value(
+                            Unexpected::Map,
+                            &"map with a single key",
+                        )),
+                        location,
+                    );
+                }
+                let Some((variant, node)) = entries.into_iter().next() else {
+                    unreachable!("map should have exactly one entry");
+                };
+                at(
+                    visitor.visit_enum(NodeEnumAccess {
+                        variant,
+                        node,
+                        parse_values: self.parse_values,
+                        error: PhantomData,
+                    }),
+                    location,
+                )
+            }
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_unit()
+    }
+}
+
+/// A [`MapAccess`] over the entries of a [`Node::Map`]
+struct NodeMapAccess<E> {
+    iter: indexmap::map::IntoIter<String, Node>,
+    value: Option<Node>,
+    parse_values: bool,
+    error: PhantomData<E>,
+}
+
+impl<'de, E: KeyValueError> MapAccess<'de> for NodeMapAccess<E> {
+    type Error = E;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, E> {
+        let Some((key, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+        let location = match &value {
+            Node::Scalar(_, location) => Some(*location),
+            Node::Map(_, location) => *location,
+        };
+        self.value = Some(value);
+        seed.deserialize(ScalarDeserializer::new(key, location, false))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, E> {
+        let Some(value) = self.value.take() else {
+            return Err(de::Error::custom("value requested before key"));
+        };
+        seed.deserialize(NodeDeserializer::new(value, self.parse_values))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// A [`SeqAccess`] over the values of a [`Node::Map`] whose keys are indices,
+/// sorted by index
+struct NodeSeqAccess<E> {
+    iter: std::vec::IntoIter<(usize, Node)>,
+    parse_values: bool,
+    error: PhantomData<E>,
+}
+
+impl<'de, E: KeyValueError> SeqAccess<'de> for NodeSeqAccess<E> {
+    type Error = E;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, E> {
+        match self.iter.next() {
+            Some((_, node)) => seed
+                .deserialize(NodeDeserializer::new(node, self.parse_values))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// An [`EnumAccess`] for a single-entry [`Node::Map`]
+struct NodeEnumAccess<E> {
+    variant: String,
+    node: Node,
+    parse_values: bool,
+    error: PhantomData<E>,
+}
+
+impl<'de, E: KeyValueError> EnumAccess<'de> for NodeEnumAccess<E> {
+    type Error = E;
+    type Variant = NodeDeserializer<E>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, NodeDeserializer<E>), E> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, NodeDeserializer::new(self.node, self.parse_values)))
+    }
+}
+
+impl<'de, E: KeyValueError> VariantAccess<'de> for NodeDeserializer<E> {
+    type Error = E;
+
+    fn unit_variant(self) -> Result<(), E> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, E> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, E> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        self.deserialize_map(visitor)
+    }
+}
+
+/// The shapes of documents that a key-value format can represent
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Shape {
+    /// The maximum number of keys in a path to a value; 1 means that
+    /// documents are flat
+    pub(crate) max_depth: usize,
+
+    /// Whether the top-level map may contain non-map values when
+    /// `max_depth` is greater than 1
+    pub(crate) root_scalars: bool,
+
+    /// Whether string values may contain line breaks
+    pub(crate) multiline: bool,
+
+    /// Whether sequences with elements that cannot be joined with commas
+    /// (i.e., non-scalars and strings containing commas) may be serialized as
+    /// maps from indices to elements
+    pub(crate) indexed_seqs: bool,
+
+    /// Check whether a key at the given 0-based depth can be represented,
+    /// returning an error message if not
+    pub(crate) check_key: fn(&str, usize) -> Result<(), String>,
+}
+
+/// A value in a flattened key-value document
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Entry {
+    /// The keys leading to the value
+    pub(crate) path: Vec<String>,
+
+    /// The value, or `None` for an empty map
+    pub(crate) value: Option<String>,
+}
+
+/// Serialize `value` as a list of paths to string values, failing if it does
+/// not fit `shape`.
+///
+/// Sequences of scalars are joined with commas (or, if that is not possible
+/// and `shape` allows it, serialized as maps from indices to elements), and
+/// `None` and unit values are omitted.
+pub(crate) fn flatten<T, E>(value: &T, shape: &Shape) -> Result<Vec<Entry>, PathError<E>>
+where
+    T: Serialize + ?Sized,
+    E: KeyValueError,
+{
+    let mut entries = Vec::new();
+    serpath(
+        value,
+        FlatSerializer {
+            entries: &mut entries,
+            path: Vec::new(),
+            shape,
+            error: PhantomData,
+        },
+    )?;
+    Ok(entries)
+}
+
+/// A [`Serializer`] that adds the values it serializes to a list of
+/// [`Entry`]s
+struct FlatSerializer<'a, E> {
+    entries: &'a mut Vec<Entry>,
+    path: Vec<String>,
+    shape: &'a Shape,
+    error: PhantomData<E>,
+}
+
+impl<'a, E: KeyValueError> FlatSerializer<'a, E> {
+    fn error(msg: &str) -> E {
+        ser::Error::custom(msg)
+    }
+
+    fn scalar(self, value: String) -> Result<(), E> {
+        if self.path.is_empty() {
+            return Err(Self::error("top level of document must be a map"));
+        }
+        if self.path.len() == 1 && self.shape.max_depth > 1 && !self.shape.root_scalars {
+            return Err(Self::error("top-level values must be maps"));
+        }
+        if !self.shape.multiline && value.contains(['\n', '\r']) {
+            return Err(Self::error("strings cannot contain line breaks"));
+        }
+        self.entries.push(Entry {
+            path: self.path,
+            value: Some(value),
+        });
+        Ok(())
+    }
+
+    /// Return a serializer for the value at `key` within the current value
+    fn child(&mut self, key: String) -> Result<FlatSerializer<'_, E>, E> {
+        (self.shape.check_key)(&key, self.path.len()).map_err(|msg| Self::error(&msg))?;
+        let mut path = self.path.clone();
+        path.push(key);
+        Ok(FlatSerializer {
+            entries: self.entries,
+            path,
+            shape: self.shape,
+            error: PhantomData,
+        })
+    }
+
+    fn map(self) -> Result<FlatMap<'a, E>, E> {
+        if self.path.len() >= self.shape.max_depth {
+            return Err(Self::error("maps cannot be nested this deeply"));
+        }
+        Ok(FlatMap {
+            start: self.entries.len(),
+            ser: self,
+            key: None,
+        })
+    }
+
+    fn list(self) -> FlatList<'a, E> {
+        FlatList {
+            ser: self,
+            items: Vec::new(),
+            len: 0,
+            indexed: false,
+        }
+    }
+}
+
+impl<'a, E: KeyValueError> Serializer for FlatSerializer<'a, E> {
+    type Ok = ();
+    type Error = E;
+    type SerializeSeq = FlatList<'a, E>;
+    type SerializeTuple = FlatList<'a, E>;
+    type SerializeTupleStruct = FlatList<'a, E>;
+    type SerializeTupleVariant = FlatList<'a, E>;
+    type SerializeMap = FlatMap<'a, E>;
+    type SerializeStruct = FlatMap<'a, E>;
+    type SerializeStructVariant = FlatMap<'a, E>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), E> {
+        self.scalar(format!("{v:?}"))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), E> {
+        self.scalar(format!("{v:?}"))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), E> {
+        self.scalar(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), E> {
+        self.scalar(v.to_owned())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), E> {
+        Err(Self::error("byte strings are not supported"))
+    }
+
+    fn serialize_none(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), E> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), E> {
+        self.scalar(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), E> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), E> {
+        let mut map = self.map()?;
+        value.serialize(map.ser.child(variant.to_owned())?)?;
+        map.finish();
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<FlatList<'a, E>, E> {
+        Ok(self.list())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<FlatList<'a, E>, E> {
+        Ok(self.list())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<FlatList<'a, E>, E> {
+        Ok(self.list())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<FlatList<'a, E>, E> {
+        // The variant's map has to be closed after the list is serialized,
+        // so nest the list under the variant name directly.
+        if self.path.len() >= self.shape.max_depth {
+            return Err(Self::error("maps cannot be nested this deeply"));
+        }
+        let mut ser = self;
+        let child = ser.child(variant.to_owned())?;
+        let path = child.path;
+        Ok(FlatSerializer {
+            entries: ser.entries,
+            path,
+            shape: ser.shape,
+            error: PhantomData,
+        }
+        .list())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<FlatMap<'a, E>, E> {
+        self.map()
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<FlatMap<'a, E>, E> {
+        self.map()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<FlatMap<'a, E>, E> {
+        if self.path.len() >= self.shape.max_depth {
+            return Err(Self::error("maps cannot be nested this deeply"));
+        }
+        let mut ser = self;
+        let child = ser.child(variant.to_owned())?;
+        let path = child.path;
+        FlatSerializer {
+            entries: ser.entries,
+            path,
+            shape: ser.shape,
+            error: PhantomData,
+        }
+        .map()
+    }
+}
+
+/// Serializer for the entries of a map or struct
+struct FlatMap<'a, E> {
+    ser: FlatSerializer<'a, E>,
+    key: Option<String>,
+    /// The number of entries before the map was started
+    start: usize,
+}
+
+impl<E: KeyValueError> FlatMap<'_, E> {
+    /// Record the map as an empty map if none of its values produced entries
+    fn finish(self) {
+        if self.ser.entries.len() == self.start && !self.ser.path.is_empty() {
+            self.ser.entries.push(Entry {
+                path: self.ser.path,
+                value: None,
+            });
+        }
+    }
+}
+
+impl<E: KeyValueError> ser::SerializeMap for FlatMap<'_, E> {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), E> {
+        self.key = Some(key.serialize(StringSerializer::new("map keys must be strings"))?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), E> {
+        let Some(key) = self.key.take() else {
+            return Err(ser::Error::custom("value serialized before key"));
+        };
+        value.serialize(self.ser.child(key)?)
+    }
+
+    fn end(self) -> Result<(), E> {
+        self.finish();
+        Ok(())
+    }
+}
+
+impl<E: KeyValueError> ser::SerializeStruct for FlatMap<'_, E> {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), E> {
+        value.serialize(self.ser.child(key.to_owned())?)
+    }
+
+    fn end(self) -> Result<(), E> {
+        self.finish();
+        Ok(())
+    }
+}
+
+impl<E: KeyValueError> ser::SerializeStructVariant for FlatMap<'_, E> {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), E> {
+        value.serialize(self.ser.child(key.to_owned())?)
+    }
+
+    fn end(self) -> Result<(), E> {
+        self.finish();
+        Ok(())
+    }
+}
+
+/// Serializer for the elements of a sequence, which are joined with commas
+/// or, once an element is encountered that cannot be joined, serialized as
+/// the values of a map from indices
+struct FlatList<'a, E> {
+    ser: FlatSerializer<'a, E>,
+    items: Vec<String>,
+    /// The number of elements serialized so far
+    len: usize,
+    /// Whether the elements are being serialized as a map from indices
+    indexed: bool,
+}
+
+impl<E: KeyValueError> FlatList<'_, E> {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), E> {
+        let index = self.len;
+        self.len += 1;
+        if !self.indexed {
+            let r = value.serialize(StringSerializer::new("sequences can only contain scalars"));
+            match r {
+                Ok(item) if !item.contains(',') => {
+                    self.items.push(item);
+                    return Ok(());
+                }
+                Ok(_) if !self.ser.shape.indexed_seqs => {
+                    return Err(ser::Error::custom(
+                        "sequence elements cannot contain commas",
+                    ));
+                }
+                Err(e) if !self.ser.shape.indexed_seqs => return Err(e),
+                _ => self.start_indexing()?,
+            }
+        }
+        value.serialize(self.ser.child(index.to_string())?)
+    }
+
+    /// Switch to serializing elements as a map from indices, moving any
+    /// elements already serialized into the map
+    fn start_indexing(&mut self) -> Result<(), E> {
+        if self.ser.path.len() >= self.ser.shape.max_depth {
+            return Err(ser::Error::custom("maps cannot be nested this deeply"));
+        }
+        self.indexed = true;
+        for (i, item) in std::mem::take(&mut self.items).into_iter().enumerate() {
+            self.ser.child(i.to_string())?.scalar(item)?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), E> {
+        if self.indexed {
+            Ok(())
+        } else {
+            let value = self.items.join(", ");
+            self.ser.scalar(value)
+        }
+    }
+}
+
+impl<E: KeyValueError> ser::SerializeSeq for FlatList<'_, E> {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), E> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), E> {
+        self.finish()
+    }
+}
+
+impl<E: KeyValueError> ser::SerializeTuple for FlatList<'_, E> {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), E> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), E> {
+        self.finish()
+    }
+}
+
+impl<E: KeyValueError> ser::SerializeTupleStruct for FlatList<'_, E> {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), E> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), E> {
+        self.finish()
+    }
+}
+
+impl<E: KeyValueError> ser::SerializeTupleVariant for FlatList<'_, E> {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), E> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), E> {
+        self.finish()
+    }
+}
+
+/// A [`Serializer`] that converts scalars to strings and fails with a given
+/// message on anything else
+struct StringSerializer<E> {
+    msg: &'static str,
+    error: PhantomData<E>,
+}
+
+impl<E: KeyValueError> StringSerializer<E> {
+    fn new(msg: &'static str) -> Self {
+        StringSerializer {
+            msg,
+            error: PhantomData,
+        }
+    }
+
+    fn fail<T>(&self) -> Result<T, E> {
+        Err(ser::Error::custom(self.msg))
+    }
+}
+
+macro_rules! serialize_display {
+    ($($method:ident($ty:ty))*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<String, E> {
+                Ok(v.to_string())
+            }
+        )*
+    };
+}
+
+impl<E: KeyValueError> Serializer for StringSerializer<E> {
+    type Ok = String;
+    type Error = E;
+    type SerializeSeq = Impossible<String, E>;
+    type SerializeTuple = Impossible<String, E>;
+    type SerializeTupleStruct = Impossible<String, E>;
+    type SerializeTupleVariant = Impossible<String, E>;
+    type SerializeMap = Impossible<String, E>;
+    type SerializeStruct = Impossible<String, E>;
+    type SerializeStructVariant = Impossible<String, E>;
+
+    serialize_display! {
+        serialize_bool(bool) serialize_i8(i8) serialize_i16(i16)
+        serialize_i32(i32) serialize_i64(i64) serialize_i128(i128)
+        serialize_u8(u8) serialize_u16(u16) serialize_u32(u32)
+        serialize_u64(u64) serialize_u128(u128) serialize_char(char)
+        serialize_str(&str)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<String, E> {
+        Ok(format!("{v:?}"))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<String, E> {
+        Ok(format!("{v:?}"))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, E> {
+        self.fail()
+    }
+
+    fn serialize_none(self) -> Result<String, E> {
+        self.fail()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String, E> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, E> {
+        self.fail()
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, E> {
+        self.fail()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, E> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, E> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, E> {
+        self.fail()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, E> {
+        self.fail()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, E> {
+        self.fail()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, E> {
+        self.fail()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, E> {
+        self.fail()
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, E> {
+        self.fail()
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, E> {
+        self.fail()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, E> {
+        self.fail()
+    }
+}
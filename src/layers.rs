@@ -0,0 +1,707 @@
+//! Loading configuration from multiple files merged on top of each other
+use crate::env::{EnvStrings, EnvTree, OverlayDeserializer};
+use crate::locate::{Location, Segment, display_path, locate};
+use crate::value::{Map, Value, ValueError};
+use crate::{AnyFormat, Cfgfifo, EnvError, EnvSource, LoadError, read_to_string};
+use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
+use serde_path_to_error::{Error as PathError, deserialize as depath};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// A loader that deserializes a value from multiple configuration files
+/// merged on top of each other.
+///
+/// Each file (or "layer") is loaded with a [`Cfgfifo`] into an untyped tree,
+/// the trees are merged in the order that the layers were added, with later
+/// layers taking precedence over earlier ones, and the merged tree is
+/// deserialized into the requested type.  Each layer may be in any format
+/// supported by the `Cfgfifo`.
+///
+/// Trees are merged according to the following rules:
+///
+/// - When both layers have a map at the same position, the maps are merged
+///   recursively: keys present in only one layer are kept, and values for
+///   keys present in both are merged.  Keys are kept in the order in which
+///   they were first seen.
+///
+/// - When both layers have an array at the same position, the later array
+///   either replaces the earlier one or is appended to it, depending on the
+///   [`ArrayMerge`] setting.
+///
+/// - A null value in a later layer is handled according to the
+///   [`NullMerge`] setting.
+///
+/// - In all other cases, the later value replaces the earlier one.
+///
+/// A layer whose top-level value is null (e.g., an empty YAML file) is
+/// skipped.  If no layers are loaded, the value is deserialized from an empty
+/// map.
+#[cfg_attr(all(feature = "json", feature = "toml"), doc = concat!(
+    "# Example\n",
+    "\n",
+    "```\n",
+    "use cfgfifo::Layers;\n",
+    "use serde::Deserialize;\n",
+    "\n",
+    "#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]\n",
+    "struct Config {\n",
+    "    name: String,\n",
+    "    port: u16,\n",
+    "    tags: Vec<String>,\n",
+    "}\n",
+    "\n",
+    "let tmpdir = tempfile::tempdir().unwrap();\n",
+    "let system = tmpdir.path().join(\"system.toml\");\n",
+    "std::fs::write(&system, \"name = 'system'\\nport = 80\\ntags = ['a']\\n\").unwrap();\n",
+    "let user = tmpdir.path().join(\"user.json\");\n",
+    "std::fs::write(&user, r#\"{\"port\": 8080, \"tags\": [\"b\"]}\"#).unwrap();\n",
+    "\n",
+    "let cfg: Config = Layers::new()\n",
+    "    .file(&system)\n",
+    "    .file(&user)\n",
+    "    .optional_file(tmpdir.path().join(\"project.yaml\"))\n",
+    "    .load()\n",
+    "    .unwrap();\n",
+    "\n",
+    "assert_eq!(\n",
+    "    cfg,\n",
+    "    Config {\n",
+    "        name: String::from(\"system\"),\n",
+    "        port: 8080,\n",
+    "        tags: vec![String::from(\"b\")],\n",
+    "    }\n",
+    ");\n",
+    "```\n",
+))]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Layers {
+    cfgfifo: Cfgfifo,
+    sources: Vec<Layer>,
+    arrays: ArrayMerge,
+    nulls: NullMerge,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Layer {
+    File { path: PathBuf, optional: bool },
+    Env(EnvSource),
+}
+
+impl Layers {
+    /// Create a new `Layers` instance with no layers that loads files with
+    /// the default [`Cfgfifo`]
+    pub fn new() -> Layers {
+        Layers::default()
+    }
+
+    /// Set the [`Cfgfifo`] used to identify the formats of & load the layers
+    pub fn cfgfifo(mut self, cfgfifo: Cfgfifo) -> Self {
+        self.cfgfifo = cfgfifo;
+        self
+    }
+
+    /// Add a file as a layer on top of the layers added so far.
+    ///
+    /// It is an error for the file to not exist when loading.
+    pub fn file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.sources.push(Layer::File {
+            path: path.into(),
+            optional: false,
+        });
+        self
+    }
+
+    /// Add a file as a layer on top of the layers added so far, skipping it
+    /// if it does not exist when loading
+    pub fn optional_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.sources.push(Layer::File {
+            path: path.into(),
+            optional: true,
+        });
+        self
+    }
+
+    /// Add the values of the environment variables matched by an
+    /// [`EnvSource`] as a layer on top of the layers added so far.
+    ///
+    /// The environment is read when loading.
+    pub fn env(mut self, env: EnvSource) -> Self {
+        self.sources.push(Layer::Env(env));
+        self
+    }
+
+    /// Set how arrays in later layers are merged with arrays in earlier
+    /// layers.
+    ///
+    /// The default is [`ArrayMerge::Replace`].
+    pub fn arrays(mut self, arrays: ArrayMerge) -> Self {
+        self.arrays = arrays;
+        self
+    }
+
+    /// Set how null values in later layers are merged with earlier layers.
+    ///
+    /// The default is [`NullMerge::Replace`].
+    pub fn nulls(mut self, nulls: NullMerge) -> Self {
+        self.nulls = nulls;
+        self
+    }
+
+    /// Load & merge the layers and deserialize the result
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a non-optional layer does not exist, if loading
+    /// any layer fails, or if deserializing the merged tree fails.
+    pub fn load<T: DeserializeOwned>(&self) -> Result<T, LayerError> {
+        let (tree, tracker) = self.load_tree()?;
+        tracker.deserialize(tree)
+    }
+
+    /// Load & merge the layers and deserialize the result, also returning a
+    /// [`Provenance`] that records which layer each value in the merged tree
+    /// came from
+    #[cfg_attr(all(feature = "json", feature = "toml"), doc = concat!(
+        "# Example\n",
+        "\n",
+        "```\n",
+        "use cfgfifo::{Layers, Origin};\n",
+        "use serde::Deserialize;\n",
+        "\n",
+        "#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]\n",
+        "struct Config {\n",
+        "    name: String,\n",
+        "    port: u16,\n",
+        "}\n",
+        "\n",
+        "let tmpdir = tempfile::tempdir().unwrap();\n",
+        "let system = tmpdir.path().join(\"system.toml\");\n",
+        "std::fs::write(&system, \"name = 'system'\\nport = 80\\n\").unwrap();\n",
+        "let user = tmpdir.path().join(\"user.json\");\n",
+        "std::fs::write(&user, \"{\\n  \\\"port\\\": 8080\\n}\\n\").unwrap();\n",
+        "\n",
+        "let (cfg, provenance) = Layers::new()\n",
+        "    .file(&system)\n",
+        "    .file(&user)\n",
+        "    .load_with_provenance::<Config>()\n",
+        "    .unwrap();\n",
+        "assert_eq!(cfg.port, 8080);\n",
+        "\n",
+        "let Some(Origin::File { path, location, .. }) = provenance.get(\"port\") else {\n",
+        "    panic!(\"port should come from a file\");\n",
+        "};\n",
+        "assert_eq!(path, &user);\n",
+        "assert_eq!(location.map(|loc| loc.line()), Some(2));\n",
+        "```\n",
+    ))]
+    /// # Errors
+    ///
+    /// Returns an error under the same circumstances as [`Layers::load()`].
+    pub fn load_with_provenance<T: DeserializeOwned>(&self) -> Result<(T, Provenance), LayerError> {
+        let (tree, tracker) = self.load_tree()?;
+        let mut contents = HashMap::new();
+        let mut origins = IndexMap::new();
+        let mut path = Vec::new();
+        walk_leaves(&tree, &mut path, &mut |leaf| {
+            let Some((i, source)) = tracker.leaves.get(leaf) else {
+                return;
+            };
+            let origin = match &tracker.loaded[*i] {
+                Loaded::File { path, format } => {
+                    let location = format.builtin().and_then(|fmt| {
+                        contents
+                            .entry(*i)
+                            .or_insert_with(|| read_to_string(path).ok())
+                            .as_deref()
+                            .and_then(|content| locate(fmt, content, source))
+                    });
+                    Origin::File {
+                        path: path.to_path_buf(),
+                        format: format.clone(),
+                        location,
+                    }
+                }
+                Loaded::Env(env) => {
+                    let Some(var) = env.var_for(&display_path(source)) else {
+                        return;
+                    };
+                    Origin::Env {
+                        var: var.to_owned(),
+                    }
+                }
+            };
+            origins.insert(display_path(leaf), origin);
+        });
+        let value = tracker.deserialize(tree)?;
+        Ok((value, Provenance { origins }))
+    }
+
+    /// Load & merge the layers into an untyped tree, tracking which layer
+    /// each leaf came from
+    fn load_tree(&self) -> Result<(Value, Tracker<'_>), LayerError> {
+        let mut tree = Value::Map(Map::new());
+        let mut tracker = Tracker::default();
+        for layer in &self.sources {
+            if let Some((value, loaded)) = self.load_layer(layer)? {
+                tracker.loaded.push(loaded);
+                self.merge(&mut tree, value, &mut tracker);
+            }
+        }
+        Ok((tree, tracker))
+    }
+
+    /// Load a single layer, returning `None` if it should be skipped
+    fn load_layer<'a>(&self, layer: &'a Layer) -> Result<Option<(Value, Loaded<'a>)>, LayerError> {
+        match layer {
+            Layer::File { path, optional } => {
+                match self.cfgfifo.load_with_format::<Value>(path, None) {
+                    Ok((Value::Null, _)) => Ok(None),
+                    Ok((value, format)) => Ok(Some((value, Loaded::File { path, format }))),
+                    Err(LoadError::Open { source, .. })
+                        if *optional && source.kind() == io::ErrorKind::NotFound =>
+                    {
+                        Ok(None)
+                    }
+                    Err(source) => Err(LayerError::Load {
+                        path: path.clone(),
+                        source: Box::new(source),
+                    }),
+                }
+            }
+            Layer::Env(env) => {
+                let mut tree = env.load()?;
+                let value = std::mem::take(&mut tree.value);
+                Ok(Some((value, Loaded::Env(tree))))
+            }
+        }
+    }
+
+    /// Merge `upper`, the contents of the most recently loaded layer, on top
+    /// of `lower`, which is located at `tracker.path` in the merged tree
+    fn merge(&self, lower: &mut Value, upper: Value, tracker: &mut Tracker<'_>) {
+        match (lower, upper) {
+            (Value::Map(lower), Value::Map(upper)) => {
+                for (key, value) in upper {
+                    if value == Value::Null {
+                        match self.nulls {
+                            NullMerge::Replace => (),
+                            NullMerge::Ignore => continue,
+                            NullMerge::Remove => {
+                                lower.shift_remove(&key);
+                                continue;
+                            }
+                        }
+                    }
+                    tracker.push(Segment::Key(key.clone()), Segment::Key(key.clone()));
+                    if let Some(slot) = lower.get_mut(&key) {
+                        self.merge(slot, value, tracker);
+                    } else {
+                        tracker.record(&value);
+                        lower.insert(key, value);
+                    }
+                    tracker.pop();
+                }
+            }
+            (Value::Seq(lower), Value::Seq(upper)) if self.arrays == ArrayMerge::Append => {
+                for (i, value) in upper.into_iter().enumerate() {
+                    tracker.push(Segment::Index(lower.len()), Segment::Index(i));
+                    tracker.record(&value);
+                    tracker.pop();
+                    lower.push(value);
+                }
+            }
+            (_, Value::Null) if self.nulls == NullMerge::Ignore => (),
+            (lower, upper) => {
+                tracker.record(&upper);
+                *lower = upper;
+            }
+        }
+    }
+}
+
+/// State for tracking the origins of values while merging layers
+#[derive(Debug, Default)]
+struct Tracker<'a> {
+    /// The layers loaded so far
+    loaded: Vec<Loaded<'a>>,
+
+    /// The path in the merged tree currently being merged into
+    path: Vec<Segment>,
+
+    /// The path in the most recently loaded layer corresponding to `path`.
+    /// This differs from `path` when arrays are appended.
+    source: Vec<Segment>,
+
+    /// Mapping from paths of leaves in the merged tree to indices into
+    /// `loaded` and the paths of the leaves within those layers.  Entries
+    /// for paths that were later overwritten by non-leaves may remain; they
+    /// are never looked up.
+    leaves: HashMap<Vec<Segment>, (usize, Vec<Segment>)>,
+}
+
+/// Information about a loaded layer needed for determining the origins of its
+/// values
+#[derive(Debug)]
+enum Loaded<'a> {
+    File { path: &'a Path, format: AnyFormat },
+    Env(EnvTree),
+}
+
+impl Tracker<'_> {
+    /// Deserialize the merged tree, converting the strings that came from
+    /// environment variables to whatever types are requested
+    fn deserialize<T: DeserializeOwned>(&self, tree: Value) -> Result<T, LayerError> {
+        let strings = self
+            .leaves
+            .iter()
+            .filter_map(|(path, (i, _))| match &self.loaded[*i] {
+                Loaded::Env(env) => Some((path.clone(), env.parse_values)),
+                Loaded::File { .. } => None,
+            })
+            .collect::<EnvStrings>();
+        depath(OverlayDeserializer::new(tree, &strings)).map_err(LayerError::Deserialize)
+    }
+
+    /// Record the leaves of `value`, which is being placed at `self.path`, as
+    /// coming from the most recently loaded layer
+    fn record(&mut self, value: &Value) {
+        let Some(layer) = self.loaded.len().checked_sub(1) else {
+            return;
+        };
+        walk_leaves(value, &mut Vec::new(), &mut |leaf| {
+            let path = [&self.path, leaf].concat();
+            let source = [&self.source, leaf].concat();
+            self.leaves.insert(path, (layer, source));
+        });
+    }
+
+    fn push(&mut self, path: Segment, source: Segment) {
+        self.path.push(path);
+        self.source.push(source);
+    }
+
+    fn pop(&mut self) {
+        self.path.pop();
+        self.source.pop();
+    }
+}
+
+/// Call `f` on the path of each leaf within `value` (i.e., each scalar, null,
+/// or empty map or sequence), in order, with `path` as the path to `value`
+/// itself
+fn walk_leaves<F: FnMut(&[Segment])>(value: &Value, path: &mut Vec<Segment>, f: &mut F) {
+    match value {
+        Value::Map(map) if !map.is_empty() => {
+            for (key, value) in map {
+                path.push(Segment::Key(key.clone()));
+                walk_leaves(value, path, f);
+                path.pop();
+            }
+        }
+        Value::Seq(values) if !values.is_empty() => {
+            for (i, value) in values.iter().enumerate() {
+                path.push(Segment::Index(i));
+                walk_leaves(value, path, f);
+                path.pop();
+            }
+        }
+        _ => f(path),
+    }
+}
+
+/// A record of which layer each value in a merged configuration came from, as
+/// returned by [`Layers::load_with_provenance()`].
+///
+/// Origins are recorded for each leaf of the merged tree, i.e., for each
+/// scalar, null, and empty array or map.  Leaves are identified by their
+/// paths, formatted the same way as the paths in [`LayerError::Deserialize`]
+/// errors: map keys are joined with `.`, array indices are written as `[i]`,
+/// and the path of a top-level leaf is `.`.  Entries are listed in the order
+/// that their leaves appear in the merged tree.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Provenance {
+    origins: IndexMap<String, Origin>,
+}
+
+impl Provenance {
+    /// Return the origin of the leaf at the given path, if any
+    pub fn get(&self, path: &str) -> Option<&Origin> {
+        self.origins.get(path)
+    }
+
+    /// Iterate over the paths of the leaves and their origins
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Origin)> + '_ {
+        self.origins
+            .iter()
+            .map(|(path, origin)| (path.as_str(), origin))
+    }
+
+    /// Return the number of leaves with recorded origins
+    pub fn len(&self) -> usize {
+        self.origins.len()
+    }
+
+    /// Return true if no origins were recorded
+    pub fn is_empty(&self) -> bool {
+        self.origins.is_empty()
+    }
+}
+
+/// The source of a value in a merged configuration.
+///
+/// An `Origin` displays as the path to its file followed by the location of
+/// the value within the file, if known, e.g. `/etc/app/config.toml:12:8`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Origin {
+    /// The value was loaded from a file
+    File {
+        /// The path to the file
+        path: PathBuf,
+
+        /// The format that the file was loaded as
+        format: AnyFormat,
+
+        /// The location of the value within the file, if it could be
+        /// determined.  Locations are only available for built-in formats.
+        location: Option<Location>,
+    },
+
+    /// The value was read from an environment variable
+    Env {
+        /// The name of the variable
+        var: String,
+    },
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::File { path, location, .. } => {
+                write!(f, "{}", path.display())?;
+                if let Some(loc) = location {
+                    write!(f, ":{loc}")?;
+                }
+                Ok(())
+            }
+            Origin::Env { var } => write!(f, "environment variable {var}"),
+        }
+    }
+}
+
+/// How arrays in later layers are merged with arrays at the same position in
+/// earlier layers
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ArrayMerge {
+    /// The later array replaces the earlier one
+    #[default]
+    Replace,
+
+    /// The elements of the later array are appended to the earlier one
+    Append,
+}
+
+/// How null values in later layers are merged with earlier layers
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum NullMerge {
+    /// A null value replaces the earlier value, like any other value
+    #[default]
+    Replace,
+
+    /// A null value is ignored, leaving the earlier value (if any) in place
+    Ignore,
+
+    /// A null value in a map removes the key from the merged map, so that the
+    /// field reverts to its default value (if any) when deserializing
+    Remove,
+}
+
+/// Error type returned by [`Layers::load()`] and
+/// [`Layers::load_with_provenance()`]
+#[derive(Debug, Error)]
+pub enum LayerError {
+    /// Returned if loading a layer failed
+    #[error("failed to load configuration layer {}", path.display())]
+    Load {
+        /// The path to the layer's file
+        path: PathBuf,
+        /// The error that occurred
+        source: Box<LoadError>,
+    },
+
+    /// Returned if reading an environment variable layer failed
+    #[error("failed to read configuration layer from environment")]
+    Env(#[from] EnvError),
+
+    /// Returned if deserializing the merged layers failed
+    #[error("failed to deserialize merged configuration")]
+    Deserialize(#[source] PathError<ValueError>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect::<Map>(),
+        )
+    }
+
+    fn merged(layers: &Layers, values: Vec<Value>) -> Value {
+        let mut tree = Value::Map(Map::new());
+        for v in values {
+            layers.merge(&mut tree, v, &mut Tracker::default());
+        }
+        tree
+    }
+
+    #[test]
+    fn merge_maps() {
+        let tree = merged(
+            &Layers::new(),
+            vec![
+                map([
+                    ("a", Value::Integer(1)),
+                    (
+                        "b",
+                        map([("x", Value::Integer(1)), ("y", Value::Integer(2))]),
+                    ),
+                ]),
+                map([
+                    (
+                        "b",
+                        map([("y", Value::Integer(3)), ("z", Value::Integer(4))]),
+                    ),
+                    ("c", Value::Bool(true)),
+                ]),
+            ],
+        );
+        assert_eq!(
+            tree,
+            map([
+                ("a", Value::Integer(1)),
+                (
+                    "b",
+                    map([
+                        ("x", Value::Integer(1)),
+                        ("y", Value::Integer(3)),
+                        ("z", Value::Integer(4)),
+                    ])
+                ),
+                ("c", Value::Bool(true)),
+            ])
+        );
+    }
+
+    #[test]
+    fn merge_scalar_over_map() {
+        let tree = merged(
+            &Layers::new(),
+            vec![
+                map([("a", map([("x", Value::Integer(1))]))]),
+                map([("a", Value::String(String::from("flat")))]),
+            ],
+        );
+        assert_eq!(tree, map([("a", Value::String(String::from("flat")))]));
+    }
+
+    #[test]
+    fn merge_arrays() {
+        let layers = vec![
+            map([("a", Value::Seq(vec![Value::Integer(1), Value::Integer(2)]))]),
+            map([("a", Value::Seq(vec![Value::Integer(3)]))]),
+        ];
+        assert_eq!(
+            merged(&Layers::new(), layers.clone()),
+            map([("a", Value::Seq(vec![Value::Integer(3)]))])
+        );
+        assert_eq!(
+            merged(&Layers::new().arrays(ArrayMerge::Append), layers),
+            map([(
+                "a",
+                Value::Seq(vec![
+                    Value::Integer(1),
+                    Value::Integer(2),
+                    Value::Integer(3)
+                ])
+            )])
+        );
+    }
+
+    #[test]
+    fn merge_nulls() {
+        let layers = vec![
+            map([("a", Value::Integer(1)), ("b", Value::Integer(2))]),
+            map([("a", Value::Null), ("c", Value::Null)]),
+        ];
+        assert_eq!(
+            merged(&Layers::new(), layers.clone()),
+            map([
+                ("a", Value::Null),
+                ("b", Value::Integer(2)),
+                ("c", Value::Null)
+            ])
+        );
+        assert_eq!(
+            merged(&Layers::new().nulls(NullMerge::Ignore), layers.clone()),
+            map([("a", Value::Integer(1)), ("b", Value::Integer(2))])
+        );
+        assert_eq!(
+            merged(&Layers::new().nulls(NullMerge::Remove), layers),
+            map([("b", Value::Integer(2))])
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn track_origins() {
+        let layers = Layers::new().arrays(ArrayMerge::Append);
+        let paths = [PathBuf::from("a.json"), PathBuf::from("b.json")];
+        let values = [
+            map([
+                ("a", map([("x", Value::Integer(1))])),
+                ("b", Value::Seq(vec![Value::Integer(1)])),
+            ]),
+            map([
+                ("a", Value::String(String::from("flat"))),
+                ("b", Value::Seq(vec![Value::Integer(2)])),
+            ]),
+        ];
+        let mut tree = Value::Map(Map::new());
+        let mut tracker = Tracker::default();
+        for (path, value) in paths.iter().zip(values) {
+            tracker.loaded.push(Loaded::File {
+                path,
+                format: AnyFormat::from(crate::Format::Json),
+            });
+            layers.merge(&mut tree, value, &mut tracker);
+        }
+        let mut origins = Vec::new();
+        walk_leaves(&tree, &mut Vec::new(), &mut |leaf| {
+            origins.push((display_path(leaf), tracker.leaves.get(leaf).cloned()));
+        });
+        let key = |k: &str| Segment::Key(k.to_owned());
+        assert_eq!(
+            origins,
+            [
+                (String::from("a"), Some((1, vec![key("a")]))),
+                (
+                    String::from("b[0]"),
+                    Some((0, vec![key("b"), Segment::Index(0)]))
+                ),
+                (
+                    String::from("b[1]"),
+                    Some((1, vec![key("b"), Segment::Index(0)]))
+                ),
+            ]
+        );
+    }
+}
@@ -27,15 +27,49 @@
 //!   [`Cfgfifo`] struct and use its [`load()`][Cfgfifo::load] and
 //!   [`dump()`][Cfgfifo::dump] methods.
 //!
+//! - To modify a file in place while preserving its comments & layout (where
+//!   supported by the format), use [`Cfgfifo::update()`].
+//!
+//! - To load configuration from multiple files (e.g., system, user, and
+//!   project files) merged on top of each other, use [`Layers`].  To find
+//!   out which file each merged value came from, use
+//!   [`Layers::load_with_provenance()`].
+//!
+//! - To find an application's configuration files in the standard user &
+//!   system configuration directories, use [`Cfgfifo::discover()`].  To find
+//!   project configuration files in a directory and its ancestors, use
+//!   [`WalkUp`].
+//!
+//! - To override configuration values with environment variables, use
+//!   [`EnvSource`].
+//!
+//! - To find keys in a file that the target type ignored (e.g., misspelled
+//!   field names), use [`Cfgfifo::load_with_ignored()`], or enable
+//!   [strict mode][Cfgfifo::strict] to reject them.
+//!
+//! - To show where in a file deserialization failed, use
+//!   [`LoadError::location()`] or [`DeserializeError::render()`], which
+//!   also suggests the closest valid name for misspelled fields & variants.
+//!   To report every invalid value in a file at once, use
+//!   [`Cfgfifo::validate()`].
+//!
+//! - To work with configuration whose structure isn't known ahead of time,
+//!   load it as a [`Value`], which can be dumped to any format.
+//!
+//! - To convert a file or string from one format to another, use
+//!   [`Cfgfifo::convert()`] or [`Format::transcode()`].
+//!
 //! - For per-format operations, including (de)serializing to & from strings,
 //!   readers, and writers, use the [`Format`] enum.
 //!
+//! - To support a file format not built into `cfgfifo`, implement the
+//!   [`Backend`] trait for it and register it with [`Cfgfifo::backend()`].
+//!
 //! Features
 //! ========
 //!
 //! Support for each configuration file format is controlled by a Cargo
-//! feature; the features for all formats are enabled by default.  These
-//! features are:
+//! feature.  The features for the following formats are enabled by default:
 //!
 //! - `json` — Support for JSON via the [`serde_json`] crate
 //! - `json5` — Support for JSON5 via the [`json5`] crate
@@ -43,6 +77,27 @@
 //! - `toml` — Support for TOML via the [`toml`] crate
 //! - `yaml` — Support for YAML via the [`serde_yaml`] crate
 //!
+//! Support for the following additional formats is controlled by features
+//! that are not enabled by default:
+//!
+//! - `ini` — Support for INI (see [`IniOptions`] for the supported syntax)
+//! - `properties` — Support for Java `.properties` files
+//! - `dotenv` — Support for dotenv (`.env`) files (see [`DotenvOptions`] for
+//!   the supported syntax)
+//! - `hcl` — Support for HCL via the [`hcl-rs`][::hcl] crate
+//! - `kdl` — Support for [KDL](https://kdl.dev) (see [`Format::Kdl`] for how
+//!   KDL documents map to Rust values)
+//!
+//! Transparent compression of files is controlled by the following additional
+//! features, none of which are enabled by default:
+//!
+//! - `gzip` — Support for gzip-compressed files (e.g., `config.json.gz`) via
+//!   the [`flate2`] crate
+//! - `xz` — Support for xz-compressed files (e.g., `config.json.xz`) via the
+//!   [`liblzma`] crate
+//! - `zstd` — Support for Zstandard-compressed files (e.g.,
+//!   `config.json.zst`) via the [`zstd`] crate
+//!
 //! Format Limitations
 //! ==================
 //!
@@ -53,6 +108,27 @@
 //!
 //! The following format-specific limitations are currently known:
 //!
+//! - INI only supports top-level types that serialize to maps whose values are
+//!   scalars, sequences of scalars, or maps of such values.  Strings cannot
+//!   contain line breaks, sequence elements cannot contain commas, and
+//!   `None` values are omitted.
+//!
+//! - Java properties only supports top-level types that serialize to maps.
+//!   Map keys cannot be empty or contain periods, and `None` values and empty
+//!   maps & sequences are omitted.
+//!
+//! - dotenv only supports top-level types that serialize to maps whose values
+//!   are scalars or sequences of scalars.  Sequence elements cannot contain
+//!   commas, and `None` values are omitted.
+//!
+//! - HCL only supports top-level types that serialize to maps whose keys are
+//!   valid HCL identifiers.  NaN and infinite floats are serialized as null.
+//!
+//! - KDL only supports top-level types that serialize to maps.  Empty
+//!   sequences are serialized as nodes with no arguments, which deserialize as
+//!   `None` when the sequence is optional.  Non-empty maps whose keys are all
+//!   `-` cannot be serialized, as they would deserialize as sequences.
+//!
 //! - RON has limited support for internally tagged enums with fields, untagged
 //!   enums with fields, and the `serde(flatten)` attribute.
 //!
@@ -99,13 +175,64 @@
 //! }
 //! ```
 
+mod backend;
+mod compression;
+mod convert;
+mod discover;
+#[cfg(feature = "dotenv")]
+mod dotenv;
+mod env;
+#[cfg(feature = "hcl")]
+mod hcl;
+#[cfg(feature = "ini")]
+mod ini;
+#[cfg(feature = "kdl")]
+mod kdl;
+#[cfg(any(feature = "ini", feature = "properties", feature = "dotenv"))]
+mod keyvalue;
+mod layers;
+mod locate;
+#[cfg(feature = "toml")]
+mod preserve;
+#[cfg(feature = "properties")]
+mod properties;
+mod scalar;
+mod sniff;
+mod suggest;
+mod track;
+mod transcode;
+mod value;
+pub use crate::backend::*;
+pub use crate::compression::*;
+pub use crate::discover::{DiscoverError, WalkUp, config_dirs};
+#[cfg(feature = "dotenv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dotenv")))]
+pub use crate::dotenv::{DotenvError, DotenvOptions};
+use crate::env::OverlayDeserializer;
+pub use crate::env::{EnvError, EnvSource};
+#[cfg(feature = "ini")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ini")))]
+pub use crate::ini::{GlobalSection, IniError, IniOptions};
+#[cfg(feature = "kdl")]
+#[cfg_attr(docsrs, doc(cfg(feature = "kdl")))]
+pub use crate::kdl::KdlError;
+pub use crate::layers::*;
+pub use crate::locate::{LocatedError, Location, Snippet};
+#[cfg(feature = "properties")]
+#[cfg_attr(docsrs, doc(cfg(feature = "properties")))]
+pub use crate::properties::PropertiesError;
+use crate::track::{Tracker, collect_errors, depath_tracked};
+pub use crate::transcode::{Incompatibility, IncompatibilityKind, TranscodeError};
+pub use crate::value::{Datetime, Map, Value, ValueError};
 use serde::{Serialize, de::DeserializeOwned};
 #[allow(unused_imports)]
 use serde_path_to_error::{Error as PathError, deserialize as depath, serialize as serpath};
-use std::fs::File;
+use std::ffi::OsString;
+use std::fs::{self, File};
 use std::io::{self, Write};
-use std::path::Path;
-use strum::{Display, EnumIter};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use strum::{Display, EnumIter, IntoStaticStr};
 use thiserror::Error;
 
 #[cfg(feature = "ron")]
@@ -119,14 +246,21 @@ use ron::ser::PrettyConfig;
 /// A `Format` can be [displayed][std::fmt::Display] as a string containing its
 /// name in all-uppercase, and a `Format` can be [parsed][std::str::FromStr]
 /// from its name in any case.
-#[derive(Clone, Copy, Debug, Display, EnumIter, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(
+    Clone, Copy, Debug, Display, EnumIter, Eq, Hash, IntoStaticStr, Ord, PartialEq, PartialOrd,
+)]
 #[cfg_attr(
     any(
         feature = "json",
         feature = "json5",
         feature = "ron",
         feature = "toml",
-        feature = "yaml"
+        feature = "yaml",
+        feature = "ini",
+        feature = "properties",
+        feature = "dotenv",
+        feature = "hcl",
+        feature = "kdl"
     ),
     derive(strum::EnumString)
 )]
@@ -136,7 +270,12 @@ use ron::ser::PrettyConfig;
         feature = "json5",
         feature = "ron",
         feature = "toml",
-        feature = "yaml"
+        feature = "yaml",
+        feature = "ini",
+        feature = "properties",
+        feature = "dotenv",
+        feature = "hcl",
+        feature = "kdl"
     ),
     strum(ascii_case_insensitive, serialize_all = "UPPERCASE")
 )]
@@ -182,6 +321,103 @@ pub enum Format {
     #[cfg(feature = "yaml")]
     #[cfg_attr(docsrs, doc(cfg(feature = "yaml")))]
     Yaml,
+
+    /// The INI format, (de)serialized with the default [`IniOptions`].
+    ///
+    /// Serialization writes keys outside of any section first, followed by
+    /// each section separated by blank lines.
+    #[cfg(feature = "ini")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ini")))]
+    Ini,
+
+    /// The Java [properties](https://en.wikipedia.org/wiki/.properties)
+    /// format.
+    ///
+    /// Keys are split on periods into nested maps when deserializing, and
+    /// nested maps are flattened back into dotted keys when serializing.
+    /// Sequences of scalars are written as comma-separated values, while
+    /// other sequences are written with their indices as key components
+    /// (e.g., `people.0.name`).  Values are parsed as booleans or numbers
+    /// when the deserialized type calls for it.
+    ///
+    /// The full syntax of `java.util.Properties` is supported, including `=`,
+    /// `:`, and whitespace separators, `#` and `!` comments, line
+    /// continuations, and backslash escapes (including `\uXXXX`).
+    /// Serialization writes `key=value` lines and escapes all non-ASCII
+    /// characters.
+    #[cfg(feature = "properties")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "properties")))]
+    Properties,
+
+    /// The dotenv format used by `.env` files, (de)serialized with the
+    /// default [`DotenvOptions`].
+    ///
+    /// In addition to files with the `env` extension, files named just `.env`
+    /// are identified as dotenv.  Serialization writes one `KEY=value` line
+    /// per entry.
+    #[cfg(feature = "dotenv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dotenv")))]
+    Dotenv,
+
+    /// The [HCL](https://github.com/hashicorp/hcl) format used by Terraform,
+    /// (de)serialized with the [`hcl-rs`][::hcl] crate.
+    ///
+    /// Documents deserialize following the [HCL JSON
+    /// specification](https://github.com/hashicorp/hcl/blob/main/json/spec.md):
+    /// attributes become map entries, blocks become nested maps keyed by their
+    /// identifiers, and each label of a labeled block adds another level of
+    /// nesting, so that `resource "aws_instance" "web" { ami = "abc" }`
+    /// deserializes as `{"resource": {"aws_instance": {"web": {"ami":
+    /// "abc"}}}}`.  Repeated blocks with the same identifier & labels
+    /// deserialize as a sequence of maps.  Expressions other than literal
+    /// values, such as variable references & function calls, are not
+    /// evaluated and instead deserialize as template strings (e.g.,
+    /// `"${var.name}"`).
+    ///
+    /// Serialization performs the reverse mapping: map values whose keys are
+    /// all valid identifiers are written as blocks, blocks containing nothing
+    /// but blocks are written as labeled blocks instead, and all other values
+    /// (including sequences) are written as attributes.
+    #[cfg(feature = "hcl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "hcl")))]
+    Hcl,
+
+    /// The [KDL](https://kdl.dev) document language, (de)serialized with a
+    /// built-in implementation of KDL 2.0.
+    ///
+    /// A document or a node's children deserialize as a map from node names to
+    /// node values, and sibling nodes that share a name deserialize as a
+    /// sequence of their values.  The value of a node depends on what it
+    /// contains:
+    ///
+    /// - A node with a single argument (`port 8080`) is that argument.
+    ///
+    /// - A node with multiple arguments (`list 1 2 3`) is a sequence of them.
+    ///
+    /// - A node with nothing after its name is null (or an empty sequence or
+    ///   map, if one is requested).
+    ///
+    /// - A node with properties and/or children (`server host=localhost {
+    ///   port 8080 }`) is a map of the properties followed by the children.
+    ///
+    /// - A node whose children are all named `-` (`hosts { - a; - b }`) is a
+    ///   sequence of the children's values.
+    ///
+    /// A node with both arguments and properties or children is an error.
+    /// Type annotations are ignored, and enums are either strings (for unit
+    /// variants) or nodes with a single property or child named after the
+    /// variant.  When deserializing a sequence, a single node whose value is a
+    /// map is treated as a sequence of one map, so that repeated nodes like
+    /// `person name=Alice` can be used for sequences of any length.
+    ///
+    /// Serialization produces idiomatic KDL in the same mapping: scalars and
+    /// sequences of scalars are written as arguments, maps are written as
+    /// children, and other sequences are written as children named `-`, with
+    /// maps of scalars written as properties (`- id=1 name=Alice`).  `None` is
+    /// written as `#null`.
+    #[cfg(feature = "kdl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "kdl")))]
+    Kdl,
 }
 
 impl Format {
@@ -218,6 +454,16 @@ impl Format {
             Format::Toml => &["toml"],
             #[cfg(feature = "yaml")]
             Format::Yaml => &["yaml", "yml"],
+            #[cfg(feature = "ini")]
+            Format::Ini => &["ini"],
+            #[cfg(feature = "properties")]
+            Format::Properties => &["properties"],
+            #[cfg(feature = "dotenv")]
+            Format::Dotenv => &["env"],
+            #[cfg(feature = "hcl")]
+            Format::Hcl => &["hcl"],
+            #[cfg(feature = "kdl")]
+            Format::Kdl => &["kdl"],
             #[allow(unreachable_patterns)]
             _ => unreachable!(),
         }
@@ -288,6 +534,38 @@ impl Format {
         Format::from_extension(ext).ok_or_else(|| IdentifyError::Unknown(ext.to_owned()))
     }
 
+    /// Guess the format of a file from its contents.
+    ///
+    /// The contents are inspected for distinctive syntax, such as a leading
+    /// `{` or `[` (JSON and JSON5), comments and unquoted keys (JSON5), named
+    /// structs (RON), `key = value` pairs and `[table]` headers (TOML),
+    /// `---` document markers and `key: value` pairs (YAML), and TOML-like
+    /// syntax with unquoted strings or `;` comments (INI), and dotted
+    /// `key=value` pairs without any `[section]` headers (Java properties),
+    /// `KEY=value` pairs with uppercase keys or `export` prefixes (dotenv),
+    /// `name "label" {` block headers (HCL), and nodes with quoted, numeric,
+    /// or `#keyword` arguments or `key=value` properties (KDL).  The enabled
+    /// formats that the contents could plausibly be in are returned, most
+    /// likely first; if no format seems likely, an empty `Vec` is returned.
+    ///
+    /// This is only a heuristic; the contents are not fully parsed, and so
+    /// loading them in the top-ranked format may still fail.
+    #[cfg_attr(all(feature = "json", feature = "toml", feature = "yaml"), doc = concat!(
+        "# Example\n",
+        "\n",
+        "```\n",
+        "use cfgfifo::Format;\n",
+        "\n",
+        "assert_eq!(Format::sniff(\"{\\\"key\\\": 42}\")[0], Format::Json);\n",
+        "assert_eq!(Format::sniff(\"[table]\\nkey = 42\\n\")[0], Format::Toml);\n",
+        "assert_eq!(Format::sniff(\"---\\nkey: 42\\n\")[0], Format::Yaml);\n",
+        "assert!(Format::sniff(\"Hello, world!\").is_empty());\n",
+        "```\n",
+    ))]
+    pub fn sniff(content: &str) -> Vec<Format> {
+        sniff::sniff(content)
+    }
+
     /// Serialize a value to a string in this format
     #[cfg_attr(feature = "json", doc = concat!(
         "# Example\n",
@@ -362,7 +640,7 @@ impl Format {
             Format::Toml => {
                 let mut buff = toml::ser::Buffer::new();
                 let ser = toml::Serializer::pretty(&mut buff);
-                serpath(value, ser)?;
+                value::with_toml_datetimes(|| serpath(value, ser))?;
                 Ok(buff.to_string())
             }
             #[cfg(feature = "yaml")]
@@ -374,11 +652,86 @@ impl Format {
                 };
                 Ok(s)
             }
+            #[cfg(feature = "ini")]
+            Format::Ini => IniOptions::new().dump_to_string(value),
+            #[cfg(feature = "properties")]
+            Format::Properties => properties::to_string(value).map_err(SerializeError::Properties),
+            #[cfg(feature = "dotenv")]
+            Format::Dotenv => DotenvOptions::new().dump_to_string(value),
+            #[cfg(feature = "hcl")]
+            Format::Hcl => hcl::to_string(value).map_err(Into::into),
+            #[cfg(feature = "kdl")]
+            Format::Kdl => kdl::to_string(value).map_err(SerializeError::Kdl),
             #[allow(unreachable_patterns)]
             _ => unreachable!(),
         }
     }
 
+    /// Serialize a value to a string in this format, preserving the comments
+    /// & layout of `original` (a previous serialization in this format) where
+    /// possible.
+    ///
+    /// For TOML, the value is serialized and then merged into `original` so
+    /// that only the keys whose values changed are rewritten; comments,
+    /// whitespace, key order, and the representations of unchanged values are
+    /// kept.  Keys missing from the new serialization are removed, and new
+    /// keys are added after the existing ones.  If `original` is not valid
+    /// TOML, the value is serialized as with [`Format::dump_to_string()`].
+    ///
+    /// For all other formats, this is currently the same as
+    /// [`Format::dump_to_string()`], and `original` is ignored.
+    #[cfg_attr(feature = "toml", doc = concat!(
+        "# Example\n",
+        "\n",
+        "```\n",
+        "use cfgfifo::Format;\n",
+        "use serde::{Deserialize, Serialize};\n",
+        "\n",
+        "#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]\n",
+        "struct Data {\n",
+        "    name: String,\n",
+        "    size: u32,\n",
+        "}\n",
+        "\n",
+        "let original = concat!(\n",
+        "    \"# The name of the thing\\n\",\n",
+        "    \"name = 'Example'\\n\",\n",
+        "    \"size = 42  # Not too big\\n\",\n",
+        ");\n",
+        "\n",
+        "let mut datum: Data = Format::Toml.load_from_str(original).unwrap();\n",
+        "datum.size = 23;\n",
+        "\n",
+        "let s = Format::Toml.update_string(original, &datum).unwrap();\n",
+        "\n",
+        "assert_eq!(\n",
+        "    s,\n",
+        "    concat!(\n",
+        "        \"# The name of the thing\\n\",\n",
+        "        \"name = 'Example'\\n\",\n",
+        "        \"size = 23  # Not too big\\n\",\n",
+        "    )\n",
+        ");\n",
+        "```\n",
+    ))]
+    /// # Errors
+    ///
+    /// Returns an error if the underlying serializer returns an error.
+    #[allow(unused_variables)]
+    pub fn update_string<T: Serialize>(
+        &self,
+        original: &str,
+        value: &T,
+    ) -> Result<String, SerializeError> {
+        let s = self.dump_to_string(value)?;
+        match self {
+            #[cfg(feature = "toml")]
+            Format::Toml => Ok(preserve::update_toml(original, s)),
+            #[allow(unreachable_patterns)]
+            _ => Ok(s),
+        }
+    }
+
     /// Deserialize a string in this format
     #[cfg_attr(feature = "yaml", doc = concat!(
         "# Example\n",
@@ -415,25 +768,37 @@ impl Format {
     /// # Errors
     ///
     /// Returns an error if the underlying deserializer returns an error.
-    #[allow(unused_variables)]
     pub fn load_from_str<T: DeserializeOwned>(&self, s: &str) -> Result<T, DeserializeError> {
+        self.load_from_str_tracked(s, None)
+    }
+
+    /// Like [`Format::load_from_str()`], but with a [`Tracker`] for recording
+    /// the paths of any keys that `T` ignored and substituting placeholders
+    /// for values that previously failed to deserialize
+    // `tracker` is unused when no formats are enabled
+    #[allow(unused_variables)]
+    pub(crate) fn load_from_str_tracked<T: DeserializeOwned>(
+        &self,
+        s: &str,
+        tracker: Option<&mut Tracker>,
+    ) -> Result<T, DeserializeError> {
         match self {
             #[cfg(feature = "json")]
             Format::Json => {
                 let mut de = serde_json::Deserializer::from_str(s);
-                let value = depath(&mut de)?;
+                let value = depath_tracked(&mut de, tracker)?;
                 de.end().map_err(DeserializeError::JsonEnd)?;
                 Ok(value)
             }
             #[cfg(feature = "json5")]
             Format::Json5 => {
                 let mut de = json5::Deserializer::from_str(s);
-                depath(&mut de).map_err(Into::into)
+                depath_tracked(&mut de, tracker).map_err(Into::into)
             }
             #[cfg(feature = "ron")]
             Format::Ron => {
                 let mut de = ron::Deserializer::from_str(s).map_err(DeserializeError::RonStart)?;
-                let value = match depath(&mut de) {
+                let value = match depath_tracked(&mut de, tracker) {
                     Ok(value) => value,
                     Err(e) => {
                         let path = e.path().clone();
@@ -448,13 +813,33 @@ impl Format {
             }
             #[cfg(feature = "toml")]
             Format::Toml => {
-                let de = toml::Deserializer::parse(s).map_err(DeserializeError::TomlParse)?;
-                depath(de).map_err(Into::into)
+                let de = toml::Deserializer::parse(s)
+                    .map_err(|error| DeserializeError::toml_parse(error, s))?;
+                depath_tracked(de, tracker).map_err(|error| DeserializeError::toml(error, s))
             }
             #[cfg(feature = "yaml")]
             Format::Yaml => {
                 let de = serde_yaml::Deserializer::from_str(s);
-                depath(de).map_err(Into::into)
+                depath_tracked(de, tracker).map_err(Into::into)
+            }
+            #[cfg(feature = "ini")]
+            Format::Ini => IniOptions::new().load_from_str_tracked(s, tracker),
+            #[cfg(feature = "properties")]
+            Format::Properties => {
+                let de = properties::deserializer(s).map_err(DeserializeError::Properties)?;
+                depath_tracked(de, tracker).map_err(DeserializeError::Properties)
+            }
+            #[cfg(feature = "dotenv")]
+            Format::Dotenv => DotenvOptions::new().load_from_str_tracked(s, tracker),
+            #[cfg(feature = "hcl")]
+            Format::Hcl => {
+                let de = hcl::deserializer(s)?;
+                depath_tracked(de, tracker).map_err(Into::into)
+            }
+            #[cfg(feature = "kdl")]
+            Format::Kdl => {
+                let de = kdl::deserializer(s).map_err(DeserializeError::Kdl)?;
+                depath_tracked(de, tracker).map_err(DeserializeError::Kdl)
             }
             #[allow(unreachable_patterns)]
             _ => unreachable!(),
@@ -536,6 +921,36 @@ impl Format {
                 let mut ser = serde_yaml::Serializer::new(writer);
                 serpath(value, &mut ser).map_err(Into::into)
             }
+            #[cfg(feature = "ini")]
+            Format::Ini => {
+                let s = self.dump_to_string(value)?;
+                writer.write_all(s.as_bytes())?;
+                Ok(())
+            }
+            #[cfg(feature = "properties")]
+            Format::Properties => {
+                let s = self.dump_to_string(value)?;
+                writer.write_all(s.as_bytes())?;
+                Ok(())
+            }
+            #[cfg(feature = "dotenv")]
+            Format::Dotenv => {
+                let s = self.dump_to_string(value)?;
+                writer.write_all(s.as_bytes())?;
+                Ok(())
+            }
+            #[cfg(feature = "hcl")]
+            Format::Hcl => {
+                let s = self.dump_to_string(value)?;
+                writer.write_all(s.as_bytes())?;
+                Ok(())
+            }
+            #[cfg(feature = "kdl")]
+            Format::Kdl => {
+                let s = self.dump_to_string(value)?;
+                writer.write_all(s.as_bytes())?;
+                Ok(())
+            }
             #[allow(unreachable_patterns)]
             _ => unreachable!(),
         }
@@ -547,39 +962,215 @@ impl Format {
     ///
     /// Returns an error if an I/O error occurs or if the underlying
     /// deserializer returns an error.
-    #[allow(unused_variables)]
     pub fn load_from_reader<R: io::Read, T: DeserializeOwned>(
         &self,
         reader: R,
+    ) -> Result<T, DeserializeError> {
+        self.load_from_reader_tracked(reader, None)
+    }
+
+    /// Like [`Format::load_from_reader()`], but with a [`Tracker`] for
+    /// recording the paths of any keys that `T` ignored and substituting
+    /// placeholders for values that previously failed to deserialize
+    // `tracker` is unused when no formats are enabled
+    #[allow(unused_variables)]
+    pub(crate) fn load_from_reader_tracked<R: io::Read, T: DeserializeOwned>(
+        &self,
+        reader: R,
+        tracker: Option<&mut Tracker>,
     ) -> Result<T, DeserializeError> {
         match self {
             #[cfg(feature = "json")]
             Format::Json => {
                 let mut de = serde_json::Deserializer::from_reader(reader);
-                let value = depath(&mut de)?;
+                let value = depath_tracked(&mut de, tracker)?;
                 de.end().map_err(DeserializeError::JsonEnd)?;
                 Ok(value)
             }
             #[cfg(feature = "json5")]
             Format::Json5 => {
                 let s = io::read_to_string(reader)?;
-                self.load_from_str(&s)
+                self.load_from_str_tracked(&s, tracker)
             }
             #[cfg(feature = "ron")]
             Format::Ron => {
                 let s = io::read_to_string(reader)?;
-                self.load_from_str(&s)
+                self.load_from_str_tracked(&s, tracker)
             }
             #[cfg(feature = "toml")]
             Format::Toml => {
                 let s = io::read_to_string(reader)?;
-                self.load_from_str(&s)
+                self.load_from_str_tracked(&s, tracker)
             }
             #[cfg(feature = "yaml")]
             Format::Yaml => {
                 let de = serde_yaml::Deserializer::from_reader(reader);
-                depath(de).map_err(Into::into)
+                depath_tracked(de, tracker).map_err(Into::into)
+            }
+            #[cfg(feature = "ini")]
+            Format::Ini => {
+                let s = io::read_to_string(reader)?;
+                self.load_from_str_tracked(&s, tracker)
+            }
+            #[cfg(feature = "properties")]
+            Format::Properties => {
+                let s = io::read_to_string(reader)?;
+                self.load_from_str_tracked(&s, tracker)
+            }
+            #[cfg(feature = "dotenv")]
+            Format::Dotenv => {
+                let s = io::read_to_string(reader)?;
+                self.load_from_str_tracked(&s, tracker)
+            }
+            #[cfg(feature = "hcl")]
+            Format::Hcl => {
+                let s = io::read_to_string(reader)?;
+                self.load_from_str_tracked(&s, tracker)
+            }
+            #[cfg(feature = "kdl")]
+            Format::Kdl => {
+                let s = io::read_to_string(reader)?;
+                self.load_from_str_tracked(&s, tracker)
+            }
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+    }
+
+    /// Convert a string in format `from` to a string in format `to` without
+    /// deserializing it into an intermediate type.
+    ///
+    /// The input is serialized as it is deserialized, preserving the order
+    /// of map keys.  Map keys that are numbers or booleans are converted to
+    /// strings, and tagged values (such as YAML's `!Tag value`) are converted
+    /// to single-entry maps from the tag to the value.  TOML datetimes are
+    /// written as native datetimes when converting to TOML and as strings
+    /// otherwise.
+    ///
+    /// As with [`Format::dump_to_string()`], the output does not necessarily
+    /// end with a newline.
+    #[cfg_attr(all(feature = "toml", feature = "yaml"), doc = concat!(
+        "# Example\n",
+        "\n",
+        "```\n",
+        "use cfgfifo::Format;\n",
+        "\n",
+        "let yaml = concat!(\n",
+        "    \"name: Example\\n\",\n",
+        "    \"server:\\n\",\n",
+        "    \"  port: 8080\\n\",\n",
+        ");\n",
+        "\n",
+        "let toml = Format::transcode(Format::Yaml, Format::Toml, yaml).unwrap();\n",
+        "\n",
+        "assert_eq!(\n",
+        "    toml,\n",
+        "    concat!(\n",
+        "        \"name = \\\"Example\\\"\\n\",\n",
+        "        \"\\n\",\n",
+        "        \"[server]\\n\",\n",
+        "        \"port = 8080\\n\",\n",
+        "    )\n",
+        ");\n",
+        "```\n",
+    ))]
+    /// # Errors
+    ///
+    /// Returns an error if the input cannot be deserialized as `from`, if the
+    /// input contains a value that `to` cannot represent (e.g., a null value
+    /// or a non-table top level when converting to TOML, or a byte string
+    /// when converting to YAML), or if the underlying serializer returns an
+    /// error.
+    pub fn transcode(from: Format, to: Format, input: &str) -> Result<String, TranscodeError> {
+        Format::transcode_inner(from, to, input)
+            .map_err(|e| e.into_transcode_error(from, to, input))
+    }
+
+    /// Like [`Format::transcode()`], but without retrieving the full details
+    /// of deserialization errors
+    #[allow(unused_variables)]
+    fn transcode_inner(
+        from: Format,
+        to: Format,
+        input: &str,
+    ) -> Result<String, transcode::Error<DeserializeError>> {
+        match from {
+            #[cfg(feature = "json")]
+            Format::Json => {
+                let mut de = serde_json::Deserializer::from_str(input);
+                let s = transcode::transcode(&mut de, to)
+                    .map_err(|e| e.map_deserialize(DeserializeError::from))?;
+                de.end()
+                    .map_err(|e| transcode::Error::Deserialize(DeserializeError::JsonEnd(e)))?;
+                Ok(s)
+            }
+            #[cfg(feature = "json5")]
+            Format::Json5 => {
+                let mut de = json5::Deserializer::from_str(input);
+                transcode::transcode(&mut de, to)
+                    .map_err(|e| e.map_deserialize(DeserializeError::from))
+            }
+            #[cfg(feature = "ron")]
+            Format::Ron => {
+                let mut de = ron::Deserializer::from_str(input)
+                    .map_err(|e| transcode::Error::Deserialize(DeserializeError::RonStart(e)))?;
+                let s = transcode::transcode(&mut de, to).map_err(|e| {
+                    e.map_deserialize(|e| {
+                        let path = e.path().clone();
+                        let ron_e = de.span_error(e.into_inner());
+                        DeserializeError::from(PathError::new(path, ron_e))
+                    })
+                })?;
+                de.end().map_err(|e| {
+                    transcode::Error::Deserialize(DeserializeError::RonEnd(de.span_error(e)))
+                })?;
+                Ok(s)
+            }
+            #[cfg(feature = "toml")]
+            Format::Toml => match toml::Deserializer::parse(input) {
+                Ok(de) => transcode::transcode(de, to)
+                    .map_err(|e| e.map_deserialize(|error| DeserializeError::toml(error, input))),
+                Err(error) => Err(transcode::Error::Deserialize(DeserializeError::toml_parse(
+                    error, input,
+                ))),
+            },
+            #[cfg(feature = "yaml")]
+            Format::Yaml => {
+                let de = serde_yaml::Deserializer::from_str(input);
+                transcode::transcode(de, to).map_err(|e| e.map_deserialize(DeserializeError::from))
             }
+            #[cfg(feature = "ini")]
+            Format::Ini => match IniOptions::new().deserializer(input) {
+                Ok(de) => transcode::transcode(de, to)
+                    .map_err(|e| e.map_deserialize(DeserializeError::Ini)),
+                Err(e) => Err(transcode::Error::Deserialize(DeserializeError::Ini(e))),
+            },
+            #[cfg(feature = "properties")]
+            Format::Properties => match properties::deserializer(input) {
+                Ok(de) => transcode::transcode(de, to)
+                    .map_err(|e| e.map_deserialize(DeserializeError::Properties)),
+                Err(e) => Err(transcode::Error::Deserialize(DeserializeError::Properties(
+                    e,
+                ))),
+            },
+            #[cfg(feature = "dotenv")]
+            Format::Dotenv => match DotenvOptions::new().deserializer(input) {
+                Ok(de) => transcode::transcode(de, to)
+                    .map_err(|e| e.map_deserialize(DeserializeError::Dotenv)),
+                Err(e) => Err(transcode::Error::Deserialize(DeserializeError::Dotenv(e))),
+            },
+            #[cfg(feature = "hcl")]
+            Format::Hcl => match hcl::deserializer(input) {
+                Ok(de) => transcode::transcode(de, to)
+                    .map_err(|e| e.map_deserialize(DeserializeError::from)),
+                Err(e) => Err(transcode::Error::Deserialize(DeserializeError::from(e))),
+            },
+            #[cfg(feature = "kdl")]
+            Format::Kdl => match kdl::deserializer(input) {
+                Ok(de) => transcode::transcode(de, to)
+                    .map_err(|e| e.map_deserialize(DeserializeError::Kdl)),
+                Err(e) => Err(transcode::Error::Deserialize(DeserializeError::Kdl(e))),
+            },
             #[allow(unreachable_patterns)]
             _ => unreachable!(),
         }
@@ -615,12 +1206,25 @@ pub fn dump<P: AsRef<Path>, T: Serialize>(path: P, value: &T) -> Result<(), Dump
 /// By default, a `Cfgfifo` instance's [`identify()`][Cfgfifo::identify],
 /// [`load()`][Cfgfifo::load], and [`dump()`][Cfgfifo::dump] methods act the
 /// same as [`Format::identify()`], [`load()`], and [`dump()`], but the
-/// instance can be customized to only support a subset of enabled [`Format`]s
-/// and/or to use a given fallback [`Format`] if identifying a file's format
-/// fails.
+/// instance can be customized to only support a subset of enabled [`Format`]s,
+/// to support custom [`Backend`]s, to map additional file extensions & file
+/// names to formats, and/or to use a given fallback [`Format`] if identifying a
+/// file's format fails.
+///
+/// Custom backends are compared by pointer (see [`AnyFormat`]), so two
+/// instances that each add a backend with [`Cfgfifo::backend()`] are unequal
+/// even if the backends are identical, while an instance and its clones are
+/// equal.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Cfgfifo {
-    formats: Vec<Format>,
+    formats: Vec<AnyFormat>,
+    extensions: Vec<(String, AnyFormat)>,
+    filenames: Vec<(OsString, AnyFormat)>,
+    sniff: bool,
+    try_each: bool,
+    atomic: bool,
+    strict: bool,
+    env: Option<EnvSource>,
     fallback: Option<Format>,
 }
 
@@ -628,22 +1232,153 @@ impl Cfgfifo {
     /// Create a new Cfgfifo instance
     pub fn new() -> Cfgfifo {
         Cfgfifo {
-            formats: Format::iter().collect(),
+            formats: Format::iter().map(AnyFormat::from).collect(),
+            extensions: Vec::new(),
+            filenames: Vec::new(),
+            sniff: false,
+            try_each: false,
+            atomic: false,
+            strict: false,
+            env: None,
             fallback: None,
         }
     }
 
-    /// Set the [`Format`]s to support.
+    /// Set the formats to support.
     ///
-    /// By default, all enabled formats are selected.
+    /// By default, all enabled [`Format`]s are selected.  Formats are tried in
+    /// the given order when identifying a file's format, and this method
+    /// replaces any formats previously set, including any custom backends
+    /// added with [`Cfgfifo::backend()`].
     ///
     /// This is useful if you want to always restrict loading & dumping to a
     /// certain set of formats even if more formats become enabled via [feature
     /// unification].
     ///
     /// [feature unification]: https://doc.rust-lang.org/cargo/reference/features.html#feature-unification
-    pub fn formats<I: IntoIterator<Item = Format>>(mut self, iter: I) -> Self {
-        self.formats = iter.into_iter().collect();
+    pub fn formats<I>(mut self, iter: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<AnyFormat>,
+    {
+        self.formats = iter.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add support for a custom format.
+    ///
+    /// The backend is appended to the list of supported formats, and so it
+    /// will only be used for a file extension if no previously-added format
+    /// claims that extension.
+    pub fn backend<B: Backend + 'static>(mut self, backend: B) -> Self {
+        self.formats.push(AnyFormat::custom(backend));
+        self
+    }
+
+    /// Associate a file extension with a format.
+    ///
+    /// Extensions added with this method are matched case-insensitively, may
+    /// optionally start with a period, and take precedence over the formats'
+    /// built-in extensions.  If the same extension is added more than once,
+    /// the first association wins.
+    pub fn extension<F: Into<AnyFormat>>(mut self, ext: &str, format: F) -> Self {
+        let ext = ext.strip_prefix('.').unwrap_or(ext);
+        self.extensions.push((ext.to_owned(), format.into()));
+        self
+    }
+
+    /// Associate an exact file name with a format.
+    ///
+    /// File name rules are matched case-sensitively against the final
+    /// component of a file path and are consulted before any file extensions.
+    /// This allows identifying files like `Cargo.lock` or `.eslintrc` whose
+    /// extension (if any) does not indicate their format.  If the same file
+    /// name is added more than once, the first association wins.
+    pub fn filename<S: Into<OsString>, F: Into<AnyFormat>>(mut self, name: S, format: F) -> Self {
+        self.filenames.push((name.into(), format.into()));
+        self
+    }
+
+    /// Enable or disable content sniffing.
+    ///
+    /// When content sniffing is enabled and [`Cfgfifo::load()`] is unable to
+    /// identify a file's format from its path, the file's contents are
+    /// inspected with [`Format::sniff()`], and the most likely supported
+    /// built-in format is used.  The fallback format, if any, is only used if
+    /// sniffing fails to produce a guess.
+    ///
+    /// Content sniffing is disabled by default.  It does not affect
+    /// [`Cfgfifo::identify()`] or [`Cfgfifo::dump()`].
+    pub fn sniff(mut self, sniff: bool) -> Self {
+        self.sniff = sniff;
+        self
+    }
+
+    /// Enable or disable try-each loading.
+    ///
+    /// When try-each loading is enabled and [`Cfgfifo::load()`] is unable to
+    /// identify a file's format from its path, the file is deserialized with
+    /// each supported format in the order that the formats were configured,
+    /// and the first successful result is returned.  If deserialization fails
+    /// for every format, a [`LoadError::AllFailed`] is returned.
+    ///
+    /// Try-each loading is disabled by default.  It does not affect
+    /// [`Cfgfifo::identify()`] or [`Cfgfifo::dump()`].
+    pub fn try_each(mut self, try_each: bool) -> Self {
+        self.try_each = try_each;
+        self
+    }
+
+    /// Enable or disable atomic writes.
+    ///
+    /// When atomic writes are enabled, [`Cfgfifo::dump()`] serializes to a
+    /// temporary file in the same directory as the destination path, syncs the
+    /// temporary file to disk, and then renames it over the destination.  If
+    /// serialization or writing fails, the temporary file is removed and any
+    /// previous file at the destination is left untouched.  When replacing an
+    /// existing file, the new file is given the same permissions as the old
+    /// one, and if the destination is a symbolic link, the file it points to
+    /// is replaced (or created, if the link is dangling) rather than the link
+    /// itself.
+    ///
+    /// Atomic writes are disabled by default, in which case the destination
+    /// is truncated and written to in place.
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Enable or disable strict mode.
+    ///
+    /// When strict mode is enabled, [`Cfgfifo::load()`] and
+    /// [`Cfgfifo::update()`] fail with a [`DeserializeError::UnknownKeys`]
+    /// error if the file contains any keys that the target type ignored, such
+    /// as misspelled field names that would otherwise silently fall back to
+    /// their defaults.  To retrieve such keys without failing, use
+    /// [`Cfgfifo::load_with_ignored()`] with strict mode disabled.
+    ///
+    /// Strict mode is disabled by default.  It is not applied by [`Layers`].
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Overlay values read from environment variables on top of the values
+    /// loaded from files by [`Cfgfifo::load()`].
+    ///
+    /// When an [`EnvSource`] is set, `load()` first deserializes the file
+    /// into an untyped tree, then merges the values from the environment
+    /// variables on top of it (with maps merged recursively and all other
+    /// values replaced), and finally deserializes the result into the
+    /// requested type.  Deserialization errors are then reported as
+    /// [`LoadError::Overlay`], which names the environment variable
+    /// responsible, if any.
+    ///
+    /// The environment is not consulted by [`Cfgfifo::update()`] or by
+    /// [`Layers`], which takes environment variables as a separate layer via
+    /// [`Layers::env()`].
+    pub fn env(mut self, env: EnvSource) -> Self {
+        self.env = Some(env);
         self
     }
 
@@ -653,7 +1388,17 @@ impl Cfgfifo {
         self
     }
 
-    /// Determine the [`Format`] of a file path based on its file extension.
+    /// Determine the format of a file path based on its file name or
+    /// extension.
+    ///
+    /// The file name is first checked against the rules added with
+    /// [`Cfgfifo::filename()`], after which the file extension is checked
+    /// against the extensions added with [`Cfgfifo::extension()`] and then
+    /// against the extensions of the supported formats.
+    ///
+    /// If the path ends in the file extension of a supported [`Compression`]
+    /// format (e.g., `config.json.gz`), that extension is removed before
+    /// identifying the format.
     #[cfg_attr(all(feature = "json", feature = "yaml"), doc = concat!(
         "# Example\n",
         "\n",
@@ -669,58 +1414,700 @@ impl Cfgfifo {
         "assert_eq!(cfgfifo.identify(\"path/to/file.ron\").unwrap(), Format::Json);\n",
         "assert_eq!(cfgfifo.identify(\"path/to/file.cfg\").unwrap(), Format::Json);\n",
         "assert_eq!(cfgfifo.identify(\"path/to/file\").unwrap(), Format::Json);\n",
+        "\n",
+        "let cfgfifo = Cfgfifo::new()\n",
+        "    .extension(\"cfg\", Format::Yaml)\n",
+        "    .filename(\".myapprc\", Format::Json);\n",
+        "\n",
+        "assert_eq!(cfgfifo.identify(\"path/to/file.cfg\").unwrap(), Format::Yaml);\n",
+        "assert_eq!(cfgfifo.identify(\"path/to/.myapprc\").unwrap(), Format::Json);\n",
         "```\n",
     ))]
     /// # Errors
     ///
-    /// Returns an error if the given file path does not have an extension, the
-    /// extension is not valid Unicode, or the extension does not belong to a
-    /// supported [`Format`].
+    /// Returns an error if the given file path does not match a file name
+    /// rule and either does not have an extension, has an extension that is
+    /// not valid Unicode, or has an extension that does not belong to a
+    /// supported format.
     ///
     /// All error conditions are suppressed if a [fallback][Cfgfifo::fallback]
     /// was set.
-    pub fn identify<P: AsRef<Path>>(&self, path: P) -> Result<Format, IdentifyError> {
-        let ext = match (get_ext(path.as_ref()), self.fallback) {
-            (Ok(ext), _) => ext,
-            #[allow(unreachable_patterns)]
-            (Err(_), Some(f)) => return Ok(f),
-            (Err(e), _) => return Err(e),
-        };
-        self.formats
+    pub fn identify<P: AsRef<Path>>(&self, path: P) -> Result<AnyFormat, IdentifyError> {
+        match self.identify_path(path.as_ref()) {
+            Ok(f) => Ok(f),
+            Err(e) => self.fallback.map(AnyFormat::from).ok_or(e),
+        }
+    }
+
+    /// Identify the format of a file path without using the fallback
+    fn identify_path(&self, path: &Path) -> Result<AnyFormat, IdentifyError> {
+        let (_, path) = Compression::split(path);
+        if let Some(name) = path.file_name()
+            && let Some((_, f)) = self.filenames.iter().find(|(n, _)| n == name)
+        {
+            return Ok(f.clone());
+        }
+        let ext = get_ext(&path)?;
+        self.extensions
             .iter()
-            .find(|f| f.has_extension(ext))
-            .copied()
-            .or(self.fallback)
+            .find(|(x, _)| x.eq_ignore_ascii_case(ext))
+            .map(|(_, f)| f)
+            .or_else(|| self.formats.iter().find(|f| f.has_extension(ext)))
+            .cloned()
             .ok_or_else(|| IdentifyError::Unknown(ext.to_owned()))
     }
 
+    /// Return the formats to try when loading the given file contents after
+    /// identifying the format from the file path failed, in order of
+    /// preference
+    fn candidates(&self, content: &[u8]) -> Vec<AnyFormat> {
+        let mut candidates: Vec<AnyFormat> = Vec::new();
+        if self.sniff
+            && let Ok(content) = std::str::from_utf8(content)
+        {
+            candidates.extend(
+                Format::sniff(content)
+                    .into_iter()
+                    .filter(|f| self.formats.iter().any(|g| g == f))
+                    .map(AnyFormat::from),
+            );
+        }
+        if self.try_each {
+            for f in &self.formats {
+                if !candidates.contains(f) {
+                    candidates.push(f.clone());
+                }
+            }
+        }
+        if let Some(f) = self.fallback.map(AnyFormat::from)
+            && !candidates.contains(&f)
+        {
+            candidates.push(f);
+        }
+        candidates
+    }
+
     /// Deserialize the contents of the given file, with the format
-    /// automatically determined based on the file's extension.
+    /// automatically determined based on the file's name or extension.
+    ///
+    /// If the format cannot be determined from the file path:
+    ///
+    /// - If [try-each loading][Cfgfifo::try_each] is enabled, the file is
+    ///   deserialized with each supported format in turn (starting with the
+    ///   formats guessed by [content sniffing][Cfgfifo::sniff], if enabled, and
+    ///   ending with the fallback format, if set), and the first successful
+    ///   result is returned.
+    ///
+    /// - Otherwise, if content sniffing is enabled, the format is guessed from
+    ///   the file's contents.
+    ///
+    /// - Otherwise, the fallback format is used, if set.
+    ///
+    /// If the path ends in the file extension of a supported [`Compression`]
+    /// format, the file is decompressed before deserializing.
     ///
     /// # Errors
     ///
-    /// Returns an error if the format cannot be determined from the file
-    /// extension and no fallback format was set, if an I/O error occurs, or if
-    /// the underlying deserializer returns an error.
+    /// Returns an error if the format cannot be determined from the file path
+    /// (or contents, if sniffing is enabled) and no fallback format was set,
+    /// if an I/O error occurs, or if the underlying deserializer returns an
+    /// error.  If try-each loading is enabled and deserialization fails for
+    /// every format tried, a [`LoadError::AllFailed`] listing each format's
+    /// error is returned.
     pub fn load<T: DeserializeOwned, P: AsRef<Path>>(&self, path: P) -> Result<T, LoadError> {
-        let fmt = self.identify(&path)?;
-        let fp = io::BufReader::new(File::open(path).map_err(LoadError::Open)?);
-        fmt.load_from_reader(fp).map_err(Into::into)
+        if self.strict {
+            self.load_with_ignored(path).map(|(value, _)| value)
+        } else {
+            self.load_tracked(path.as_ref(), None)
+                .map(|(value, _)| value)
+        }
     }
 
-    /// Serialize a value to the given file, with the format automatically
-    /// determined based on the file's extension.
+    /// Like [`Cfgfifo::load()`], but also return the paths of any keys in the
+    /// file (or environment, if [`Cfgfifo::env()`] is set) that `T` ignored.
     ///
+    /// A key is ignored when `T` has no field for it and does not reject
+    /// unknown fields, which typically means that the key was misspelled.
+    /// Paths are formatted the same way as the paths in
+    /// [`serde_path_to_error::Path`], e.g., `server.ports[0].name`.  Keys
+    /// inside internally tagged or untagged enums and flattened fields cannot
+    /// be tracked and are never reported.
+    #[cfg_attr(feature = "toml", doc = concat!(
+        "# Example\n",
+        "\n",
+        "```\n",
+        "use cfgfifo::Cfgfifo;\n",
+        "use serde::Deserialize;\n",
+        "\n",
+        "#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]\n",
+        "struct Config {\n",
+        "    #[serde(default)]\n",
+        "    enable_foo: bool,\n",
+        "}\n",
+        "\n",
+        "let tmpdir = tempfile::tempdir().unwrap();\n",
+        "let path = tmpdir.path().join(\"config.toml\");\n",
+        "std::fs::write(&path, \"enalbe_foo = true\\n\").unwrap();\n",
+        "\n",
+        "let (cfg, ignored) = Cfgfifo::new().load_with_ignored::<Config, _>(&path).unwrap();\n",
+        "assert!(!cfg.enable_foo);\n",
+        "assert_eq!(ignored, [\"enalbe_foo\"]);\n",
+        "```\n",
+    ))]
     /// # Errors
     ///
-    /// Returns an error if the format cannot be determined from the file
-    /// extension and no fallback format was set, if an I/O error occurs, or if
-    /// the underlying serializer returns an error.
-    pub fn dump<P: AsRef<Path>, T: Serialize>(&self, path: P, value: &T) -> Result<(), DumpError> {
-        let fmt = self.identify(&path)?;
-        let mut fp = io::BufWriter::new(File::create(path).map_err(DumpError::Open)?);
-        fmt.dump_to_writer(&mut fp, value)?;
-        fp.flush().map_err(DumpError::Flush)
+    /// Returns an error under the same circumstances as [`Cfgfifo::load()`].
+    pub fn load_with_ignored<T: DeserializeOwned, P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(T, Vec<String>), LoadError> {
+        let path = path.as_ref();
+        let mut tracker = Tracker::default();
+        let (value, format) = self.load_tracked(path, Some(&mut tracker))?;
+        self.check_ignored(path, format, &tracker.ignored)?;
+        Ok((value, tracker.ignored))
+    }
+
+    /// Like [`Cfgfifo::load()`], but also return the format that the file was
+    /// successfully deserialized from, and, if `tracker` is given, append the
+    /// paths of any keys that `T` ignored to `tracker.ignored` instead of
+    /// checking them against strict mode
+    fn load_tracked<T: DeserializeOwned>(
+        &self,
+        path: &Path,
+        mut tracker: Option<&mut Tracker>,
+    ) -> Result<(T, AnyFormat), LoadError> {
+        match &self.env {
+            None => self.load_with_format(path, tracker),
+            Some(env) => {
+                let (mut tree, format) =
+                    self.load_with_format::<Value>(path, tracker.as_deref_mut())?;
+                let overlay = env.load()?;
+                overlay.overlay(&mut tree);
+                let strings = overlay.string_paths();
+                let de = OverlayDeserializer::new(tree, &strings);
+                let value = depath_tracked(de, tracker).map_err(|source| LoadError::Overlay {
+                    var: overlay
+                        .var_for(&source.path().to_string())
+                        .map(String::from),
+                    source,
+                })?;
+                Ok((value, format))
+            }
+        }
+    }
+
+    /// Like [`Cfgfifo::load()`], but instead of stopping at the first value
+    /// that fails to deserialize, continue on past it and report every such
+    /// failure at once.
+    ///
+    /// Deserialization is retried with each value that produced an error
+    /// (such as a value of the wrong type or an unknown enum variant) replaced
+    /// by a placeholder, each missing field filled in with a placeholder, and
+    /// each unknown field skipped, until it either succeeds or fails in a way
+    /// that cannot be worked around, such as a syntax error.  Every error
+    /// encountered along the way is returned in a [`LoadError::Invalid`], and
+    /// each error's [path][DeserializeError::path] and
+    /// [location][DeserializeError::location] are available for reporting.
+    ///
+    /// If [strict mode][Cfgfifo::strict] is enabled and any keys were
+    /// ignored, a [`DeserializeError::UnknownKeys`] is included as the last
+    /// error.  Environment variables configured with [`Cfgfifo::env()`] are
+    /// not applied.
+    #[cfg_attr(feature = "toml", doc = concat!(
+        "# Example\n",
+        "\n",
+        "```\n",
+        "use cfgfifo::{Cfgfifo, LoadError};\n",
+        "use serde::Deserialize;\n",
+        "\n",
+        "#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]\n",
+        "struct Config {\n",
+        "    name: String,\n",
+        "    port: u16,\n",
+        "    debug: bool,\n",
+        "}\n",
+        "\n",
+        "let tmpdir = tempfile::tempdir().unwrap();\n",
+        "let path = tmpdir.path().join(\"config.toml\");\n",
+        "std::fs::write(&path, \"debug = 1\\nport = \\\"80\\\"\\n\").unwrap();\n",
+        "\n",
+        "let Err(LoadError::Invalid { errors, .. }) = Cfgfifo::new().validate::<Config, _>(&path) else {\n",
+        "    panic!(\"validation did not fail\");\n",
+        "};\n",
+        "let paths = errors\n",
+        "    .iter()\n",
+        "    .map(|e| e.path().unwrap().to_string())\n",
+        "    .collect::<Vec<_>>();\n",
+        "assert_eq!(paths, [\"debug\", \"port\", \".\"]);\n",
+        "assert!(errors[2].to_string().contains(\"missing field `name`\"));\n",
+        "```\n",
+    ))]
+    /// # Errors
+    ///
+    /// Returns a [`LoadError::Invalid`] if any values failed to deserialize,
+    /// or another error under the same circumstances as [`Cfgfifo::load()`]
+    /// if the file cannot be read or parsed.
+    pub fn validate<T: DeserializeOwned, P: AsRef<Path>>(&self, path: P) -> Result<T, LoadError> {
+        let path = path.as_ref();
+        // Identify the format (and rule out I/O & syntax errors) the same way
+        // as `load()` does
+        let (_, format) = self.load_with_format::<Value>(path, None)?;
+        let content = read_to_string(path)?;
+        let mut tracker = Tracker::default();
+        let r = collect_errors(&mut tracker, |tracker| {
+            format.load_from_str_tracked::<T>(&content, Some(tracker))
+        });
+        let mut errors = match r {
+            Ok(value) => {
+                self.check_ignored(path, format, &tracker.ignored)?;
+                return Ok(value);
+            }
+            Err(errors) => errors,
+        };
+        if self.strict && !tracker.ignored.is_empty() {
+            errors.push(DeserializeError::UnknownKeys(tracker.ignored));
+        }
+        Err(LoadError::Invalid {
+            path: path.to_owned(),
+            format,
+            errors,
+        })
+    }
+
+    /// If strict mode is enabled and `ignored` is nonempty, return a
+    /// [`DeserializeError::UnknownKeys`] error
+    fn check_ignored(
+        &self,
+        path: &Path,
+        format: AnyFormat,
+        ignored: &[String],
+    ) -> Result<(), LoadError> {
+        if self.strict && !ignored.is_empty() {
+            Err(LoadError::Deserialize {
+                path: path.to_owned(),
+                format,
+                source: Box::new(DeserializeError::UnknownKeys(ignored.to_vec())),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Like [`Cfgfifo::load()`], but also return the format that the file
+    /// was successfully deserialized from.  If `tracker` is given, the paths
+    /// of any keys that `T` ignored are appended to `tracker.ignored`.
+    pub(crate) fn load_with_format<T: DeserializeOwned>(
+        &self,
+        path: &Path,
+        mut tracker: Option<&mut Tracker>,
+    ) -> Result<(T, AnyFormat), LoadError> {
+        let (compression, _) = Compression::split(path);
+        let identify_error = |source| LoadError::Identify {
+            path: path.to_owned(),
+            source,
+        };
+        let open_error = |source| LoadError::Open {
+            path: path.to_owned(),
+            source,
+        };
+        let fmt = match self.identify_path(path) {
+            Ok(fmt) => fmt,
+            Err(e) if self.sniff || self.try_each => {
+                let mut content = fs::read(path).map_err(open_error)?;
+                if let Some(c) = compression {
+                    content = c.decompress(&*content).map_err(|source| LoadError::Read {
+                        path: path.to_owned(),
+                        source,
+                    })?;
+                }
+                let candidates = self.candidates(&content);
+                if !self.try_each {
+                    let fmt = candidates
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| identify_error(e))?;
+                    return match fmt.load_from_reader_tracked(&*content, tracker) {
+                        Ok(value) => Ok((value, fmt)),
+                        Err(source) => Err(LoadError::Deserialize {
+                            path: path.to_owned(),
+                            format: fmt,
+                            source: Box::new(source),
+                        }),
+                    };
+                }
+                let mut failures = Vec::new();
+                let start = tracker.as_ref().map(|t| t.ignored.len());
+                for fmt in candidates {
+                    match fmt.load_from_reader_tracked(&*content, tracker.as_deref_mut()) {
+                        Ok(value) => return Ok((value, fmt)),
+                        Err(err) => {
+                            if let (Some(t), Some(start)) = (tracker.as_deref_mut(), start) {
+                                t.ignored.truncate(start);
+                            }
+                            failures.push((fmt, err));
+                        }
+                    }
+                }
+                return Err(if failures.is_empty() {
+                    identify_error(e)
+                } else {
+                    LoadError::AllFailed {
+                        path: path.to_owned(),
+                        failures,
+                    }
+                });
+            }
+            Err(e) => self
+                .fallback
+                .map(AnyFormat::from)
+                .ok_or_else(|| identify_error(e))?,
+        };
+        let fp = io::BufReader::new(File::open(path).map_err(open_error)?);
+        let value = match compression {
+            Some(c) => c
+                .decoder(fp)
+                .map_err(DeserializeError::from)
+                .and_then(|reader| fmt.load_from_reader_tracked(reader, tracker)),
+            None => fmt.load_from_reader_tracked(fp, tracker),
+        };
+        match value {
+            Ok(value) => Ok((value, fmt)),
+            Err(source) => Err(LoadError::Deserialize {
+                path: path.to_owned(),
+                format: fmt,
+                source: Box::new(source),
+            }),
+        }
+    }
+
+    /// Serialize a value to the given file, with the format automatically
+    /// determined based on the file's extension.
+    ///
+    /// If the path ends in the file extension of a supported [`Compression`]
+    /// format, the serialized value is compressed before writing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the format cannot be determined from the file
+    /// extension and no fallback format was set, if an I/O error occurs, or if
+    /// the underlying serializer returns an error.
+    pub fn dump<P: AsRef<Path>, T: Serialize>(&self, path: P, value: &T) -> Result<(), DumpError> {
+        let path = path.as_ref();
+        let fmt = self.identify(path).map_err(|source| DumpError::Identify {
+            path: path.to_owned(),
+            source,
+        })?;
+        self.write_file(path, &fmt, |fp| fmt.dump_to_writer(fp, value))
+    }
+
+    /// Modify the contents of the given file in place, preserving comments &
+    /// layout where possible.
+    ///
+    /// The file's contents are deserialized as with [`Cfgfifo::load()`],
+    /// passed to `f` for modification, and then serialized back to the file
+    /// with [`AnyFormat::update_string()`], which leaves the file's comments,
+    /// whitespace, and key order intact and rewrites only the changed values
+    /// for formats that support it (currently TOML).  For other formats, the
+    /// file is rewritten as with [`Cfgfifo::dump()`].  If [atomic
+    /// writes][Cfgfifo::atomic] are enabled, they are used when writing the
+    /// file back.
+    ///
+    /// On success, the modified value is returned.
+    #[cfg_attr(feature = "toml", doc = concat!(
+        "# Example\n",
+        "\n",
+        "```\n",
+        "use cfgfifo::Cfgfifo;\n",
+        "use serde::{Deserialize, Serialize};\n",
+        "\n",
+        "#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]\n",
+        "struct Config {\n",
+        "    name: String,\n",
+        "    size: u32,\n",
+        "}\n",
+        "\n",
+        "let tmpdir = tempfile::tempdir().unwrap();\n",
+        "let path = tmpdir.path().join(\"config.toml\");\n",
+        "std::fs::write(&path, \"# My config\\nname = 'Example'\\nsize = 42\\n\").unwrap();\n",
+        "\n",
+        "let cfg: Config = Cfgfifo::new().update(&path, |cfg: &mut Config| cfg.size += 1).unwrap();\n",
+        "assert_eq!(cfg.size, 43);\n",
+        "assert_eq!(\n",
+        "    std::fs::read_to_string(&path).unwrap(),\n",
+        "    \"# My config\\nname = 'Example'\\nsize = 43\\n\",\n",
+        ");\n",
+        "```\n",
+    ))]
+    /// # Errors
+    ///
+    /// Returns an error if loading the file fails for any of the reasons
+    /// described under [`Cfgfifo::load()`] or writing it back fails for any of
+    /// the reasons described under [`Cfgfifo::dump()`].
+    pub fn update<T, P, F>(&self, path: P, f: F) -> Result<T, UpdateError>
+    where
+        T: Serialize + DeserializeOwned,
+        P: AsRef<Path>,
+        F: FnOnce(&mut T),
+    {
+        let path = path.as_ref();
+        let fmt = self.identify(path).map_err(|source| LoadError::Identify {
+            path: path.to_owned(),
+            source,
+        })?;
+        let original = read_to_string(path)?;
+        let mut tracker = self.strict.then(Tracker::default);
+        let mut value = fmt
+            .load_from_str_tracked::<T>(&original, tracker.as_mut())
+            .map_err(|source| LoadError::Deserialize {
+                path: path.to_owned(),
+                format: fmt.clone(),
+                source: Box::new(source),
+            })?;
+        if let Some(tracker) = tracker {
+            self.check_ignored(path, fmt.clone(), &tracker.ignored)?;
+        }
+        f(&mut value);
+        let s = fmt
+            .update_string(&original, &value)
+            .map_err(|source| DumpError::Serialize {
+                path: path.to_owned(),
+                format: fmt.clone(),
+                source: Box::new(source),
+            })?;
+        self.write_file(path, &fmt, |fp| {
+            fp.write_all(s.as_bytes())?;
+            if !s.ends_with('\n') {
+                fp.write_all(b"\n")?;
+            }
+            Ok(())
+        })?;
+        Ok(value)
+    }
+
+    /// Convert the file at `src` to the format of the file at `dst`, with both
+    /// formats determined as with [`Cfgfifo::identify()`].
+    ///
+    /// When both formats are built-in, the contents of `src` are converted
+    /// with [`Format::transcode()`] without deserializing them into an
+    /// intermediate type; otherwise, they are loaded into a [`Value`] and
+    /// dumped from that.  Compression and [atomic writes][Cfgfifo::atomic]
+    /// are handled the same way as by [`Cfgfifo::load()`] and
+    /// [`Cfgfifo::dump()`].
+    #[cfg_attr(all(feature = "json", feature = "toml"), doc = concat!(
+        "# Example\n",
+        "\n",
+        "```\n",
+        "use cfgfifo::Cfgfifo;\n",
+        "\n",
+        "let tmpdir = tempfile::tempdir().unwrap();\n",
+        "let src = tmpdir.path().join(\"config.json\");\n",
+        "let dst = tmpdir.path().join(\"config.toml\");\n",
+        "std::fs::write(&src, r#\"{\"name\": \"Example\", \"size\": 42}\"#).unwrap();\n",
+        "\n",
+        "Cfgfifo::new().convert(&src, &dst).unwrap();\n",
+        "assert_eq!(\n",
+        "    std::fs::read_to_string(&dst).unwrap(),\n",
+        "    \"name = \\\"Example\\\"\\nsize = 42\\n\",\n",
+        ");\n",
+        "```\n",
+    ))]
+    /// # Errors
+    ///
+    /// Returns an error if the format of either file cannot be determined, if
+    /// an I/O error occurs, if `src` cannot be deserialized, if `src` contains
+    /// a value that the format of `dst` cannot represent, or if the underlying
+    /// serializer returns an error.
+    pub fn convert<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        src: P,
+        dst: Q,
+    ) -> Result<(), ConvertError> {
+        let src = src.as_ref();
+        let dst = dst.as_ref();
+        let from = self.identify(src).map_err(|source| LoadError::Identify {
+            path: src.to_owned(),
+            source,
+        })?;
+        let to = self.identify(dst).map_err(|source| DumpError::Identify {
+            path: dst.to_owned(),
+            source,
+        })?;
+        let input = read_to_string(src)?;
+        let deserialize_error = |source| LoadError::Deserialize {
+            path: src.to_owned(),
+            format: from.clone(),
+            source,
+        };
+        let serialize_error = |source| DumpError::Serialize {
+            path: dst.to_owned(),
+            format: to.clone(),
+            source,
+        };
+        let output = if let (Some(f), Some(t)) = (from.builtin(), to.builtin()) {
+            Format::transcode(f, t, &input).map_err(|e| match e {
+                TranscodeError::Deserialize { source, .. } => deserialize_error(source).into(),
+                TranscodeError::Serialize { source, .. } => serialize_error(source).into(),
+                TranscodeError::Incompatible(source) => ConvertError::Incompatible {
+                    src: src.to_owned(),
+                    dst: dst.to_owned(),
+                    source,
+                },
+            })?
+        } else {
+            let value = from
+                .load_from_str::<Value>(&input)
+                .map_err(|e| deserialize_error(Box::new(e)))?;
+            to.dump_to_string(&value)
+                .map_err(|e| serialize_error(Box::new(e)))?
+        };
+        self.write_file(dst, &to, |fp| {
+            fp.write_all(output.as_bytes())?;
+            if !output.ends_with('\n') {
+                fp.write_all(b"\n")?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Open `path` for writing (either directly or via a temporary file,
+    /// depending on whether atomic writes are enabled) and call `write` on a
+    /// writer for it that compresses the output if necessary
+    fn write_file<F>(&self, path: &Path, fmt: &AnyFormat, write: F) -> Result<(), DumpError>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<(), SerializeError>,
+    {
+        let compression = Compression::identify(path);
+        let serialize_error = |source| DumpError::Serialize {
+            path: path.to_owned(),
+            format: fmt.clone(),
+            source: Box::new(source),
+        };
+        let flush_error = |source| DumpError::Flush {
+            path: path.to_owned(),
+            source,
+        };
+        let write_to = |file: &File| {
+            let mut fp = io::BufWriter::new(file);
+            if let Some(c) = compression {
+                let mut enc = c
+                    .encoder(fp)
+                    .map_err(|e| serialize_error(SerializeError::from(e)))?;
+                write(&mut enc).map_err(serialize_error)?;
+                fp = enc.finish().map_err(flush_error)?;
+            } else {
+                write(&mut fp).map_err(serialize_error)?;
+            }
+            fp.flush().map_err(flush_error)
+        };
+        if self.atomic {
+            dump_atomic(path, |fp| {
+                write_to(fp)?;
+                fp.sync_all().map_err(flush_error)
+            })
+        } else {
+            let fp = File::create(path).map_err(|source| DumpError::Open {
+                path: path.to_owned(),
+                source,
+            })?;
+            write_to(&fp)
+        }
+    }
+}
+
+/// Read the contents of a file into a string, decompressing them if necessary
+pub(crate) fn read_to_string(path: &Path) -> Result<String, LoadError> {
+    let fp = io::BufReader::new(File::open(path).map_err(|source| LoadError::Open {
+        path: path.to_owned(),
+        source,
+    })?);
+    let s = match Compression::identify(path) {
+        Some(c) => c.decoder(fp).and_then(io::read_to_string),
+        None => io::read_to_string(fp),
+    };
+    s.map_err(|source| LoadError::Read {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+/// Call `write` on a new temporary file in the same directory as `path`, and
+/// then, if it succeeds, move the temporary file to `path`
+fn dump_atomic<F>(path: &Path, write: F) -> Result<(), DumpError>
+where
+    F: FnOnce(&File) -> Result<(), DumpError>,
+{
+    let open_error = |source| DumpError::Open {
+        path: path.to_owned(),
+        source,
+    };
+    let target = if is_symlink(path) {
+        resolve_symlink(path).map_err(open_error)?
+    } else {
+        path.to_owned()
+    };
+    let permissions = match fs::metadata(&target) {
+        Ok(md) => Some(md.permissions()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(open_error(e)),
+    };
+    let dir = match target.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(".cfgfifo-").suffix(".tmp");
+    #[cfg(unix)]
+    if permissions.is_none() {
+        // Match the permissions that `File::create()` would use, rather than
+        // tempfile's default of 0600.
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o666));
+    }
+    let tmp = builder.tempfile_in(dir).map_err(open_error)?;
+    if let Some(perms) = permissions {
+        fs::set_permissions(tmp.path(), perms).map_err(open_error)?;
+    }
+    write(tmp.as_file())?;
+    let replace_error = |source| DumpError::Replace {
+        path: path.to_owned(),
+        source,
+    };
+    tmp.persist(&target).map_err(|e| replace_error(e.error))?;
+    // Sync the directory as well so that the rename itself survives a crash.
+    // Directories cannot be opened as files on Windows, where `MoveFileEx()`
+    // takes care of this instead.
+    #[cfg(unix)]
+    File::open(dir)
+        .and_then(|d| d.sync_all())
+        .map_err(replace_error)?;
+    Ok(())
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|md| md.file_type().is_symlink())
+}
+
+/// Return the path that the symbolic link `path` ultimately points to.  Unlike
+/// `fs::canonicalize()`, this also works for dangling links, so that dumping
+/// through one creates its target as `File::create()` would.
+fn resolve_symlink(path: &Path) -> io::Result<PathBuf> {
+    match fs::canonicalize(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let mut target = path.to_owned();
+            while is_symlink(&target) {
+                let link = fs::read_link(&target)?;
+                // Relative links are resolved against the directory
+                // containing them.
+                target = match target.parent() {
+                    Some(dir) => dir.join(link),
+                    None => link,
+                };
+            }
+            Ok(target)
+        }
+        r => r,
     }
 }
 
@@ -793,6 +2180,69 @@ pub enum SerializeError {
     #[cfg_attr(docsrs, doc(cfg(feature = "yaml")))]
     #[error(transparent)]
     Yaml(#[from] PathError<serde_yaml::Error>),
+
+    /// Returned if INI serialization failed
+    #[cfg(feature = "ini")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ini")))]
+    #[error(transparent)]
+    Ini(PathError<IniError>),
+
+    /// Returned if Java properties serialization failed
+    #[cfg(feature = "properties")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "properties")))]
+    #[error(transparent)]
+    Properties(PathError<PropertiesError>),
+
+    /// Returned if dotenv serialization failed
+    #[cfg(feature = "dotenv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dotenv")))]
+    #[error(transparent)]
+    Dotenv(PathError<DotenvError>),
+
+    /// Returned if HCL serialization failed
+    #[cfg(feature = "hcl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "hcl")))]
+    #[error(transparent)]
+    Hcl(#[from] PathError<::hcl::Error>),
+
+    /// Returned if KDL serialization failed
+    #[cfg(feature = "kdl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "kdl")))]
+    #[error(transparent)]
+    Kdl(PathError<KdlError>),
+
+    /// Returned if serialization with a custom [`Backend`] failed
+    #[error(transparent)]
+    Custom(CustomError),
+}
+
+impl SerializeError {
+    /// Return the path to the value at which the error occurred, if known
+    pub fn path(&self) -> Option<&serde_path_to_error::Path> {
+        match self {
+            SerializeError::Io(_) | SerializeError::Custom(_) => None,
+            #[cfg(any(feature = "json", feature = "json5"))]
+            SerializeError::Json(e) => Some(e.path()),
+            #[cfg(feature = "ron")]
+            SerializeError::RonStart(_) => None,
+            #[cfg(feature = "ron")]
+            SerializeError::Ron(e) => Some(e.path()),
+            #[cfg(feature = "toml")]
+            SerializeError::Toml(e) => Some(e.path()),
+            #[cfg(feature = "yaml")]
+            SerializeError::Yaml(e) => Some(e.path()),
+            #[cfg(feature = "ini")]
+            SerializeError::Ini(e) => Some(e.path()),
+            #[cfg(feature = "properties")]
+            SerializeError::Properties(e) => Some(e.path()),
+            #[cfg(feature = "dotenv")]
+            SerializeError::Dotenv(e) => Some(e.path()),
+            #[cfg(feature = "hcl")]
+            SerializeError::Hcl(e) => Some(e.path()),
+            #[cfg(feature = "kdl")]
+            SerializeError::Kdl(e) => Some(e.path()),
+        }
+    }
 }
 
 /// Error type returned by [`Format::load_from_str()`] and
@@ -850,20 +2300,346 @@ pub enum DeserializeError {
     /// the deserializer
     #[cfg(feature = "toml")]
     #[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
-    #[error(transparent)]
-    TomlParse(toml::de::Error),
+    #[error("{error}")]
+    TomlParse {
+        /// The error that occurred
+        error: toml::de::Error,
+        /// The position in the input of the start of the error's span, if any
+        location: Option<Location>,
+    },
 
     /// Returned if TOML deserialization failed
     #[cfg(feature = "toml")]
     #[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
-    #[error(transparent)]
-    Toml(#[from] PathError<toml::de::Error>),
+    #[error("{error}")]
+    Toml {
+        /// The error that occurred
+        error: Box<PathError<toml::de::Error>>,
+        /// The position in the input of the start of the error's span, if any
+        location: Option<Location>,
+    },
 
     /// Returned if YAML deserialization failed
     #[cfg(feature = "yaml")]
     #[cfg_attr(docsrs, doc(cfg(feature = "yaml")))]
     #[error(transparent)]
     Yaml(#[from] PathError<serde_yaml::Error>),
+
+    /// Returned if INI deserialization failed
+    #[cfg(feature = "ini")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ini")))]
+    #[error(transparent)]
+    Ini(PathError<IniError>),
+
+    /// Returned if Java properties deserialization failed
+    #[cfg(feature = "properties")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "properties")))]
+    #[error(transparent)]
+    Properties(PathError<PropertiesError>),
+
+    /// Returned if dotenv deserialization failed
+    #[cfg(feature = "dotenv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dotenv")))]
+    #[error(transparent)]
+    Dotenv(PathError<DotenvError>),
+
+    /// Returned if HCL deserialization failed
+    #[cfg(feature = "hcl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "hcl")))]
+    #[error(transparent)]
+    Hcl(#[from] PathError<::hcl::Error>),
+
+    /// Returned if KDL deserialization failed
+    #[cfg(feature = "kdl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "kdl")))]
+    #[error(transparent)]
+    Kdl(PathError<KdlError>),
+
+    /// Returned if deserialization with a custom [`Backend`] failed
+    #[error(transparent)]
+    Custom(#[from] PathError<CustomError>),
+
+    /// Returned by [`Cfgfifo::load()`] and [`Cfgfifo::update()`] if [strict
+    /// mode][Cfgfifo::strict] is enabled and the input contained keys that
+    /// the target type ignored
+    #[error("unknown keys: {}", .0.join(", "))]
+    UnknownKeys(
+        /// The paths of the ignored keys
+        Vec<String>,
+    ),
+}
+
+impl DeserializeError {
+    /// Return the position in the input at which the error occurred, if
+    /// known.
+    ///
+    /// Not every error has a position; for example, I/O errors and errors
+    /// from custom [`Backend`]s never do.  Depending on the format, the
+    /// column may count either characters or bytes.
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            DeserializeError::Io(_)
+            | DeserializeError::Custom(_)
+            | DeserializeError::UnknownKeys(_) => None,
+            #[cfg(feature = "json")]
+            DeserializeError::Json(e) => json_location(e.inner()),
+            #[cfg(feature = "json")]
+            DeserializeError::JsonEnd(e) => json_location(e),
+            #[cfg(feature = "json5")]
+            DeserializeError::Json5(e) => e
+                .inner()
+                .position()
+                .map(|pos| Location::new(pos.line + 1, pos.column + 1)),
+            #[cfg(feature = "ron")]
+            DeserializeError::RonStart(e) | DeserializeError::RonEnd(e) => ron_location(e),
+            #[cfg(feature = "ron")]
+            DeserializeError::Ron(e) => ron_location(e.inner()),
+            #[cfg(feature = "toml")]
+            DeserializeError::TomlParse { location, .. }
+            | DeserializeError::Toml { location, .. } => *location,
+            #[cfg(feature = "yaml")]
+            DeserializeError::Yaml(e) => e
+                .inner()
+                .location()
+                .map(|loc| Location::new(loc.line(), loc.column())),
+            #[cfg(feature = "ini")]
+            DeserializeError::Ini(e) => e.inner().location(),
+            #[cfg(feature = "properties")]
+            DeserializeError::Properties(e) => e.inner().location(),
+            #[cfg(feature = "dotenv")]
+            DeserializeError::Dotenv(e) => e.inner().location(),
+            #[cfg(feature = "hcl")]
+            DeserializeError::Hcl(e) => match e.inner() {
+                ::hcl::Error::Parse(e) => {
+                    Some(Location::new(e.location().line(), e.location().column()))
+                }
+                _ => None,
+            },
+            #[cfg(feature = "kdl")]
+            DeserializeError::Kdl(e) => e.inner().location(),
+        }
+    }
+
+    /// Return the byte range of the token or node in the input at which the
+    /// error occurred, if known.
+    ///
+    /// Currently, only errors from TOML and KDL input have spans.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            #[cfg(feature = "toml")]
+            DeserializeError::TomlParse { error, .. } => error.span(),
+            #[cfg(feature = "toml")]
+            DeserializeError::Toml { error, .. } => error.inner().span(),
+            #[cfg(feature = "kdl")]
+            DeserializeError::Kdl(e) => e.inner().span(),
+            _ => None,
+        }
+    }
+
+    /// Return the path to the value at which the error occurred, if known
+    pub fn path(&self) -> Option<&serde_path_to_error::Path> {
+        match self {
+            DeserializeError::Io(_) | DeserializeError::UnknownKeys(_) => None,
+            #[cfg(feature = "json")]
+            DeserializeError::Json(e) => Some(e.path()),
+            #[cfg(feature = "json")]
+            DeserializeError::JsonEnd(_) => None,
+            #[cfg(feature = "json5")]
+            DeserializeError::Json5(e) => Some(e.path()),
+            #[cfg(feature = "ron")]
+            DeserializeError::RonStart(_) | DeserializeError::RonEnd(_) => None,
+            #[cfg(feature = "ron")]
+            DeserializeError::Ron(e) => Some(e.path()),
+            #[cfg(feature = "toml")]
+            DeserializeError::TomlParse { .. } => None,
+            #[cfg(feature = "toml")]
+            DeserializeError::Toml { error, .. } => Some(error.path()),
+            #[cfg(feature = "yaml")]
+            DeserializeError::Yaml(e) => Some(e.path()),
+            #[cfg(feature = "ini")]
+            DeserializeError::Ini(e) => Some(e.path()),
+            #[cfg(feature = "properties")]
+            DeserializeError::Properties(e) => Some(e.path()),
+            #[cfg(feature = "dotenv")]
+            DeserializeError::Dotenv(e) => Some(e.path()),
+            #[cfg(feature = "hcl")]
+            DeserializeError::Hcl(e) => Some(e.path()),
+            #[cfg(feature = "kdl")]
+            DeserializeError::Kdl(e) => Some(e.path()),
+            DeserializeError::Custom(e) => Some(e.path()),
+        }
+    }
+
+    /// If the error is due to an unknown field or enum variant name, return
+    /// the expected name that is closest to it by edit distance, provided that
+    /// it is close enough to plausibly be what was meant.
+    pub fn suggestion(&self) -> Option<String> {
+        match self {
+            #[cfg(feature = "ron")]
+            DeserializeError::RonStart(e) | DeserializeError::RonEnd(e) => ron_suggestion(&e.code),
+            #[cfg(feature = "ron")]
+            DeserializeError::Ron(e) => ron_suggestion(&e.inner().code),
+            _ => suggest::from_message(&self.message()),
+        }
+    }
+
+    /// If the error is serde's "missing field" error, return the name of the
+    /// field
+    fn missing_field(&self) -> Option<String> {
+        match self {
+            #[cfg(feature = "ron")]
+            DeserializeError::Ron(e) => match e.inner().code {
+                ron::error::Error::MissingStructField { field, .. } => Some(field.to_owned()),
+                _ => None,
+            },
+            _ => quoted_name(&self.message(), "missing field `"),
+        }
+    }
+
+    /// If the error is serde's "unknown field" error, return the name of the
+    /// field
+    fn unknown_field(&self) -> Option<String> {
+        match self {
+            #[cfg(feature = "ron")]
+            DeserializeError::Ron(e) => match &e.inner().code {
+                ron::error::Error::NoSuchStructField { found, .. } => Some(found.clone()),
+                _ => None,
+            },
+            _ => quoted_name(&self.message(), "unknown field `"),
+        }
+    }
+
+    /// Render the error message followed by a [`Snippet`] of `source` (which
+    /// must be the input that failed to deserialize) pointing at the error's
+    /// [location][DeserializeError::location] and, if there is one, the
+    /// error's [suggestion][DeserializeError::suggestion].
+    ///
+    /// If the error does not have a location, the snippet is omitted.
+    pub fn render(&self, source: &str) -> String {
+        let help = self
+            .suggestion()
+            .map(|name| format!("\nhelp: did you mean `{name}`?"))
+            .unwrap_or_default();
+        match self.location() {
+            Some(loc) => format!(
+                "{}\n --> {loc}\n{}{help}",
+                self.message(),
+                Snippet::new(source, loc)
+            ),
+            None => format!("{self}{help}"),
+        }
+    }
+
+    /// Return the error message without any source snippet that the
+    /// underlying error may include in its `Display` output
+    fn message(&self) -> String {
+        match self {
+            // toml's errors already render a snippet of the input
+            #[cfg(feature = "toml")]
+            DeserializeError::TomlParse { error, .. } => error.message().to_owned(),
+            #[cfg(feature = "toml")]
+            DeserializeError::Toml { error, .. } => {
+                with_path(error.path(), error.inner().message())
+            }
+            // The location is shown separately in the rendering
+            #[cfg(feature = "ini")]
+            DeserializeError::Ini(e) => with_path(e.path(), e.inner().message()),
+            #[cfg(feature = "properties")]
+            DeserializeError::Properties(e) => with_path(e.path(), e.inner().message()),
+            #[cfg(feature = "dotenv")]
+            DeserializeError::Dotenv(e) => with_path(e.path(), e.inner().message()),
+            // hcl's parse errors already render a snippet of the input
+            #[cfg(feature = "hcl")]
+            DeserializeError::Hcl(e) => match e.inner() {
+                ::hcl::Error::Parse(pe) => with_path(e.path(), pe.message()),
+                _ => e.to_string(),
+            },
+            #[cfg(feature = "kdl")]
+            DeserializeError::Kdl(e) => with_path(e.path(), e.inner().message()),
+            _ => self.to_string(),
+        }
+    }
+}
+
+/// Prefix `msg` with `path` the same way that [`serde_path_to_error::Error`]
+/// displays itself
+#[cfg(any(
+    feature = "toml",
+    feature = "ini",
+    feature = "properties",
+    feature = "dotenv",
+    feature = "hcl",
+    feature = "kdl"
+))]
+fn with_path(path: &serde_path_to_error::Path, msg: &str) -> String {
+    if path
+        .iter()
+        .all(|seg| matches!(seg, serde_path_to_error::Segment::Unknown))
+    {
+        msg.to_owned()
+    } else {
+        format!("{path}: {msg}")
+    }
+}
+
+/// If `msg` contains `prefix`, return the text between it and the next
+/// backtick
+fn quoted_name(msg: &str, prefix: &str) -> Option<String> {
+    let (_, rest) = msg.split_once(prefix)?;
+    let (name, _) = rest.split_once('`')?;
+    Some(name.to_owned())
+}
+
+#[cfg(feature = "json")]
+fn json_location(e: &serde_json::Error) -> Option<Location> {
+    // serde_json reports line 0 for errors without a position
+    (e.line() > 0).then(|| Location::new(e.line(), e.column()))
+}
+
+#[cfg(feature = "ron")]
+fn ron_suggestion(e: &ron::error::Error) -> Option<String> {
+    match e {
+        ron::error::Error::NoSuchEnumVariant {
+            expected, found, ..
+        }
+        | ron::error::Error::NoSuchStructField {
+            expected, found, ..
+        } => suggest::closest(found, expected.iter().copied()).map(String::from),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "ron")]
+fn ron_location(e: &ron::error::SpannedError) -> Option<Location> {
+    let pos = e.span.start;
+    (pos.line > 0).then(|| Location::new(pos.line, pos.col))
+}
+
+#[cfg(feature = "toml")]
+impl DeserializeError {
+    /// Construct a [`DeserializeError::TomlParse`] for an error that occurred
+    /// while parsing `source`
+    fn toml_parse(error: toml::de::Error, source: &str) -> DeserializeError {
+        let location = toml_location(&error, source);
+        DeserializeError::TomlParse { error, location }
+    }
+
+    /// Construct a [`DeserializeError::Toml`] for an error that occurred
+    /// while deserializing `source`
+    fn toml(error: PathError<toml::de::Error>, source: &str) -> DeserializeError {
+        let location = toml_location(error.inner(), source);
+        DeserializeError::Toml {
+            error: Box::new(error),
+            location,
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+fn toml_location(e: &toml::de::Error, source: &str) -> Option<Location> {
+    // `toml::de::Error` only exposes a byte span into the input
+    e.span()
+        .map(|span| Location::from_offset(source, span.start))
 }
 
 #[cfg(feature = "ron")]
@@ -879,16 +2655,179 @@ impl From<PathError<ron::error::SpannedError>> for DeserializeError {
 pub enum LoadError {
     /// Returned if the file format could not be identified from the file
     /// extension
-    #[error("failed to identify file format")]
-    Identify(#[from] IdentifyError),
+    #[error("failed to identify file format of {}", path.display())]
+    Identify {
+        /// The path to the file
+        path: PathBuf,
+        /// The error that occurred
+        source: IdentifyError,
+    },
 
     /// Returned if the file could not be opened for reading
-    #[error("failed to open file for reading")]
-    Open(#[source] io::Error),
+    #[error("failed to open {} for reading", path.display())]
+    Open {
+        /// The path to the file
+        path: PathBuf,
+        /// The error that occurred
+        source: io::Error,
+    },
+
+    /// Returned if reading or decompressing the file's contents failed
+    #[error("failed to read {}", path.display())]
+    Read {
+        /// The path to the file
+        path: PathBuf,
+        /// The error that occurred
+        source: io::Error,
+    },
 
     /// Returned if deserialization failed
-    #[error("failed to deserialize file contents")]
-    Deserialize(#[from] DeserializeError),
+    #[error("failed to deserialize {} as {format}", path.display())]
+    Deserialize {
+        /// The path to the file
+        path: PathBuf,
+        /// The format that the file was deserialized as
+        format: AnyFormat,
+        /// The error that occurred
+        source: Box<DeserializeError>,
+    },
+
+    /// Returned by [`Cfgfifo::validate()`] if any values in the file failed
+    /// to deserialize
+    #[error(
+        "failed to deserialize {} as {format}: found {} error{}",
+        path.display(),
+        errors.len(),
+        if errors.len() == 1 { "" } else { "s" }
+    )]
+    Invalid {
+        /// The path to the file
+        path: PathBuf,
+        /// The format that the file was deserialized as
+        format: AnyFormat,
+        /// The errors that occurred, in the order in which they were
+        /// encountered
+        errors: Vec<DeserializeError>,
+    },
+
+    /// Returned if [try-each loading][Cfgfifo::try_each] was enabled and
+    /// deserialization failed for every format tried
+    #[error("failed to deserialize {} in any format: {}", path.display(), DisplayFailures(failures))]
+    AllFailed {
+        /// The path to the file
+        path: PathBuf,
+        /// Each format tried, in order, along with the error it produced
+        failures: Vec<(AnyFormat, DeserializeError)>,
+    },
+
+    /// Returned if reading the environment variables configured with
+    /// [`Cfgfifo::env()`] failed
+    #[error("failed to read configuration from environment")]
+    Env(#[from] EnvError),
+
+    /// Returned if deserializing the file's contents with the environment
+    /// variables configured with [`Cfgfifo::env()`] overlaid on top failed
+    #[error("{}", match var {
+        Some(var) => format!("failed to deserialize value of environment variable {var}"),
+        None => String::from("failed to deserialize file contents overlaid with environment variables"),
+    })]
+    Overlay {
+        /// The name of the environment variable that set the value that
+        /// failed to deserialize, if the value came from the environment
+        var: Option<String>,
+        /// The error that occurred
+        source: PathError<ValueError>,
+    },
+}
+
+impl LoadError {
+    /// Return the position in the file at which deserialization failed, if
+    /// known.
+    ///
+    /// This is only ever `Some` for [`LoadError::Deserialize`] errors; see
+    /// [`DeserializeError::location()`].
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            LoadError::Deserialize { source, .. } => source.location(),
+            _ => None,
+        }
+    }
+
+    /// Return the byte range of the token or node in the file at which
+    /// deserialization failed, if known.
+    ///
+    /// This is only ever `Some` for [`LoadError::Deserialize`] errors; see
+    /// [`DeserializeError::span()`].
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            LoadError::Deserialize { source, .. } => source.span(),
+            _ => None,
+        }
+    }
+
+    /// If deserialization failed due to an unknown field or enum variant
+    /// name, return the closest expected name; see
+    /// [`DeserializeError::suggestion()`].
+    pub fn suggestion(&self) -> Option<String> {
+        match self {
+            LoadError::Deserialize { source, .. } => source.suggestion(),
+            LoadError::Overlay { source, .. } => suggest::from_message(&source.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Helper for displaying the errors in [`LoadError::AllFailed`]
+struct DisplayFailures<'a>(&'a [(AnyFormat, DeserializeError)]);
+
+impl std::fmt::Display for DisplayFailures<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (fmt, e)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{fmt}: {e}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error type returned by [`Cfgfifo::update()`]
+#[derive(Debug, Error)]
+pub enum UpdateError {
+    /// Returned if reading or deserializing the file failed
+    #[error("failed to load file")]
+    Load(#[from] LoadError),
+
+    /// Returned if serializing or writing back the modified value failed
+    #[error("failed to write back file")]
+    Dump(#[from] DumpError),
+}
+
+/// Error type returned by [`Cfgfifo::convert()`]
+#[derive(Debug, Error)]
+pub enum ConvertError {
+    /// Returned if identifying the format of, reading, or deserializing the
+    /// source file failed
+    #[error("failed to load source file")]
+    Load(#[from] LoadError),
+
+    /// Returned if the source file contained a value that the format of the
+    /// destination file cannot represent
+    #[error("cannot convert {} to {}", src.display(), dst.display())]
+    Incompatible {
+        /// The path to the source file
+        src: PathBuf,
+        /// The path to the destination file
+        dst: PathBuf,
+        /// The value that could not be represented
+        source: Incompatibility,
+    },
+
+    /// Returned if identifying the format of, serializing to, or writing the
+    /// destination file failed
+    #[error("failed to write destination file")]
+    Dump(#[from] DumpError),
 }
 
 /// Error type returned by [`dump()`] and [`Cfgfifo::dump()`]
@@ -896,20 +2835,52 @@ pub enum LoadError {
 pub enum DumpError {
     /// Returned if the file format could not be identified from the file
     /// extension
-    #[error("failed to identify file format")]
-    Identify(#[from] IdentifyError),
+    #[error("failed to identify file format of {}", path.display())]
+    Identify {
+        /// The path to the file
+        path: PathBuf,
+        /// The error that occurred
+        source: IdentifyError,
+    },
 
     /// Returned if the file could not be opened for writing
-    #[error("failed to open file for writing")]
-    Open(#[source] io::Error),
+    #[error("failed to open {} for writing", path.display())]
+    Open {
+        /// The path to the file
+        path: PathBuf,
+        /// The error that occurred
+        source: io::Error,
+    },
 
     /// Returned if serialization failed
-    #[error("failed to serialize structure")]
-    Serialize(#[from] SerializeError),
+    #[error("failed to serialize structure as {format} to {}", path.display())]
+    Serialize {
+        /// The path to the file
+        path: PathBuf,
+        /// The format that the structure was serialized as
+        format: AnyFormat,
+        /// The error that occurred
+        source: Box<SerializeError>,
+    },
 
     /// Returned if flushing the file failed after writing
-    #[error("failed to flush output file")]
-    Flush(#[source] io::Error),
+    #[error("failed to flush {}", path.display())]
+    Flush {
+        /// The path to the file
+        path: PathBuf,
+        /// The error that occurred
+        source: io::Error,
+    },
+
+    /// Returned if an atomic write failed to move the temporary file into
+    /// place
+    #[error("failed to replace {}", path.display())]
+    Replace {
+        /// The path to the file
+        path: PathBuf,
+        /// The error that occurred
+        source: io::Error,
+    },
 }
 
 #[cfg(feature = "ron")]
@@ -920,6 +2891,15 @@ fn ron_config() -> PrettyConfig {
 }
 
 fn get_ext(path: &Path) -> Result<&str, IdentifyError> {
+    // A file named just `.env` has no extension as far as `Path` is
+    // concerned, but it is the canonical name for dotenv files
+    #[cfg(feature = "dotenv")]
+    if path
+        .file_name()
+        .is_some_and(|name| name.eq_ignore_ascii_case(".env"))
+    {
+        return Ok("env");
+    }
     path.extension()
         .ok_or(IdentifyError::NoExtension)?
         .to_str()
@@ -932,7 +2912,7 @@ mod tests {
     use rstest::rstest;
 
     #[rstest]
-    #[case("file.ini", "ini")]
+    #[case("file.txt", "txt")]
     #[case("file.xml", "xml")]
     #[case("file.cfg", "cfg")]
     #[case("file.jsn", "jsn")]
@@ -1305,6 +3285,42 @@ mod tests {
             assert!(cfg.identify("file").is_err());
         }
 
+        #[cfg(all(feature = "json", feature = "toml", feature = "yaml"))]
+        #[test]
+        fn extension() {
+            let cfg = Cfgfifo::new()
+                .extension("conf", Format::Toml)
+                .extension(".CFG", Format::Yaml)
+                .extension("json", Format::Yaml)
+                .extension("cfg", Format::Json);
+            assert_eq!(cfg.identify("app.conf").unwrap(), Format::Toml);
+            assert_eq!(cfg.identify("app.CONF").unwrap(), Format::Toml);
+            assert_eq!(cfg.identify("settings.cfg").unwrap(), Format::Yaml);
+            assert_eq!(cfg.identify("file.json").unwrap(), Format::Yaml);
+            assert_eq!(cfg.identify("file.toml").unwrap(), Format::Toml);
+            assert!(cfg.identify("file.txt").is_err());
+            assert!(cfg.identify("conf").is_err());
+        }
+
+        #[cfg(all(feature = "json", feature = "toml", feature = "yaml"))]
+        #[test]
+        fn filename() {
+            let cfg = Cfgfifo::new()
+                .filename("Cargo.lock", Format::Toml)
+                .filename(".eslintrc", Format::Json)
+                .filename("config.json", Format::Yaml)
+                .extension("lock", Format::Json);
+            assert_eq!(cfg.identify("Cargo.lock").unwrap(), Format::Toml);
+            assert_eq!(cfg.identify("path/to/Cargo.lock").unwrap(), Format::Toml);
+            assert_eq!(cfg.identify("cargo.lock").unwrap(), Format::Json);
+            assert_eq!(cfg.identify("yarn.lock").unwrap(), Format::Json);
+            assert_eq!(cfg.identify("/home/me/.eslintrc").unwrap(), Format::Json);
+            assert!(cfg.identify(".eslintrc.bak").is_err());
+            assert_eq!(cfg.identify("config.json").unwrap(), Format::Yaml);
+            assert_eq!(cfg.identify("other.json").unwrap(), Format::Json);
+            assert_eq!(cfg.identify(".myapprc"), Err(IdentifyError::NoExtension));
+        }
+
         #[cfg(all(feature = "json", feature = "toml", feature = "yaml"))]
         #[test]
         fn formats_fallback() {
@@ -0,0 +1,672 @@
+//! Finding the positions of values within serialized documents
+use crate::Format;
+use crate::scalar::ScalarError;
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::ser;
+use std::fmt;
+use std::ops::Range;
+
+/// A line & column position within a file.
+///
+/// Both the line and column are 1-based.  Depending on the format, the column
+/// may count either characters or bytes.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Location {
+    line: usize,
+    column: usize,
+}
+
+impl Location {
+    /// Create a new `Location` from a 1-based line and column
+    pub fn new(line: usize, column: usize) -> Location {
+        Location { line, column }
+    }
+
+    /// Compute the `Location` of a byte offset within a string.
+    ///
+    /// The resulting column counts characters.  Offsets past the end of the
+    /// string are clamped to the end of the string.
+    pub fn from_offset(s: &str, offset: usize) -> Location {
+        let mut offset = offset.min(s.len());
+        while !s.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &s[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Location {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    /// Return the 1-based line number
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Return the 1-based column number
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Error type for (de)serialization of the formats whose parsers &
+/// serializers are implemented by this crate, such as INI.
+///
+/// The error's `Display` output consists of the message followed by the
+/// location, if known.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LocatedError {
+    message: String,
+    location: Option<Location>,
+    span: Option<Range<usize>>,
+}
+
+impl LocatedError {
+    #[allow(dead_code)]
+    pub(crate) fn new<S: Into<String>>(message: S, location: Location) -> LocatedError {
+        LocatedError {
+            message: message.into(),
+            location: Some(location),
+            span: None,
+        }
+    }
+
+    /// Attach `location` and the byte range `span` of the offending input to
+    /// the error if it does not already have a location
+    #[allow(dead_code)]
+    #[must_use]
+    pub(crate) fn at_span(mut self, location: Location, span: Range<usize>) -> LocatedError {
+        if self.location.is_none() {
+            self.location = Some(location);
+            self.span = Some(span);
+        }
+        self
+    }
+
+    /// Return the error message without the location
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Return the position in the input at which the error occurred, if
+    /// known.
+    ///
+    /// The column counts characters.
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+
+    /// Return the byte range of the token or node in the input at which the
+    /// error occurred, if known.
+    ///
+    /// Currently, only KDL errors record spans.
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.span.clone()
+    }
+}
+
+impl fmt::Display for LocatedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(loc) = self.location {
+            write!(f, " at line {} column {}", loc.line(), loc.column())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LocatedError {}
+
+impl de::Error for LocatedError {
+    fn custom<T: fmt::Display>(msg: T) -> LocatedError {
+        LocatedError {
+            message: msg.to_string(),
+            location: None,
+            span: None,
+        }
+    }
+}
+
+impl ser::Error for LocatedError {
+    fn custom<T: fmt::Display>(msg: T) -> LocatedError {
+        LocatedError {
+            message: msg.to_string(),
+            location: None,
+            span: None,
+        }
+    }
+}
+
+impl ScalarError for LocatedError {
+    fn at(mut self, location: Location) -> LocatedError {
+        if self.location.is_none() {
+            self.location = Some(location);
+        }
+        self
+    }
+}
+
+/// A rendering of the source lines at a [`Location`] with a caret pointing at
+/// the location's column, for use in error messages.
+///
+/// The `Display` implementation prints the line before the location (if any)
+/// and the line containing the location, each prefixed with its line number,
+/// followed by a caret line:
+///
+/// ```text
+///   |
+/// 3 | host = "localhost"
+/// 4 | port = "high"
+///   |        ^
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Snippet<'a> {
+    source: &'a str,
+    location: Location,
+}
+
+impl<'a> Snippet<'a> {
+    /// Create a `Snippet` of `source` at `location`
+    pub fn new(source: &'a str, location: Location) -> Snippet<'a> {
+        Snippet { source, location }
+    }
+
+    /// Return the location that the snippet points to
+    pub fn location(&self) -> Location {
+        self.location
+    }
+}
+
+impl fmt::Display for Snippet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lineno = self.location.line.max(1);
+        let line_at = |n: usize| {
+            self.source
+                .split('\n')
+                .nth(n - 1)
+                .map_or("", |ln| ln.strip_suffix('\r').unwrap_or(ln))
+        };
+        let width = lineno.to_string().len();
+        writeln!(f, "{:width$} |", "")?;
+        if lineno > 1 {
+            writeln!(f, "{:width$} | {}", lineno - 1, line_at(lineno - 1))?;
+        }
+        let line = line_at(lineno);
+        writeln!(f, "{lineno:width$} | {line}")?;
+        // Keep tabs in the padding so that the caret lines up with the
+        // source line when displayed.
+        let pad = line
+            .chars()
+            .take(self.location.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        write!(f, "{:width$} | {pad}^", "")
+    }
+}
+
+/// A component of a path to a value within a document
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Format a path in the same style as [`serde_path_to_error::Path`]
+pub(crate) fn display_path(path: &[Segment]) -> String {
+    if path.is_empty() {
+        return String::from(".");
+    }
+    let mut s = String::new();
+    for seg in path {
+        match seg {
+            Segment::Key(key) => {
+                if !s.is_empty() {
+                    s.push('.');
+                }
+                s.push_str(key);
+            }
+            Segment::Index(i) => {
+                s.push('[');
+                s.push_str(&i.to_string());
+                s.push(']');
+            }
+        }
+    }
+    s
+}
+
+/// Message of the error used to abort deserialization upon reaching the
+/// sought value
+const MARKER: &str = "cfgfifo: value located";
+
+/// Find the location of the value at `path` within `content`, a document in
+/// the given format.
+///
+/// Returns `None` if the document cannot be parsed, the path does not exist,
+/// or the format does not report positions.
+#[allow(unused_variables)]
+pub(crate) fn locate(format: Format, content: &str, path: &[Segment]) -> Option<Location> {
+    let seed = Locator {
+        path,
+        request_str: false,
+    };
+    match format {
+        #[cfg(feature = "json")]
+        Format::Json => {
+            let mut de = serde_json::Deserializer::from_str(content);
+            let seed = Locator {
+                request_str: true,
+                ..seed
+            };
+            let e = seed.deserialize(&mut de).err()?;
+            if !e.to_string().contains(MARKER) {
+                return None;
+            }
+            // serde_json reports the position of the last byte consumed,
+            // which is either the end of a scalar or the byte before a map
+            // or sequence
+            let line_start = content
+                .split_inclusive('\n')
+                .take(e.line().saturating_sub(1))
+                .map(str::len)
+                .sum::<usize>();
+            let last = (line_start + e.column()).saturating_sub(1);
+            Some(Location::from_offset(
+                content,
+                json_value_start(content, last),
+            ))
+        }
+        #[cfg(feature = "json5")]
+        Format::Json5 => {
+            let mut de = json5::Deserializer::from_str(content);
+            let e = seed.deserialize(&mut de).err()?;
+            let pos = e.position().filter(|_| e.to_string().contains(MARKER))?;
+            Some(Location::new(pos.line + 1, pos.column + 1))
+        }
+        #[cfg(feature = "ron")]
+        Format::Ron => {
+            let mut de = ron::Deserializer::from_str(content).ok()?;
+            let e = seed.deserialize(&mut de).err()?;
+            let e = de.span_error(e);
+            (e.code.to_string().contains(MARKER))
+                .then(|| Location::new(e.span.start.line, e.span.start.col))
+        }
+        #[cfg(feature = "toml")]
+        Format::Toml => {
+            let de = toml::Deserializer::parse(content).ok()?;
+            let e = seed.deserialize(de).err()?;
+            let span = e.span().filter(|_| e.message().contains(MARKER))?;
+            Some(Location::from_offset(content, span.start))
+        }
+        #[cfg(feature = "yaml")]
+        Format::Yaml => {
+            let de = serde_yaml::Deserializer::from_str(content);
+            let e = seed.deserialize(de).err()?;
+            let loc = e.location().filter(|_| e.to_string().contains(MARKER))?;
+            Some(Location::new(loc.line(), loc.column()))
+        }
+        #[cfg(feature = "ini")]
+        Format::Ini => {
+            let de = crate::IniOptions::new().deserializer(content).ok()?;
+            let e = seed.deserialize(de).err()?;
+            e.location().filter(|_| e.message().contains(MARKER))
+        }
+        #[cfg(feature = "properties")]
+        Format::Properties => {
+            let de = crate::properties::deserializer(content).ok()?;
+            let e = seed.deserialize(de).err()?;
+            e.location().filter(|_| e.message().contains(MARKER))
+        }
+        #[cfg(feature = "dotenv")]
+        Format::Dotenv => {
+            let de = crate::DotenvOptions::new().deserializer(content).ok()?;
+            let e = seed.deserialize(de).err()?;
+            e.location().filter(|_| e.message().contains(MARKER))
+        }
+        #[cfg(feature = "kdl")]
+        Format::Kdl => {
+            let de = crate::kdl::deserializer(content).ok()?;
+            let e = seed.deserialize(de).err()?;
+            e.location().filter(|_| e.message().contains(MARKER))
+        }
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Given the byte offset `last` of either the last byte of a JSON scalar or
+/// the byte before the start of a JSON array or object, return the byte
+/// offset at which the value starts
+#[cfg(feature = "json")]
+fn json_value_start(content: &str, last: usize) -> usize {
+    let bytes = content.as_bytes();
+    let is_delim = |b: u8| matches!(b, b' ' | b'\t' | b'\n' | b'\r' | b',' | b':' | b'[');
+    match bytes.get(last) {
+        Some(b'"') => {
+            let mut i = last;
+            while i > 0 {
+                i -= 1;
+                if bytes[i] == b'"' {
+                    let backslashes = bytes[..i].iter().rev().take_while(|&&b| b == b'\\');
+                    if backslashes.count() % 2 == 0 {
+                        return i;
+                    }
+                }
+            }
+            last
+        }
+        Some(&b) if is_delim(b) => {
+            let mut i = last + 1;
+            while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+                i += 1;
+            }
+            i
+        }
+        Some(b'{') | None => last,
+        Some(_) => {
+            let mut i = last;
+            while i > 0 && !is_delim(bytes[i - 1]) {
+                i -= 1;
+            }
+            i
+        }
+    }
+}
+
+/// Deserializes a document, ignoring everything except the path to the
+/// sought value; upon reaching that value, deserialization is aborted with an
+/// error, which the deserializer annotates with the current position.
+struct Locator<'a> {
+    path: &'a [Segment],
+
+    /// Whether to request a string rather than any value upon reaching the
+    /// sought value.  `serde_json` only reports the start of a map or
+    /// sequence when rejecting one without consuming it, which is what
+    /// requesting a string does.
+    request_str: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for Locator<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        if !self.path.is_empty() {
+            deserializer.deserialize_any(self)
+        } else if self.request_str {
+            deserializer.deserialize_str(Found)
+        } else {
+            deserializer.deserialize_any(Found)
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for Locator<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, _v: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _v: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i128<E>(self, _v: i128) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _v: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u128<E>(self, _v: u128) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _v: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _v: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_bytes<E>(self, _v: &[u8]) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_none<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let Some((Segment::Index(target), rest)) = self.path.split_first() else {
+            while seq.next_element::<IgnoredAny>()?.is_some() {}
+            return Ok(());
+        };
+        let mut i = 0;
+        loop {
+            let found = if i == *target {
+                seq.next_element_seed(Locator { path: rest, ..self })?
+            } else {
+                seq.next_element::<IgnoredAny>()?.map(|_| ())
+            };
+            if found.is_none() {
+                return Ok(());
+            }
+            i += 1;
+        }
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let target = match self.path.split_first() {
+            Some((Segment::Key(key), rest)) => Some((key, rest)),
+            _ => None,
+        };
+        while let Some(key) = map.next_key::<KeyString>()? {
+            match target {
+                Some((target, rest)) if key.0 == *target => {
+                    map.next_value_seed(Locator { path: rest, ..self })?;
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A visitor that fails on any input, used to mark the sought value
+struct Found;
+
+impl Visitor<'_> for Found {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(MARKER)
+    }
+}
+
+/// A map key stringified in the same way as by [`crate::value::Value`]
+struct KeyString(String);
+
+impl<'de> de::Deserialize<'de> for KeyString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<KeyString, D::Error> {
+        crate::value::KeySeed
+            .deserialize(deserializer)
+            .map(KeyString)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+    use rstest::rstest;
+
+    fn path(s: &[&str]) -> Vec<Segment> {
+        s.iter()
+            .map(|seg| match seg.parse::<usize>() {
+                Ok(i) => Segment::Index(i),
+                Err(_) => Segment::Key((*seg).to_owned()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn from_offset() {
+        let s = "ab\ncdé\nf";
+        assert_eq!(Location::from_offset(s, 0), Location::new(1, 1));
+        assert_eq!(Location::from_offset(s, 3), Location::new(2, 1));
+        assert_eq!(Location::from_offset(s, 7), Location::new(2, 4));
+        assert_eq!(Location::from_offset(s, 8), Location::new(3, 1));
+        assert_eq!(Location::from_offset(s, 100), Location::new(3, 2));
+    }
+
+    #[test]
+    fn snippet() {
+        let src = "[server]\nhost = \"localhost\"\nport = \"high\"\n";
+        assert_eq!(
+            Snippet::new(src, Location::new(3, 8)).to_string(),
+            "  |\n2 | host = \"localhost\"\n3 | port = \"high\"\n  |        ^"
+        );
+        assert_eq!(
+            Snippet::new(src, Location::new(1, 2)).to_string(),
+            "  |\n1 | [server]\n  |  ^"
+        );
+    }
+
+    #[test]
+    fn snippet_tabs_and_past_end() {
+        let src = "a\r\n\tb = c\r\n";
+        assert_eq!(
+            Snippet::new(src, Location::new(2, 4)).to_string(),
+            "  |\n1 | a\n2 | \tb = c\n  | \t  ^"
+        );
+        assert_eq!(
+            Snippet::new(src, Location::new(2, 40)).to_string(),
+            "  |\n1 | a\n2 | \tb = c\n  | \t     ^"
+        );
+        assert_eq!(
+            Snippet::new(src, Location::new(3, 1)).to_string(),
+            "  |\n2 | \tb = c\n3 | \n  | ^"
+        );
+    }
+
+    #[test]
+    fn display_paths() {
+        assert_eq!(display_path(&[]), ".");
+        assert_eq!(display_path(&path(&["a", "b"])), "a.b");
+        assert_eq!(display_path(&path(&["a", "0", "b", "1"])), "a[0].b[1]");
+    }
+
+    #[cfg(feature = "json")]
+    #[rstest]
+    #[case(&["name"], Some(Location::new(2, 11)))]
+    #[case(&["server"], Some(Location::new(3, 13)))]
+    #[case(&["server", "port"], Some(Location::new(4, 13)))]
+    #[case(&["tags"], Some(Location::new(6, 11)))]
+    #[case(&["tags", "1"], Some(Location::new(7, 10)))]
+    #[case(&["escaped"], Some(Location::new(9, 14)))]
+    #[case(&["server", "nope"], None)]
+    #[case(&["tags", "5"], None)]
+    fn locate_json(#[case] p: &[&str], #[case] loc: Option<Location>) {
+        let content = concat!(
+            "{\n",
+            "  \"name\": \"Example\",\n",
+            "  \"server\": {\n",
+            "    \"port\": 8080\n",
+            "  },\n",
+            "  \"tags\": [\n",
+            "    \"a\", \"b\"\n",
+            "  ],\n",
+            "  \"escaped\": \"a\\\"b\\\\\"\n",
+            "}\n",
+        );
+        assert_eq!(locate(Format::Json, content, &path(p)), loc);
+    }
+
+    #[cfg(feature = "json5")]
+    #[test]
+    fn locate_json5() {
+        let content = "{\n  // comment\n  name: 'Example',\n  server: {port: 8080},\n}\n";
+        let loc = locate(Format::Json5, content, &path(&["server", "port"]));
+        assert_eq!(loc, Some(Location::new(4, 18)));
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn locate_ron() {
+        let content = "(\n    name: \"Example\",\n    server: (\n        port: 8080,\n    ),\n)\n";
+        let loc = locate(Format::Ron, content, &path(&["server", "port"]));
+        assert_eq!(loc, Some(Location::new(4, 15)));
+    }
+
+    #[cfg(feature = "toml")]
+    #[rstest]
+    #[case(&["name"], Some(Location::new(2, 8)))]
+    #[case(&["server", "port"], Some(Location::new(5, 8)))]
+    #[case(&["people", "1", "name"], Some(Location::new(11, 8)))]
+    #[case(&["server"], Some(Location::new(4, 1)))]
+    #[case(&["server", "nope"], None)]
+    fn locate_toml(#[case] p: &[&str], #[case] loc: Option<Location>) {
+        let content = concat!(
+            "# Comment\n",
+            "name = \"Example\"\n",
+            "\n",
+            "[server]\n",
+            "port = 8080\n",
+            "\n",
+            "[[people]]\n",
+            "name = \"Alice\"\n",
+            "\n",
+            "[[people]]\n",
+            "name = \"Bob\"\n",
+        );
+        assert_eq!(locate(Format::Toml, content, &path(p)), loc);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[rstest]
+    #[case(&["name"], Some(Location::new(2, 7)))]
+    #[case(&["server", "port"], Some(Location::new(4, 9)))]
+    #[case(&["tags", "1"], Some(Location::new(7, 5)))]
+    #[case(&["server"], Some(Location::new(4, 3)))]
+    #[case(&["server", "nope"], None)]
+    fn locate_yaml(#[case] p: &[&str], #[case] loc: Option<Location>) {
+        let content = concat!(
+            "# Comment\n",
+            "name: Example\n",
+            "server:\n",
+            "  port: 8080\n",
+            "tags:\n",
+            "  - a\n",
+            "  - b\n",
+        );
+        assert_eq!(locate(Format::Yaml, content, &path(p)), loc);
+    }
+}
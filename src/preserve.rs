@@ -0,0 +1,281 @@
+//! Format-preserving updates of serialized documents
+use toml_edit::{Array, ArrayOfTables, DocumentMut, InlineTable, Item, Table, Value};
+
+/// Merge the freshly-serialized TOML document `new` into the existing TOML
+/// document `original`, keeping the comments, whitespace, key order, and
+/// value representations of `original` for everything that did not change.
+///
+/// If either document fails to parse, `new` is returned unmodified.
+pub(crate) fn update_toml(original: &str, new: String) -> String {
+    let (Ok(mut doc), Ok(new_doc)) = (original.parse::<DocumentMut>(), new.parse::<DocumentMut>())
+    else {
+        return new;
+    };
+    merge_table(doc.as_table_mut(), new_doc.into_table());
+    doc.to_string()
+}
+
+fn merge_table(old: &mut Table, new: Table) {
+    old.retain(|key, _| new.contains_key(key));
+    for (key, mut item) in new {
+        if let Some(slot) = old.get_mut(&key) {
+            merge_item(slot, item);
+        } else {
+            // Let the new table be positioned relative to its old siblings
+            // rather than at its position in the new document
+            clear_positions(&mut item);
+            old.insert(&key, item);
+        }
+    }
+}
+
+fn merge_item(old: &mut Item, new: Item) {
+    match (old, new) {
+        (Item::Table(old), Item::Table(new)) => merge_table(old, new),
+        (Item::Table(old), Item::Value(Value::InlineTable(new))) => {
+            merge_table(old, new.into_table());
+        }
+        (Item::ArrayOfTables(old), Item::ArrayOfTables(new)) => merge_tables(old, new),
+        (Item::Value(old), Item::Value(new)) => merge_value(old, new),
+        (Item::Value(old @ Value::InlineTable(_)), Item::Table(new)) => {
+            merge_value(old, Value::InlineTable(new.into_inline_table()));
+        }
+        (old, mut new) => {
+            clear_positions(&mut new);
+            *old = new;
+        }
+    }
+}
+
+fn merge_tables(old: &mut ArrayOfTables, new: ArrayOfTables) {
+    let len = new.len();
+    for (i, mut table) in new.into_iter().enumerate() {
+        if let Some(slot) = old.get_mut(i) {
+            merge_table(slot, table);
+        } else {
+            table.set_position(None);
+            old.push(table);
+        }
+    }
+    while old.len() > len {
+        old.remove(old.len() - 1);
+    }
+}
+
+fn merge_value(old: &mut Value, new: Value) {
+    match (old, new) {
+        (Value::InlineTable(old), Value::InlineTable(new)) => merge_inline_table(old, new),
+        (Value::Array(old), Value::Array(new)) => merge_array(old, new),
+        (old, mut new) => {
+            if !scalar_eq(old, &new) {
+                *new.decor_mut() = old.decor().clone();
+                *old = new;
+            }
+        }
+    }
+}
+
+fn merge_inline_table(old: &mut InlineTable, new: InlineTable) {
+    old.retain(|key, _| new.contains_key(key));
+    for (key, value) in new {
+        if let Some(slot) = old.get_mut(&key) {
+            merge_value(slot, value);
+        } else {
+            old.insert(key, value);
+        }
+    }
+}
+
+fn merge_array(old: &mut Array, new: Array) {
+    let len = new.len();
+    for (i, value) in new.into_iter().enumerate() {
+        if let Some(slot) = old.get_mut(i) {
+            merge_value(slot, value);
+        } else {
+            old.push(value);
+        }
+    }
+    while old.len() > len {
+        old.remove(old.len() - 1);
+    }
+}
+
+/// Test whether two values are equal scalars, ignoring their representations
+fn scalar_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => a.value() == b.value(),
+        (Value::Integer(a), Value::Integer(b)) => a.value() == b.value(),
+        (Value::Float(a), Value::Float(b)) => a.value().to_bits() == b.value().to_bits(),
+        (Value::Boolean(a), Value::Boolean(b)) => a.value() == b.value(),
+        (Value::Datetime(a), Value::Datetime(b)) => a.value() == b.value(),
+        _ => false,
+    }
+}
+
+/// Clear the document positions of all tables in `item` so that they are
+/// rendered after the tables preceding them in their new document
+fn clear_positions(item: &mut Item) {
+    match item {
+        Item::Table(table) => {
+            table.set_position(None);
+            for (_, item) in table.iter_mut() {
+                clear_positions(item);
+            }
+        }
+        Item::ArrayOfTables(tables) => {
+            for table in tables.iter_mut() {
+                table.set_position(None);
+                for (_, item) in table.iter_mut() {
+                    clear_positions(item);
+                }
+            }
+        }
+        Item::None | Item::Value(_) => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn unchanged() {
+        let original = indoc! {r#"
+            # Leading comment
+            name = 'Example'  # trailing comment
+            size = 0x2A
+
+            [server]
+            # The port
+            port = 8080
+            hosts = [
+                "a",  # first
+                "b",
+            ]
+        "#};
+        let new = indoc! {r#"
+            name = "Example"
+            size = 42
+
+            [server]
+            port = 8080
+            hosts = ["a", "b"]
+        "#};
+        assert_eq!(update_toml(original, new.to_owned()), original);
+    }
+
+    #[test]
+    fn changed_value() {
+        let original = indoc! {"
+            # Leading comment
+            name = 'Example'  # trailing comment
+            size = 0x2A
+
+            [server]
+            # The port
+            port = 8080
+        "};
+        let new = indoc! {r#"
+            name = "Changed"
+            size = 42
+
+            [server]
+            port = 9000
+        "#};
+        assert_eq!(
+            update_toml(original, new.to_owned()),
+            indoc! {r#"
+                # Leading comment
+                name = "Changed"  # trailing comment
+                size = 0x2A
+
+                [server]
+                # The port
+                port = 9000
+            "#}
+        );
+    }
+
+    #[test]
+    fn added_and_removed() {
+        let original = indoc! {r#"
+            name = "Example"
+            # Going away
+            obsolete = true
+
+            [server]
+            port = 8080
+
+            [[people]]
+            name = "Alice"
+        "#};
+        let new = indoc! {r#"
+            name = "Example"
+            enabled = true
+
+            [server]
+            port = 8080
+            host = "localhost"
+
+            [[people]]
+            name = "Alice"
+
+            [[people]]
+            name = "Bob"
+
+            [extra]
+            x = 1
+        "#};
+        assert_eq!(
+            update_toml(original, new.to_owned()),
+            indoc! {r#"
+                name = "Example"
+                enabled = true
+
+                [server]
+                port = 8080
+                host = "localhost"
+
+                [[people]]
+                name = "Alice"
+
+                [[people]]
+                name = "Bob"
+
+                [extra]
+                x = 1
+            "#}
+        );
+    }
+
+    #[test]
+    fn dotted_and_inline_tables_kept() {
+        let original = indoc! {"
+            server.port = 8080
+            point = { x = 1, y = 2 }
+        "};
+        let new = indoc! {"
+            [server]
+            port = 9000
+
+            [point]
+            x = 1
+            y = 3
+        "};
+        assert_eq!(
+            update_toml(original, new.to_owned()),
+            indoc! {"
+                server.port = 9000
+                point = { x = 1, y = 3 }
+            "}
+        );
+    }
+
+    #[test]
+    fn invalid_original() {
+        let new = String::from("name = \"Example\"\n");
+        assert_eq!(update_toml("name = ", new.clone()), new);
+    }
+}
@@ -0,0 +1,271 @@
+//! Support for the Java properties format
+use crate::keyvalue::{Entry, Node, NodeDeserializer, Shape, flatten};
+use crate::locate::{LocatedError, Location};
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_path_to_error::{Error as PathError, Track};
+use std::fmt::Write;
+
+/// Error type for Java properties (de)serialization; an alias of [`LocatedError`]
+pub type PropertiesError = LocatedError;
+
+/// Parse a properties document and return a deserializer for it
+pub(crate) fn deserializer(
+    s: &str,
+) -> Result<NodeDeserializer<PropertiesError>, PathError<PropertiesError>> {
+    let node = parse(s).map_err(|e| PathError::new(Track::new().path(), e))?;
+    Ok(NodeDeserializer::new(node, true))
+}
+
+/// Serialize a value as a properties document
+pub(crate) fn to_string<T: Serialize>(value: &T) -> Result<String, PathError<PropertiesError>> {
+    let shape = Shape {
+        max_depth: usize::MAX,
+        root_scalars: true,
+        multiline: true,
+        indexed_seqs: true,
+        check_key,
+    };
+    let entries = flatten(value, &shape)?;
+    let mut out = String::new();
+    for Entry { path, value } in entries {
+        // Empty maps cannot be represented and are omitted
+        if let Some(value) = value {
+            escape(&mut out, &path.join("."), true);
+            out.push('=');
+            escape(&mut out, &value, false);
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+/// Check whether `key` can be used as a component of a dotted key
+fn check_key(key: &str, _depth: usize) -> Result<(), String> {
+    if key.is_empty() {
+        Err(String::from("keys cannot be empty"))
+    } else if key.contains('.') {
+        Err(format!("key {key:?} cannot contain \".\""))
+    } else {
+        Ok(())
+    }
+}
+
+/// Append `s` to `out`, escaped for use as a key (if `is_key` is true) or
+/// value in a properties document.
+///
+/// As with Java's `Properties.store()`, characters outside of printable ASCII
+/// are written as `\uXXXX` escapes so that the output can be read in either
+/// UTF-8 or ISO 8859-1.
+fn escape(out: &mut String, s: &str, is_key: bool) {
+    for (i, c) in s.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\x0C' => out.push_str("\\f"),
+            ' ' if is_key || i == 0 => out.push_str("\\ "),
+            '=' | ':' if is_key => {
+                out.push('\\');
+                out.push(c);
+            }
+            '#' | '!' if i == 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            _ => {
+                let mut units = [0; 2];
+                for u in c.encode_utf16(&mut units) {
+                    // Writing to a `String` cannot fail
+                    let _ = write!(out, "\\u{u:04X}");
+                }
+            }
+        }
+    }
+}
+
+/// Returns whether `c` is whitespace that separates keys from values
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\x0C')
+}
+
+/// Parse a properties document into a [`Node`]
+fn parse(s: &str) -> Result<Node, PropertiesError> {
+    let s = s.strip_prefix('\u{FEFF}').unwrap_or(s);
+    let mut root = IndexMap::new();
+    let mut lines = (1..).zip(s.lines());
+    while let Some((lineno, line)) = lines.next() {
+        let content = line.trim_start_matches(is_space);
+        if content.is_empty() || content.starts_with(['#', '!']) {
+            continue;
+        }
+        // Join the logical line's physical lines, recording the location of
+        // each character
+        let mut chars = Vec::new();
+        let mut column = line.chars().count() - content.chars().count() + 1;
+        let mut lineno = lineno;
+        let mut segment = content;
+        loop {
+            let backslashes = segment.chars().rev().take_while(|&c| c == '\\').count();
+            let continued = backslashes % 2 == 1;
+            let segment_chars = segment.strip_suffix('\\').filter(|_| continued);
+            for c in segment_chars.unwrap_or(segment).chars() {
+                chars.push((c, Location::new(lineno, column)));
+                column += 1;
+            }
+            if !continued {
+                break;
+            }
+            let Some((next_lineno, next)) = lines.next() else {
+                break;
+            };
+            segment = next.trim_start_matches(is_space);
+            lineno = next_lineno;
+            column = next.chars().count() - segment.chars().count() + 1;
+        }
+        let end = Location::new(lineno, column);
+        parse_entry(&chars, end, &mut root)?;
+    }
+    Ok(Node::Map(root, None))
+}
+
+/// Parse a logical line, given as characters & their locations, and insert
+/// its key & value into `root`.  `end` is the location just past the end of
+/// the line.
+fn parse_entry(
+    chars: &[(char, Location)],
+    end: Location,
+    root: &mut IndexMap<String, Node>,
+) -> Result<(), PropertiesError> {
+    let key_loc = chars.first().map_or(end, |&(_, loc)| loc);
+    let mut iter = chars.iter().copied().peekable();
+    let mut key = String::new();
+    while let Some(&(c, loc)) = iter.peek() {
+        if c == '=' || c == ':' || is_space(c) {
+            break;
+        }
+        iter.next();
+        if c == '\\' {
+            key.push(unescape(&mut iter, loc)?);
+        } else {
+            key.push(c);
+        }
+    }
+    while iter.next_if(|&(c, _)| is_space(c)).is_some() {}
+    if iter.next_if(|&(c, _)| c == '=' || c == ':').is_some() {
+        while iter.next_if(|&(c, _)| is_space(c)).is_some() {}
+    }
+    let value_loc = iter.peek().map_or(end, |&(_, loc)| loc);
+    let mut value = String::new();
+    while let Some((c, loc)) = iter.next() {
+        if c == '\\' {
+            value.push(unescape(&mut iter, loc)?);
+        } else {
+            value.push(c);
+        }
+    }
+    insert(root, &key, Node::Scalar(value, value_loc), key_loc)
+}
+
+/// Decode the escape sequence following a backslash at `loc`
+fn unescape<I>(iter: &mut I, loc: Location) -> Result<char, PropertiesError>
+where
+    I: Iterator<Item = (char, Location)>,
+{
+    let Some((c, _)) = iter.next() else {
+        // A lone backslash at the end of the input
+        return Ok('\\');
+    };
+    match c {
+        't' => Ok('\t'),
+        'n' => Ok('\n'),
+        'r' => Ok('\r'),
+        'f' => Ok('\x0C'),
+        'u' => {
+            let high = hex4(iter, loc)?;
+            if !(0xD800..0xDC00).contains(&high) {
+                return char::from_u32(high)
+                    .ok_or_else(|| PropertiesError::new("invalid \\u escape", loc));
+            }
+            // A high surrogate must be followed by an escaped low surrogate
+            let low = match (iter.next(), iter.next()) {
+                (Some(('\\', _)), Some(('u', _))) => hex4(iter, loc)?,
+                _ => {
+                    return Err(PropertiesError::new(
+                        "unpaired surrogate in \\u escape",
+                        loc,
+                    ));
+                }
+            };
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(PropertiesError::new(
+                    "unpaired surrogate in \\u escape",
+                    loc,
+                ));
+            }
+            char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                .ok_or_else(|| PropertiesError::new("invalid \\u escape", loc))
+        }
+        c => Ok(c),
+    }
+}
+
+/// Read four hexadecimal digits
+fn hex4<I>(iter: &mut I, loc: Location) -> Result<u32, PropertiesError>
+where
+    I: Iterator<Item = (char, Location)>,
+{
+    let mut n = 0;
+    for _ in 0..4 {
+        let digit = iter
+            .next()
+            .and_then(|(c, _)| c.to_digit(16))
+            .ok_or_else(|| PropertiesError::new("malformed \\u escape", loc))?;
+        n = n * 16 + digit;
+    }
+    Ok(n)
+}
+
+/// Insert `node` into `root` at the path obtained by splitting `key` on
+/// periods
+fn insert(
+    root: &mut IndexMap<String, Node>,
+    key: &str,
+    node: Node,
+    loc: Location,
+) -> Result<(), PropertiesError> {
+    let mut segments = key.split('.').collect::<Vec<_>>();
+    if segments.iter().any(|seg| seg.is_empty()) {
+        return Err(PropertiesError::new(
+            format!("key {key:?} has an empty component"),
+            loc,
+        ));
+    }
+    let Some(last) = segments.pop() else {
+        unreachable!("split() should yield at least one item");
+    };
+    let conflict = || {
+        PropertiesError::new(
+            format!("key {key:?} conflicts with a key that has both a value and subkeys"),
+            loc,
+        )
+    };
+    let mut map = root;
+    for seg in segments {
+        let entry = map
+            .entry(seg.to_owned())
+            .or_insert_with(|| Node::Map(IndexMap::new(), Some(loc)));
+        match entry {
+            Node::Map(entries, _) => map = entries,
+            Node::Scalar(..) => return Err(conflict()),
+        }
+    }
+    if let Some(Node::Map(..)) = map.get(last) {
+        return Err(conflict());
+    }
+    // As in Java, later values for the same key replace earlier ones
+    map.insert(last.to_owned(), node);
+    Ok(())
+}
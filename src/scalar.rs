@@ -0,0 +1,298 @@
+//! Deserializing untyped string values, such as those of key-value formats
+//! and environment variables, as whatever types are requested
+use crate::locate::Location;
+use crate::value::Value;
+use serde::de::{self, DeserializeSeed, Deserializer, Expected, SeqAccess, Unexpected, Visitor};
+use std::marker::PhantomData;
+
+/// Trait for the error types of [`ScalarDeserializer`]
+pub(crate) trait ScalarError: de::Error {
+    /// Attach `location` to the error if it does not already have a location
+    #[must_use]
+    fn at(self, location: Location) -> Self;
+}
+
+/// Attach `location` (if any) to the error in `r`
+pub(crate) fn at<T, E: ScalarError>(r: Result<T, E>, location: Option<Location>) -> Result<T, E> {
+    match location {
+        Some(loc) => r.map_err(|e| e.at(loc)),
+        None => r,
+    }
+}
+
+/// Parse a string as a boolean (`true` or `false`), an integer, or a float if
+/// possible, and otherwise return it as a string
+fn parse_scalar(value: &str) -> Value {
+    if let Ok(b) = value.parse::<bool>() {
+        Value::Bool(b)
+    } else if let Ok(i) = value.parse::<i128>() {
+        Value::Integer(i)
+    } else if value.bytes().any(|b| b.is_ascii_digit())
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+        && let Ok(f) = value.parse::<f64>()
+    {
+        Value::Float(f)
+    } else {
+        Value::String(value.to_owned())
+    }
+}
+
+/// A [`Deserializer`] for a string value that coerces it to whatever type is
+/// requested.
+///
+/// When asked to deserialize the value as any type, it is parsed as a
+/// boolean, integer, or float if possible and `parse_values` is true.
+/// Booleans may also be written as `yes`/`no`, `on`/`off`, or `1`/`0`; empty
+/// strings deserialize as `None` and `()`; and strings deserialized as
+/// sequences are split on commas.
+pub(crate) struct ScalarDeserializer<E> {
+    value: String,
+    location: Option<Location>,
+    parse_values: bool,
+    error: PhantomData<E>,
+}
+
+impl<E: ScalarError> ScalarDeserializer<E> {
+    pub(crate) fn new(value: String, location: Option<Location>, parse_values: bool) -> Self {
+        ScalarDeserializer {
+            value,
+            location,
+            parse_values,
+            error: PhantomData,
+        }
+    }
+
+    fn invalid_value(&self, exp: &dyn Expected) -> E {
+        self.locate(de::Error::invalid_value(Unexpected::Str(&self.value), exp))
+    }
+
+    fn invalid_type(&self, exp: &dyn Expected) -> E {
+        self.locate(de::Error::invalid_type(Unexpected::Str(&self.value), exp))
+    }
+
+    /// Attach the value's location (if any) to `e`
+    fn locate(&self, e: E) -> E {
+        match self.location {
+            Some(loc) => e.at(loc),
+            None => e,
+        }
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident($ty:ty),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+                match self.value.trim().parse::<$ty>() {
+                    Ok(x) => at(visitor.$visit(x), self.location),
+                    Err(_) => Err(self.invalid_value(&visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de, E: ScalarError> Deserializer<'de> for ScalarDeserializer<E> {
+    type Error = E;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        if !self.parse_values {
+            return at(visitor.visit_string(self.value), self.location);
+        }
+        let r = match parse_scalar(&self.value) {
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Integer(i) => {
+                if let Ok(i) = i64::try_from(i) {
+                    visitor.visit_i64(i)
+                } else if let Ok(u) = u64::try_from(i) {
+                    visitor.visit_u64(u)
+                } else {
+                    visitor.visit_i128(i)
+                }
+            }
+            Value::Float(f) => visitor.visit_f64(f),
+            _ => visitor.visit_string(self.value),
+        };
+        at(r, self.location)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        match self.value.trim().to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => at(visitor.visit_bool(true), self.location),
+            "false" | "no" | "off" | "0" => at(visitor.visit_bool(false), self.location),
+            _ => Err(self.invalid_value(&visitor)),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        let mut chars = self.value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => at(visitor.visit_char(c), self.location),
+            _ => Err(self.invalid_value(&visitor)),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        at(visitor.visit_string(self.value), self.location)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        at(
+            visitor.visit_byte_buf(self.value.into_bytes()),
+            self.location,
+        )
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        if self.value.is_empty() {
+            at(visitor.visit_none(), self.location)
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        if self.value.is_empty() {
+            at(visitor.visit_unit(), self.location)
+        } else {
+            Err(self.invalid_value(&visitor))
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        let items = if self.value.trim().is_empty() {
+            Vec::new()
+        } else {
+            self.value.split(',').map(|s| s.trim().to_owned()).collect()
+        };
+        at(
+            visitor.visit_seq(ListAccess {
+                iter: items.into_iter(),
+                location: self.location,
+                parse_values: self.parse_values,
+                error: PhantomData,
+            }),
+            self.location,
+        )
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, E> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        Err(self.invalid_type(&visitor))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        let location = self.location;
+        at(
+            visitor.visit_enum(de::value::StringDeserializer::<E>::new(self.value)),
+            location,
+        )
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_unit()
+    }
+}
+
+/// A [`SeqAccess`] over the comma-separated elements of a string value
+struct ListAccess<E> {
+    iter: std::vec::IntoIter<String>,
+    location: Option<Location>,
+    parse_values: bool,
+    error: PhantomData<E>,
+}
+
+impl<'de, E: ScalarError> SeqAccess<'de> for ListAccess<E> {
+    type Error = E;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, E> {
+        match self.iter.next() {
+            Some(item) => seed
+                .deserialize(ScalarDeserializer::new(
+                    item,
+                    self.location,
+                    self.parse_values,
+                ))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
@@ -0,0 +1,528 @@
+//! Heuristic identification of file formats based on file contents
+use crate::Format;
+
+/// Guess the formats that the given file contents could be in, returning the
+/// enabled [`Format`]s with nonzero likelihood, most likely first.
+pub(crate) fn sniff(s: &str) -> Vec<Format> {
+    let scores = Scores::new(s);
+    let mut ranked = Format::iter()
+        .map(|f| (f, scores.get(f)))
+        .filter(|&(_, score)| score > 0)
+        .collect::<Vec<_>>();
+    // `sort_by_key()` is stable, so ties are broken by `Format` order.
+    ranked.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    ranked.into_iter().map(|(f, _)| f).collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Scores {
+    json: u32,
+    json5: u32,
+    ron: u32,
+    toml: u32,
+    yaml: u32,
+    ini: u32,
+    properties: u32,
+    dotenv: u32,
+    hcl: u32,
+    kdl: u32,
+}
+
+impl Scores {
+    #[allow(unused_variables)]
+    fn get(&self, format: Format) -> u32 {
+        match format {
+            #[cfg(feature = "json")]
+            Format::Json => self.json,
+            #[cfg(feature = "json5")]
+            Format::Json5 => self.json5,
+            #[cfg(feature = "ron")]
+            Format::Ron => self.ron,
+            #[cfg(feature = "toml")]
+            Format::Toml => self.toml,
+            #[cfg(feature = "yaml")]
+            Format::Yaml => self.yaml,
+            #[cfg(feature = "ini")]
+            Format::Ini => self.ini,
+            #[cfg(feature = "properties")]
+            Format::Properties => self.properties,
+            #[cfg(feature = "dotenv")]
+            Format::Dotenv => self.dotenv,
+            #[cfg(feature = "hcl")]
+            Format::Hcl => self.hcl,
+            #[cfg(feature = "kdl")]
+            Format::Kdl => self.kdl,
+        }
+    }
+
+    fn new(s: &str) -> Scores {
+        let mut scores = Scores::default();
+        let s = s.strip_prefix('\u{FEFF}').unwrap_or(s);
+        let (body, c_comments) = skip_comments(s);
+        if body.is_empty() {
+            return scores;
+        }
+        if body.starts_with("---") || body.starts_with("%YAML") {
+            scores.yaml += 5;
+        } else if body.starts_with("#![enable(") {
+            scores.ron += 5;
+        } else if body.starts_with('(') || starts_with_call(body) {
+            scores.ron += 4;
+        } else if body.starts_with('[') && is_toml_header(first_line(body)) {
+            scores.toml += 4;
+        } else if body.starts_with('{') || body.starts_with('[') {
+            if c_comments || has_json5_syntax(body) {
+                scores.json5 += 4;
+                scores.json += 1;
+            } else {
+                scores.json += 4;
+                scores.json5 += 3;
+            }
+            scores.yaml += 1;
+            scores.ron += 1;
+            if has_call(body) {
+                scores.ron += 4;
+            }
+        } else {
+            let mut toml_lines = 0;
+            let mut yaml_lines = 0;
+            let mut hcl_blocks = 0;
+            let mut kdl_nodes = 0;
+            for line in body.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if is_kdl_node(line) {
+                    kdl_nodes += 1;
+                } else if is_toml_header(line) || is_toml_keyval(line) {
+                    toml_lines += 1;
+                } else if is_yaml_keyval(line) || line == "-" || line.starts_with("- ") {
+                    yaml_lines += 1;
+                } else if is_hcl_block_header(line) {
+                    hcl_blocks += 1;
+                }
+            }
+            if hcl_blocks > 0 {
+                // HCL attributes look like TOML, but TOML has no blocks
+                scores.hcl += 8;
+            } else if toml_lines > 0 {
+                scores.hcl += 1;
+            }
+            if kdl_nodes > toml_lines + yaml_lines {
+                // KDL's block-style nodes look like HCL blocks
+                scores.kdl += 9;
+            } else if kdl_nodes > 0 {
+                scores.kdl += 1;
+            }
+            if toml_lines > 0 || yaml_lines > 0 {
+                if toml_lines > yaml_lines {
+                    scores.toml += 4;
+                    scores.yaml += 1;
+                } else {
+                    scores.yaml += 4;
+                    if toml_lines > 0 {
+                        scores.toml += 1;
+                    }
+                }
+            }
+        }
+        if scores.toml >= 4 {
+            // INI looks like TOML, but with unquoted strings
+            let ini_syntax = has_ini_syntax(body);
+            scores.ini += if ini_syntax { 5 } else { 1 };
+            // Java properties looks like INI, but without sections
+            let has_header = body.lines().any(|line| is_toml_header(line.trim()));
+            scores.properties += if ini_syntax && !has_header { 6 } else { 1 };
+            if !has_header && has_dotenv_syntax(body) {
+                scores.dotenv += 7;
+            }
+        }
+        scores
+    }
+}
+
+/// Skip leading whitespace and comments.  Returns the remaining text and
+/// whether any C-style (`//` or `/* */`) comments were skipped.
+fn skip_comments(mut s: &str) -> (&str, bool) {
+    let mut c_comments = false;
+    loop {
+        s = s.trim_start();
+        if s.starts_with('#') && !s.starts_with("#![") {
+            s = s.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(rest) = s.strip_prefix("//") {
+            c_comments = true;
+            s = rest.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(rest) = s.strip_prefix("/*") {
+            c_comments = true;
+            s = rest.split_once("*/").map_or("", |(_, rest)| rest);
+        } else {
+            return (s, c_comments);
+        }
+    }
+}
+
+fn first_line(s: &str) -> &str {
+    s.lines().next().unwrap_or_default().trim()
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-')
+}
+
+/// Test whether `s` is a (possibly dotted and/or quoted) TOML key
+fn is_toml_key(s: &str) -> bool {
+    let s = s.trim();
+    !s.is_empty()
+        && !s.starts_with('.')
+        && !s.ends_with('.')
+        && s.chars()
+            .all(|c| is_key_char(c) || matches!(c, '.' | ' ' | '"' | '\''))
+}
+
+/// Test whether a line is a TOML table or array-of-tables header
+fn is_toml_header(line: &str) -> bool {
+    let line = match line.split_once('#') {
+        Some((pre, _)) => pre.trim_end(),
+        None => line,
+    };
+    if let Some(inner) = line.strip_prefix("[[").and_then(|s| s.strip_suffix("]]")) {
+        is_toml_key(inner)
+    } else if let Some(inner) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        is_toml_key(inner)
+    } else {
+        false
+    }
+}
+
+/// Test whether a line is a TOML `key = value` pair
+fn is_toml_keyval(line: &str) -> bool {
+    line.split_once('=')
+        .is_some_and(|(key, value)| is_toml_key(key) && !value.starts_with('='))
+}
+
+/// Test whether `s` contains lines that are valid in INI but not TOML: `;`
+/// comments and `key = value` pairs whose values are empty or unquoted
+/// strings
+fn has_ini_syntax(s: &str) -> bool {
+    s.lines().map(str::trim).any(|line| {
+        line.starts_with(';')
+            || line.split_once('=').is_some_and(|(key, value)| {
+                let value = value.trim();
+                is_toml_key(key)
+                    && !value.starts_with(['"', '\'', '[', '{', '='])
+                    && !value.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-')
+                    && !matches!(value, "true" | "false" | "inf" | "nan")
+            })
+    })
+}
+
+/// Test whether every `key = value` line in `s` is a dotenv assignment with an
+/// uppercase key, and at least one line is such an assignment.  An `export`
+/// prefix also marks a line as dotenv, regardless of its key's case.
+fn has_dotenv_syntax(s: &str) -> bool {
+    let mut found = false;
+    for line in s.lines().map(str::trim) {
+        if !is_toml_keyval(line) {
+            continue;
+        }
+        let Some((key, _)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim_end();
+        let is_dotenv = if let Some(key) = key.strip_prefix("export ") {
+            key.trim_start().chars().all(is_key_char)
+        } else {
+            key.starts_with(|c: char| c.is_ascii_uppercase() || c == '_')
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        };
+        if !is_dotenv {
+            return false;
+        }
+        found = true;
+    }
+    found
+}
+
+/// Test whether a line is the start of an HCL block: an identifier followed by
+/// zero or more labels (quoted strings or identifiers) and an opening brace
+fn is_hcl_block_header(line: &str) -> bool {
+    let Some(header) = line.strip_suffix('{') else {
+        return false;
+    };
+    let mut words = header.split_whitespace();
+    words.next().is_some_and(is_hcl_identifier)
+        && words.all(|w| {
+            is_hcl_identifier(w) || (w.len() >= 2 && w.starts_with('"') && w.ends_with('"'))
+        })
+}
+
+fn is_hcl_identifier(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && s.chars().all(is_key_char)
+}
+
+/// Test whether a line is a KDL node whose first entry is a `key=value`
+/// property or an argument that is quoted, numeric, or a `#` keyword.  Lines
+/// that end with an opening brace are left to [`is_hcl_block_header()`], and
+/// `-` nodes must start with a property so as not to be mistaken for YAML
+/// sequence items.
+fn is_kdl_node(line: &str) -> bool {
+    if line.ends_with('{') {
+        return false;
+    }
+    let Some((name, rest)) = line.split_once([' ', '\t']) else {
+        return false;
+    };
+    if !(name == "-" || is_hcl_identifier(name)) || name == "export" {
+        return false;
+    }
+    let entry = rest.trim_start();
+    let is_property = entry.split_once('=').is_some_and(|(key, value)| {
+        is_hcl_identifier(key) && !value.is_empty() && !value.starts_with(['=', ' '])
+    });
+    is_property
+        || (name != "-"
+            && (entry.starts_with(['"', '#'])
+                || entry
+                    .strip_prefix('-')
+                    .unwrap_or(entry)
+                    .starts_with(|c: char| c.is_ascii_digit())))
+}
+
+/// Test whether a line is a YAML `key: value` pair
+fn is_yaml_keyval(line: &str) -> bool {
+    let line = line.strip_prefix("- ").unwrap_or(line);
+    let key = if let Some(i) = line.find(": ") {
+        &line[..i]
+    } else if let Some(key) = line.strip_suffix(':') {
+        key
+    } else {
+        return false;
+    };
+    !key.is_empty() && !key.starts_with(['{', '[', '(']) && !key.contains('=')
+}
+
+/// Test whether `s` starts with an identifier followed by an opening
+/// parenthesis, as in a RON named struct
+fn starts_with_call(s: &str) -> bool {
+    let ident_len = s
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(s.len());
+    ident_len > 0
+        && s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s[ident_len..].trim_start().starts_with('(')
+}
+
+/// Test whether `s` contains, outside of a string, an identifier followed by
+/// an opening parenthesis, as in a RON named struct or enum variant
+fn has_call(s: &str) -> bool {
+    outside_strings(s).any(|(i, c)| {
+        c == '('
+            && s[..i]
+                .trim_end()
+                .ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Test whether `s` contains syntax that is valid in JSON5 but not JSON:
+/// single-quoted strings, unquoted keys, trailing commas, or comments
+fn has_json5_syntax(s: &str) -> bool {
+    let mut prev: Option<char> = None;
+    for (i, c) in outside_strings(s) {
+        match c {
+            '\'' => return true,
+            '/' if prev == Some('/') || s[i + 1..].starts_with('*') => return true,
+            '}' | ']' if prev == Some(',') => return true,
+            c if (c.is_ascii_alphabetic() || c == '_' || c == '$')
+                && matches!(prev, Some('{' | ',')) =>
+            {
+                let rest = &s[i..];
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+                    .unwrap_or(rest.len());
+                if rest[end..].trim_start().starts_with(':') {
+                    return true;
+                }
+            }
+            _ => (),
+        }
+        if !c.is_whitespace() {
+            prev = Some(c);
+        }
+    }
+    false
+}
+
+/// Iterate over the characters of `s` and their byte indices, skipping over
+/// the contents and closing quotes of double-quoted strings
+fn outside_strings(s: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut in_string = false;
+    let mut escaped = false;
+    s.char_indices().filter(move |&(_, c)| {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            false
+        } else if c == '"' {
+            in_string = true;
+            true
+        } else {
+            true
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use rstest::rstest;
+
+    #[test]
+    fn json() {
+        let s = indoc! {r#"
+            {
+              "name": "Example",
+              "size": 42,
+              "tags": ["a", "b"]
+            }
+        "#};
+        let scores = Scores::new(s);
+        assert!(scores.json > scores.json5, "{scores:?}");
+        assert!(scores.json5 > scores.yaml, "{scores:?}");
+        assert_eq!(scores.toml, 0);
+    }
+
+    #[rstest]
+    #[case("// comment\n{\"a\": 1}")]
+    #[case("{a: 1}")]
+    #[case("{\"a\": 'b'}")]
+    #[case("{\"a\": [1, 2,],}")]
+    #[case("{\n  \"a\": 1, /* comment */\n}")]
+    fn json5(#[case] s: &str) {
+        let scores = Scores::new(s);
+        assert!(scores.json5 > scores.json, "{scores:?}");
+        assert!(scores.json5 > scores.ron, "{scores:?}");
+    }
+
+    #[test]
+    fn json_string_lookalikes() {
+        let scores = Scores::new(r#"{"url": "http://example.com", "key": "it's", "x": "a,}"}"#);
+        assert!(scores.json > scores.json5, "{scores:?}");
+    }
+
+    #[rstest]
+    #[case("Config(\n    name: \"Example\",\n)")]
+    #[case("(name: \"Example\", size: 42)")]
+    #[case("#![enable(implicit_some)]\n(name: \"Example\")")]
+    #[case("[Some(1), None]")]
+    #[case("{\"msg\": Response(id: 1)}")]
+    fn ron(#[case] s: &str) {
+        let scores = Scores::new(s);
+        assert!(scores.ron > scores.json, "{scores:?}");
+        assert!(scores.ron > scores.json5, "{scores:?}");
+        assert!(scores.ron > scores.yaml, "{scores:?}");
+    }
+
+    #[rstest]
+    #[case("name = \"Example\"\nsize = 42\n")]
+    #[case("# comment\n[server]\nport = 8080\n")]
+    #[case("[[people]]\nname = \"Alice\"\n")]
+    #[case("[\"quoted key\".sub] # comment\nx = 1\n")]
+    #[case("a.b.c = 1\nurl = \"http://example.com\"\n")]
+    fn toml(#[case] s: &str) {
+        let scores = Scores::new(s);
+        assert!(scores.toml > scores.yaml, "{scores:?}");
+        assert!(scores.toml > scores.json, "{scores:?}");
+        assert!(scores.toml > scores.ron, "{scores:?}");
+        assert!(scores.toml > scores.ini, "{scores:?}");
+    }
+
+    #[rstest]
+    #[case("[server]\nhost = localhost\nport = 8080\n")]
+    #[case("; comment\n[server]\nport = 8080\n")]
+    #[case("name = Example\n\n[server]\nenabled =\n")]
+    fn ini(#[case] s: &str) {
+        let scores = Scores::new(s);
+        assert!(scores.ini > scores.toml, "{scores:?}");
+        assert!(scores.ini > scores.yaml, "{scores:?}");
+        assert!(scores.ini > scores.properties, "{scores:?}");
+    }
+
+    #[rstest]
+    #[case("APP_NAME=Example\nAPP_PORT=8080\n")]
+    #[case("# comment\nexport db_url=\"postgres://localhost/app\"\n")]
+    #[case("HOST=localhost\nURL=http://${HOST}:8080 # comment\n")]
+    fn dotenv(#[case] s: &str) {
+        let scores = Scores::new(s);
+        assert!(scores.dotenv > scores.properties, "{scores:?}");
+        assert!(scores.dotenv > scores.ini, "{scores:?}");
+        assert!(scores.dotenv > scores.toml, "{scores:?}");
+        assert!(scores.dotenv > scores.yaml, "{scores:?}");
+    }
+
+    #[rstest]
+    #[case("name = \"Example\"\n\nserver {\n  port = 8080\n}\n")]
+    #[case("resource \"aws_instance\" \"web\" {\n  ami = \"abc\"\n}\n")]
+    #[case("# comment\nenabled = true\nlogging {\n  level = info\n}\n")]
+    fn hcl(#[case] s: &str) {
+        let scores = Scores::new(s);
+        assert!(scores.hcl > scores.toml, "{scores:?}");
+        assert!(scores.hcl > scores.ini, "{scores:?}");
+        assert!(scores.hcl > scores.properties, "{scores:?}");
+        assert!(scores.hcl > scores.yaml, "{scores:?}");
+    }
+
+    #[rstest]
+    #[case("name \"Example\"\nport 8080\n")]
+    #[case("// comment\nserver {\n    host localhost\n    enabled #true\n}\n")]
+    #[case("people {\n    - id=1 name=Alice\n    - id=2 name=Bob\n}\n")]
+    #[case("dependencies {\n    serde version=\"1.0\"\n}\n")]
+    fn kdl(#[case] s: &str) {
+        let scores = Scores::new(s);
+        assert!(scores.kdl > scores.hcl, "{scores:?}");
+        assert!(scores.kdl > scores.toml, "{scores:?}");
+        assert!(scores.kdl > scores.yaml, "{scores:?}");
+        assert!(scores.kdl > scores.ron, "{scores:?}");
+    }
+
+    #[rstest]
+    #[case("app.name=Example\napp.port=8080\n")]
+    #[case("# comment\nserver.host = localhost\nserver.enabled =\n")]
+    fn properties(#[case] s: &str) {
+        let scores = Scores::new(s);
+        assert!(scores.properties > scores.ini, "{scores:?}");
+        assert!(scores.properties > scores.toml, "{scores:?}");
+        assert!(scores.properties > scores.yaml, "{scores:?}");
+    }
+
+    #[rstest]
+    #[case("---\nname: Example\n")]
+    #[case("name: Example\nsize: 42\n")]
+    #[case("# comment\nserver:\n  port: 8080\n  hosts:\n    - a\n    - b\n")]
+    #[case("- name: Alice\n- name: Bob\n")]
+    #[case("url: http://example.com/?a=b\n")]
+    fn yaml(#[case] s: &str) {
+        let scores = Scores::new(s);
+        assert!(scores.yaml > scores.toml, "{scores:?}");
+        assert!(scores.yaml > scores.json, "{scores:?}");
+        assert!(scores.yaml > scores.ron, "{scores:?}");
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("   \n\n")]
+    #[case("# just a comment\n")]
+    #[case("Lorem ipsum dolor sit amet")]
+    fn nothing(#[case] s: &str) {
+        assert_eq!(Scores::new(s), Scores::default());
+        assert!(sniff(s).is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+//! "Did you mean" suggestions for unknown field & variant names
+
+/// Given the message of a serde "unknown field" or "unknown variant" error
+/// (as produced by the default implementations of
+/// [`serde::de::Error::unknown_field()`] and
+/// [`serde::de::Error::unknown_variant()`]), return the expected name closest
+/// to the unknown one, if any is close enough
+pub(crate) fn from_message(msg: &str) -> Option<String> {
+    let (_, rest) = msg
+        .split_once("unknown field `")
+        .or_else(|| msg.split_once("unknown variant `"))?;
+    let (found, rest) = rest.split_once('`')?;
+    // The expected names are listed after the unknown name, each enclosed in
+    // backticks.
+    let expected = rest.split('`').skip(1).step_by(2);
+    closest(found, expected).map(String::from)
+}
+
+/// Return the candidate closest to `found` by edit distance, provided that it
+/// is within a third of the length of `found` (but at least one edit) of it
+pub(crate) fn closest<'a, I>(found: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = (found.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(found, c), c))
+        .filter(|&(d, _)| d <= max_distance)
+        .min_by_key(|&(d, _)| d)
+        .map(|(_, c)| c)
+}
+
+/// Compute the edit distance between two strings, counting in characters and
+/// treating insertions, deletions, substitutions, and transpositions of
+/// adjacent characters as single edits (the "optimal string alignment"
+/// distance)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev2 = vec![0; b.len() + 1];
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (prev[j - 1] + cost).min(prev[j] + 1).min(row[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(prev2[j - 2] + 1);
+            }
+        }
+        prev2 = std::mem::replace(&mut prev, row);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("", "", 0)]
+    #[case("abc", "", 3)]
+    #[case("", "abc", 3)]
+    #[case("kitten", "sitting", 3)]
+    #[case("enalbe_foo", "enable_foo", 1)]
+    #[case("nmae", "name", 1)]
+    #[case("ca", "abc", 3)]
+    #[case("Clopn", "Clopen", 1)]
+    #[case("héllo", "hello", 1)]
+    fn test_edit_distance(#[case] a: &str, #[case] b: &str, #[case] distance: usize) {
+        assert_eq!(edit_distance(a, b), distance);
+        assert_eq!(edit_distance(b, a), distance);
+    }
+
+    #[test]
+    fn test_closest() {
+        let candidates = ["Open", "Closed", "Clopen"];
+        assert_eq!(closest("Clopn", candidates), Some("Clopen"));
+        assert_eq!(closest("open", candidates), Some("Open"));
+        assert_eq!(closest("Ajar", candidates), None);
+        assert_eq!(closest("x", ["y", "xyz"]), Some("y"));
+    }
+
+    #[rstest]
+    #[case(
+        "unknown field `enalbe_foo`, expected one of `enable_foo`, `bar_type`, `flavor`",
+        Some("enable_foo")
+    )]
+    #[case(
+        "bar_type: unknown variant `Clopn`, expected one of `Open`, `Closed`, `Clopen` at line 2 column 11",
+        Some("Clopen")
+    )]
+    #[case("unknown field `nmae`, expected `name`", Some("name"))]
+    #[case("unknown variant `a`, expected `b` or `c`", Some("b"))]
+    #[case("unknown field `foo`, there are no fields", None)]
+    #[case("unknown field `foo`, expected `bar`", None)]
+    #[case("missing field `name`", None)]
+    fn test_from_message(#[case] msg: &str, #[case] suggestion: Option<&str>) {
+        assert_eq!(from_message(msg).as_deref(), suggestion);
+    }
+}
@@ -0,0 +1,1054 @@
+//! Tracking the keys in a document that the target type ignored, and
+//! substituting placeholders for values that failed to deserialize
+use crate::DeserializeError;
+use crate::locate::{Segment, display_path};
+use serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, IgnoredAny, IntoDeserializer, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+};
+use serde_path_to_error::{Error as PathError, deserialize as depath};
+use std::collections::HashSet;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// State shared between the parts of a tracking deserializer
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Tracker {
+    /// The paths of the map keys that the target type ignored
+    pub(crate) ignored: Vec<String>,
+    /// The paths of values to replace with placeholders
+    placeholders: HashSet<Vec<Segment>>,
+    /// Keys to add, with placeholder values, to the ends of the maps at the
+    /// given paths
+    missing: Vec<(Vec<Segment>, String)>,
+    /// Keys to skip, along with their values, in the maps at the given paths
+    unknown: Vec<(Vec<Segment>, String)>,
+}
+
+impl Tracker {
+    /// Arrange for later deserializations to get past the error `e`.
+    ///
+    /// Returns `Some(false)` if `e` occurred at a location that was already
+    /// being worked around, in which case trying again will not get any
+    /// further, and `None` if the location of `e` is unknown.
+    fn recover(&mut self, e: &DeserializeError) -> Option<bool> {
+        let mut path = e
+            .path()?
+            .iter()
+            .map(|seg| match seg {
+                serde_path_to_error::Segment::Seq { index } => Some(Segment::Index(*index)),
+                serde_path_to_error::Segment::Map { key }
+                | serde_path_to_error::Segment::Enum { variant: key } => {
+                    Some(Segment::Key(key.clone()))
+                }
+                serde_path_to_error::Segment::Unknown => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let (list, key) = if let Some(field) = e.missing_field() {
+            (&mut self.missing, field)
+        } else if let Some(field) = e.unknown_field() {
+            // The path to an unknown field error may or may not include the
+            // field itself
+            if path
+                .last()
+                .is_some_and(|seg| *seg == Segment::Key(field.clone()))
+            {
+                path.pop();
+            }
+            (&mut self.unknown, field)
+        } else {
+            return Some(self.placeholders.insert(path));
+        };
+        let entry = (path, key);
+        if list.contains(&entry) {
+            Some(false)
+        } else {
+            list.push(entry);
+            Some(true)
+        }
+    }
+
+    /// Return the keys in `list` that belong to the map at `path`
+    fn keys_at(list: &[(Vec<Segment>, String)], path: &Path<'_>) -> Vec<String> {
+        if list.is_empty() {
+            return Vec::new();
+        }
+        let path = path.segments();
+        list.iter()
+            .filter(|(p, _)| *p == path)
+            .map(|(_, key)| key.clone())
+            .collect()
+    }
+}
+
+/// Deserialize a `T` from `de` with [`serde_path_to_error`], appending the
+/// paths of any map keys that `T` ignored to `tracker.ignored` if `tracker`
+/// is given.  Without a tracker, `de` is used as-is.
+pub(crate) fn depath_tracked<'de, D, T>(
+    de: D,
+    tracker: Option<&mut Tracker>,
+) -> Result<T, PathError<D::Error>>
+where
+    D: Deserializer<'de>,
+    T: de::Deserialize<'de>,
+{
+    match tracker {
+        Some(tracker) => depath(Track {
+            de,
+            path: Path::Root,
+            tracker,
+        }),
+        None => depath(de),
+    }
+}
+
+/// Call `load` repeatedly until it succeeds or fails with an error that
+/// cannot be worked around, each time arranging for the next call to skip
+/// past the previous call's error by substituting a placeholder for the value
+/// that failed to deserialize, inserting the missing field, or skipping the
+/// unknown field.
+///
+/// Returns the value if the first call succeeds; otherwise, returns every
+/// error encountered.
+pub(crate) fn collect_errors<T, F>(
+    tracker: &mut Tracker,
+    mut load: F,
+) -> Result<T, Vec<DeserializeError>>
+where
+    F: FnMut(&mut Tracker) -> Result<T, DeserializeError>,
+{
+    let mut errors = Vec::new();
+    loop {
+        tracker.ignored.clear();
+        let e = match load(tracker) {
+            Ok(value) if errors.is_empty() => return Ok(value),
+            Ok(_) => return Err(errors),
+            Err(e) => e,
+        };
+        match tracker.recover(&e) {
+            Some(true) => errors.push(e),
+            // The error recurred where a placeholder was already in use, which
+            // means that the input itself is malformed there
+            Some(false) => return Err(errors),
+            None => {
+                errors.push(e);
+                return Err(errors);
+            }
+        }
+    }
+}
+
+/// The path to the value currently being deserialized, as a linked list
+/// running from the value up to the root
+#[derive(Clone, Copy, Debug)]
+enum Path<'a> {
+    Root,
+    Key { parent: &'a Path<'a>, key: &'a str },
+    Index { parent: &'a Path<'a>, index: usize },
+}
+
+impl Path<'_> {
+    fn segments(&self) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut p = self;
+        loop {
+            match p {
+                Path::Root => break,
+                Path::Key { parent, key } => {
+                    segments.push(Segment::Key((*key).to_owned()));
+                    p = parent;
+                }
+                Path::Index { parent, index } => {
+                    segments.push(Segment::Index(*index));
+                    p = parent;
+                }
+            }
+        }
+        segments.reverse();
+        segments
+    }
+}
+
+/// Generate `Deserializer` methods that pass a wrapped visitor to the inner
+/// deserializer
+macro_rules! forward_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, D::Error> {
+                let (de, visitor) = self.wrap(visitor);
+                de.$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+/// Generate `Deserializer` methods for [`Track`] that, if the current path is
+/// to be replaced with a placeholder, skip the input value and deserialize
+/// from a [`Placeholder`] instead, and otherwise behave like
+/// `forward_deserialize!`
+macro_rules! track_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, D::Error> {
+                if self.is_placeholder() {
+                    self.de.deserialize_ignored_any(IgnoredAny)?;
+                    return Placeholder::new().$method($($arg,)* visitor);
+                }
+                let (de, visitor) = self.wrap(visitor);
+                de.$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+/// Generate `Visitor` methods for scalar values that pass the value to the
+/// inner visitor, optionally first calling `$record` on the visitor's `key`
+/// field and the value
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method<E: de::Error>(self, v: $ty) -> Result<V::Value, E> {
+                self.visitor.$method(v)
+            }
+        )*
+    };
+    ($record:ident; $($method:ident($ty:ty);)*) => {
+        $(
+            fn $method<E: de::Error>(self, v: $ty) -> Result<V::Value, E> {
+                Self::$record(self.key, &v);
+                self.visitor.$method(v)
+            }
+        )*
+    };
+}
+
+/// A deserializer that reports `deserialize_ignored_any()` calls — which is
+/// how serde's derived impls skip the values of unknown keys — as ignored
+/// paths, and that substitutes placeholders for the values at the paths
+/// listed in the tracker
+struct Track<'a, D> {
+    de: D,
+    path: Path<'a>,
+    tracker: &'a mut Tracker,
+}
+
+impl<'a, D> Track<'a, D> {
+    fn wrap<V>(self, visitor: V) -> (D, TrackVisitor<'a, V>) {
+        (
+            self.de,
+            TrackVisitor {
+                visitor,
+                path: self.path,
+                tracker: self.tracker,
+            },
+        )
+    }
+
+    fn is_placeholder(&self) -> bool {
+        !self.tracker.placeholders.is_empty()
+            && self.tracker.placeholders.contains(&self.path.segments())
+    }
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for Track<'_, D> {
+    type Error = D::Error;
+
+    track_deserialize! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        self.tracker
+            .ignored
+            .push(display_path(&self.path.segments()));
+        self.de.deserialize_ignored_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.de.is_human_readable()
+    }
+}
+
+/// A visitor that wraps the nested deserializers & accessors it is given in
+/// their tracking counterparts
+struct TrackVisitor<'a, V> {
+    visitor: V,
+    path: Path<'a>,
+    tracker: &'a mut Tracker,
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for TrackVisitor<'_, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.visitor.expecting(f)
+    }
+
+    forward_visit! {
+        visit_bool(bool);
+        visit_i8(i8);
+        visit_i16(i16);
+        visit_i32(i32);
+        visit_i64(i64);
+        visit_i128(i128);
+        visit_u8(u8);
+        visit_u16(u16);
+        visit_u32(u32);
+        visit_u64(u64);
+        visit_u128(u128);
+        visit_f32(f32);
+        visit_f64(f64);
+        visit_char(char);
+        visit_str(&str);
+        visit_borrowed_str(&'de str);
+        visit_string(String);
+        visit_bytes(&[u8]);
+        visit_borrowed_bytes(&'de [u8]);
+        visit_byte_buf(Vec<u8>);
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.visitor.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.visitor.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, de: D) -> Result<V::Value, D::Error> {
+        self.visitor.visit_some(Track {
+            de,
+            path: self.path,
+            tracker: self.tracker,
+        })
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, de: D) -> Result<V::Value, D::Error> {
+        self.visitor.visit_newtype_struct(Track {
+            de,
+            path: self.path,
+            tracker: self.tracker,
+        })
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+        self.visitor.visit_seq(TrackSeq {
+            seq,
+            path: self.path,
+            tracker: self.tracker,
+            index: 0,
+        })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        self.visitor
+            .visit_map(TrackMap::new(map, self.path, self.tracker))
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+        self.visitor.visit_enum(TrackEnum {
+            data,
+            path: self.path,
+            tracker: self.tracker,
+        })
+    }
+}
+
+/// A seed that deserializes its value with a [`Track`] deserializer
+struct TrackSeed<'a, S> {
+    seed: S,
+    path: Path<'a>,
+    tracker: &'a mut Tracker,
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for TrackSeed<'_, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<S::Value, D::Error> {
+        self.seed.deserialize(Track {
+            de,
+            path: self.path,
+            tracker: self.tracker,
+        })
+    }
+}
+
+struct TrackSeq<'a, A> {
+    seq: A,
+    path: Path<'a>,
+    tracker: &'a mut Tracker,
+    index: usize,
+}
+
+impl<'de, A: SeqAccess<'de>> SeqAccess<'de> for TrackSeq<'_, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, A::Error> {
+        let index = self.index;
+        self.index += 1;
+        self.seq.next_element_seed(TrackSeed {
+            seed,
+            path: Path::Index {
+                parent: &self.path,
+                index,
+            },
+            tracker: self.tracker,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.seq.size_hint()
+    }
+}
+
+struct TrackMap<'a, A> {
+    map: A,
+    path: Path<'a>,
+    tracker: &'a mut Tracker,
+    /// The most recently deserialized key, if it could be captured as a
+    /// string
+    key: Option<String>,
+    /// Keys to skip, along with their values
+    unknown: Vec<String>,
+    /// Keys to add at the end of the map, in reverse order
+    missing: Vec<String>,
+    /// Whether the most recent key was added rather than read from `map`
+    added: bool,
+}
+
+impl<'a, A> TrackMap<'a, A> {
+    fn new(map: A, path: Path<'a>, tracker: &'a mut Tracker) -> Self {
+        let unknown = Tracker::keys_at(&tracker.unknown, &path);
+        let mut missing = Tracker::keys_at(&tracker.missing, &path);
+        missing.reverse();
+        TrackMap {
+            map,
+            path,
+            tracker,
+            key: None,
+            unknown,
+            missing,
+            added: false,
+        }
+    }
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for TrackMap<'_, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, A::Error> {
+        self.key = None;
+        if self.unknown.is_empty() && self.missing.is_empty() {
+            return self.map.next_key_seed(CaptureKey {
+                seed,
+                key: &mut self.key,
+            });
+        }
+        // Keys need to be inspected before they reach `seed`, and `seed` needs
+        // to still be available once `map` is exhausted, so read the keys as
+        // strings first.
+        if !self.added {
+            while let Some(key) = self.map.next_key::<String>()? {
+                if self.unknown.contains(&key) {
+                    self.map.next_value::<IgnoredAny>()?;
+                } else {
+                    let value = seed.deserialize(key.as_str().into_deserializer())?;
+                    self.key = Some(key);
+                    return Ok(Some(value));
+                }
+            }
+        }
+        match self.missing.pop() {
+            Some(key) => {
+                self.added = true;
+                let value = seed.deserialize(key.as_str().into_deserializer())?;
+                self.key = Some(key);
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, A::Error> {
+        if self.added {
+            return seed.deserialize(Placeholder::new());
+        }
+        self.map.next_value_seed(TrackSeed {
+            seed,
+            path: Path::Key {
+                parent: &self.path,
+                key: self.key.as_deref().unwrap_or("?"),
+            },
+            tracker: self.tracker,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.map.size_hint()
+    }
+}
+
+struct TrackEnum<'a, A> {
+    data: A,
+    path: Path<'a>,
+    tracker: &'a mut Tracker,
+}
+
+impl<'a, 'de, A: EnumAccess<'de>> EnumAccess<'de> for TrackEnum<'a, A> {
+    type Error = A::Error;
+    type Variant = TrackVariant<'a, A::Variant>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), A::Error> {
+        let mut name = None;
+        let (value, variant) = self.data.variant_seed(CaptureKey {
+            seed,
+            key: &mut name,
+        })?;
+        Ok((
+            value,
+            TrackVariant {
+                variant,
+                parent: self.path,
+                name,
+                tracker: self.tracker,
+            },
+        ))
+    }
+}
+
+struct TrackVariant<'a, A> {
+    variant: A,
+    parent: Path<'a>,
+    name: Option<String>,
+    tracker: &'a mut Tracker,
+}
+
+impl<'de, A: VariantAccess<'de>> VariantAccess<'de> for TrackVariant<'_, A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.variant.unit_variant()
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, A::Error> {
+        self.variant.newtype_variant_seed(TrackSeed {
+            seed,
+            path: Path::Key {
+                parent: &self.parent,
+                key: self.name.as_deref().unwrap_or("?"),
+            },
+            tracker: self.tracker,
+        })
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, A::Error> {
+        self.variant.tuple_variant(
+            len,
+            TrackVisitor {
+                visitor,
+                path: Path::Key {
+                    parent: &self.parent,
+                    key: self.name.as_deref().unwrap_or("?"),
+                },
+                tracker: self.tracker,
+            },
+        )
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        self.variant.struct_variant(
+            fields,
+            TrackVisitor {
+                visitor,
+                path: Path::Key {
+                    parent: &self.parent,
+                    key: self.name.as_deref().unwrap_or("?"),
+                },
+                tracker: self.tracker,
+            },
+        )
+    }
+}
+
+/// A seed that records the string form of the map key or enum variant name
+/// that it deserializes
+struct CaptureKey<'a, S> {
+    seed: S,
+    key: &'a mut Option<String>,
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for CaptureKey<'_, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<S::Value, D::Error> {
+        self.seed.deserialize(KeyDeserializer { de, key: self.key })
+    }
+}
+
+struct KeyDeserializer<'a, D> {
+    de: D,
+    key: &'a mut Option<String>,
+}
+
+impl<'a, D> KeyDeserializer<'a, D> {
+    fn wrap<V>(self, visitor: V) -> (D, KeyVisitor<'a, V>) {
+        (
+            self.de,
+            KeyVisitor {
+                visitor,
+                key: self.key,
+            },
+        )
+    }
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for KeyDeserializer<'_, D> {
+    type Error = D::Error;
+
+    forward_deserialize! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.de.is_human_readable()
+    }
+}
+
+/// A visitor that records the scalar value it receives as a key
+struct KeyVisitor<'a, V> {
+    visitor: V,
+    key: &'a mut Option<String>,
+}
+
+impl<V> KeyVisitor<'_, V> {
+    fn record<T: fmt::Display>(key: &mut Option<String>, value: &T) {
+        *key = Some(value.to_string());
+    }
+
+    fn record_bytes<T: AsRef<[u8]>>(key: &mut Option<String>, value: &T) {
+        *key = Some(String::from_utf8_lossy(value.as_ref()).into_owned());
+    }
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for KeyVisitor<'_, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.visitor.expecting(f)
+    }
+
+    forward_visit! {
+        record;
+        visit_bool(bool);
+        visit_i8(i8);
+        visit_i16(i16);
+        visit_i32(i32);
+        visit_i64(i64);
+        visit_i128(i128);
+        visit_u8(u8);
+        visit_u16(u16);
+        visit_u32(u32);
+        visit_u64(u64);
+        visit_u128(u128);
+        visit_f32(f32);
+        visit_f64(f64);
+        visit_char(char);
+        visit_str(&str);
+        visit_borrowed_str(&'de str);
+        visit_string(String);
+    }
+
+    forward_visit! {
+        record_bytes;
+        visit_bytes(&[u8]);
+        visit_borrowed_bytes(&'de [u8]);
+        visit_byte_buf(Vec<u8>);
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.visitor.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.visitor.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, de: D) -> Result<V::Value, D::Error> {
+        self.visitor
+            .visit_some(KeyDeserializer { de, key: self.key })
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, de: D) -> Result<V::Value, D::Error> {
+        self.visitor
+            .visit_newtype_struct(KeyDeserializer { de, key: self.key })
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+        self.visitor.visit_seq(seq)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        self.visitor.visit_map(map)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+        self.visitor.visit_enum(data)
+    }
+}
+
+/// A deserializer that produces an arbitrary value of whatever type is
+/// requested of it, used in place of values that failed to deserialize so
+/// that deserialization can continue past them
+struct Placeholder<E>(PhantomData<E>);
+
+impl<E> Placeholder<E> {
+    fn new() -> Self {
+        Placeholder(PhantomData)
+    }
+}
+
+/// Generate `Deserializer` methods for [`Placeholder`] that call the given
+/// `Visitor` method
+macro_rules! placeholder_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*) => $visit:ident($($value:expr)?);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, E> {
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de, E: de::Error> Deserializer<'de> for Placeholder<E> {
+    type Error = E;
+
+    placeholder_deserialize! {
+        deserialize_any() => visit_unit();
+        deserialize_bool() => visit_bool(false);
+        deserialize_i8() => visit_u64(0);
+        deserialize_i16() => visit_u64(0);
+        deserialize_i32() => visit_u64(0);
+        deserialize_i64() => visit_u64(0);
+        deserialize_i128() => visit_u64(0);
+        deserialize_u8() => visit_u64(0);
+        deserialize_u16() => visit_u64(0);
+        deserialize_u32() => visit_u64(0);
+        deserialize_u64() => visit_u64(0);
+        deserialize_u128() => visit_u64(0);
+        deserialize_f32() => visit_f64(0.0);
+        deserialize_f64() => visit_f64(0.0);
+        deserialize_char() => visit_char('\0');
+        deserialize_str() => visit_str("");
+        deserialize_string() => visit_str("");
+        deserialize_bytes() => visit_bytes(&[]);
+        deserialize_byte_buf() => visit_bytes(&[]);
+        deserialize_option() => visit_none();
+        deserialize_unit() => visit_unit();
+        deserialize_unit_struct(_name: &'static str) => visit_unit();
+        deserialize_seq() => visit_seq(PlaceholderSeq::<E>::new(0));
+        deserialize_tuple(len: usize) => visit_seq(PlaceholderSeq::<E>::new(len));
+        deserialize_tuple_struct(_name: &'static str, len: usize) => visit_seq(PlaceholderSeq::<E>::new(len));
+        deserialize_map() => visit_map(PlaceholderMap::<E>::new(&[]));
+        deserialize_struct(_name: &'static str, fields: &'static [&'static str]) => visit_map(PlaceholderMap::<E>::new(fields));
+        deserialize_identifier() => visit_str("");
+        deserialize_ignored_any() => visit_unit();
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        match variants.first() {
+            Some(&variant) => visitor.visit_enum(PlaceholderEnum {
+                variant,
+                _error: PhantomData,
+            }),
+            None => Err(E::custom("enum has no variants")),
+        }
+    }
+}
+
+impl<'de, E: de::Error> VariantAccess<'de> for Placeholder<E> {
+    type Error = E;
+
+    fn unit_variant(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, E> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_seq(PlaceholderSeq::<E>::new(len))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        visitor.visit_map(PlaceholderMap::<E>::new(fields))
+    }
+}
+
+/// A sequence of [`Placeholder`]s of a given length
+struct PlaceholderSeq<E> {
+    len: usize,
+    _error: PhantomData<E>,
+}
+
+impl<E> PlaceholderSeq<E> {
+    fn new(len: usize) -> Self {
+        PlaceholderSeq {
+            len,
+            _error: PhantomData,
+        }
+    }
+}
+
+impl<'de, E: de::Error> SeqAccess<'de> for PlaceholderSeq<E> {
+    type Error = E;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, E> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(Placeholder::new()).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// A map from the given field names to [`Placeholder`]s
+struct PlaceholderMap<E> {
+    fields: std::slice::Iter<'static, &'static str>,
+    _error: PhantomData<E>,
+}
+
+impl<E> PlaceholderMap<E> {
+    fn new(fields: &'static [&'static str]) -> Self {
+        PlaceholderMap {
+            fields: fields.iter(),
+            _error: PhantomData,
+        }
+    }
+}
+
+impl<'de, E: de::Error> MapAccess<'de> for PlaceholderMap<E> {
+    type Error = E;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<Option<S::Value>, E> {
+        self.fields
+            .next()
+            .map(|&field| seed.deserialize(field.into_deserializer()))
+            .transpose()
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, E> {
+        seed.deserialize(Placeholder::new())
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+/// An enum value with the given variant and [`Placeholder`] content
+struct PlaceholderEnum<E> {
+    variant: &'static str,
+    _error: PhantomData<E>,
+}
+
+impl<'de, E: de::Error> EnumAccess<'de> for PlaceholderEnum<E> {
+    type Error = E;
+    type Variant = Placeholder<E>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Placeholder<E>), E> {
+        let value = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((value, Placeholder::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Value;
+    use serde::Deserialize;
+
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    struct Config {
+        shapes: Vec<Shape>,
+        limit: Option<Limit>,
+    }
+
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    enum Shape {
+        Rect { w: u32, h: u32 },
+        Square(Side),
+    }
+
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    struct Side {
+        len: u32,
+    }
+
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    struct Limit(Side);
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn nested_paths() {
+        let value = map([
+            (
+                "shapes",
+                Value::Seq(vec![
+                    map([(
+                        "Rect",
+                        map([
+                            ("w", Value::Integer(2)),
+                            ("h", Value::Integer(3)),
+                            ("d", Value::Integer(4)),
+                        ]),
+                    )]),
+                    map([(
+                        "Square",
+                        map([("len", Value::Integer(1)), ("color", Value::Null)]),
+                    )]),
+                ]),
+            ),
+            (
+                "limit",
+                map([("len", Value::Integer(5)), ("units", Value::Null)]),
+            ),
+            ("extra", Value::Seq(vec![map([("a", Value::Null)])])),
+        ]);
+        let mut tracker = Tracker::default();
+        let cfg = depath_tracked::<_, Config>(value, Some(&mut tracker)).unwrap();
+        assert_eq!(
+            cfg,
+            Config {
+                shapes: vec![Shape::Rect { w: 2, h: 3 }, Shape::Square(Side { len: 1 })],
+                limit: Some(Limit(Side { len: 5 })),
+            }
+        );
+        assert_eq!(
+            tracker.ignored,
+            [
+                "shapes[0].Rect.d",
+                "shapes[1].Square.color",
+                "limit.units",
+                "extra"
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_keep_paths() {
+        let value = map([(
+            "shapes",
+            Value::Seq(vec![map([("Square", map([("len", Value::Bool(true))]))])]),
+        )]);
+        let e = depath_tracked::<_, Config>(value, Some(&mut Tracker::default())).unwrap_err();
+        assert_eq!(e.path().to_string(), "shapes[0].Square.len");
+    }
+}
@@ -0,0 +1,704 @@
+//! Streaming documents from one format's deserializer into another format's
+//! serializer without an intermediate type
+
+// The transcoding machinery is unused when no formats are enabled
+#![cfg_attr(
+    not(any(
+        feature = "json",
+        feature = "json5",
+        feature = "ron",
+        feature = "toml",
+        feature = "yaml",
+        feature = "ini",
+        feature = "properties",
+        feature = "dotenv",
+        feature = "hcl",
+        feature = "kdl"
+    )),
+    allow(dead_code)
+)]
+use crate::locate::{Segment, display_path};
+use crate::value::{Datetime, KeySeed, TOML_DATETIME_FIELD, Value};
+use crate::{DeserializeError, Format, SerializeError};
+use serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, Visitor,
+};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_path_to_error::{Error as PathError, Track};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use thiserror::Error;
+
+/// Error type returned by [`Format::transcode()`]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TranscodeError {
+    /// Returned if deserializing the input failed
+    #[error("failed to deserialize input as {format}")]
+    Deserialize {
+        /// The format that the input was deserialized as
+        format: Format,
+        /// The error that occurred
+        source: Box<DeserializeError>,
+    },
+
+    /// Returned if serializing the output failed
+    #[error("failed to serialize output as {format}")]
+    Serialize {
+        /// The format that the output was serialized as
+        format: Format,
+        /// The error that occurred
+        source: Box<SerializeError>,
+    },
+
+    /// Returned if the input contained a value that the output format cannot
+    /// represent
+    #[error(transparent)]
+    Incompatible(#[from] Incompatibility),
+}
+
+/// A value encountered while transcoding that the output format cannot
+/// represent
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+#[error("{format} cannot represent {kind}{}", if path == "." {
+    String::new()
+} else {
+    format!(" (at {path})")
+})]
+pub struct Incompatibility {
+    /// The format that the output was being serialized as
+    pub format: Format,
+
+    /// The kind of value that could not be represented
+    pub kind: IncompatibilityKind,
+
+    /// The path to the value in the document, formatted the same way as a
+    /// [`serde_path_to_error::Path`] (e.g., `server.ports[0]`), or `.` for the
+    /// top level of the document
+    pub path: String,
+}
+
+/// The kinds of values that some formats cannot represent
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum IncompatibilityKind {
+    /// A top-level value that is not a table/map.  TOML, INI, Java
+    /// properties, dotenv, HCL, and KDL documents must be tables.
+    NonTableRoot,
+
+    /// A null, unit, or `None` value, which TOML has no representation for
+    Null,
+
+    /// A byte string, which YAML, INI, Java properties, and dotenv have no
+    /// representation for
+    Bytes,
+
+    /// An integer outside the range supported by the format.  TOML only
+    /// supports `i64` integers, and JSON, JSON5, and RON only support `i64`
+    /// and `u64` integers.
+    Integer,
+
+    /// A NaN or infinite float, which JSON, JSON5, and HCL have no
+    /// representation for
+    NonFiniteFloat,
+}
+
+impl fmt::Display for IncompatibilityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IncompatibilityKind::NonTableRoot => write!(f, "a top-level value that is not a table"),
+            IncompatibilityKind::Null => write!(f, "null values"),
+            IncompatibilityKind::Bytes => write!(f, "byte strings"),
+            IncompatibilityKind::Integer => write!(f, "integers this large"),
+            IncompatibilityKind::NonFiniteFloat => write!(f, "NaN or infinite floats"),
+        }
+    }
+}
+
+/// Returns whether `format` can represent values of the given kind (other
+/// than integers, which are checked by [`supports_integer()`])
+#[allow(unused_variables)]
+fn supports(format: Format, kind: IncompatibilityKind) -> bool {
+    match (format, kind) {
+        #[cfg(feature = "json")]
+        (Format::Json, IncompatibilityKind::NonFiniteFloat) => false,
+        #[cfg(feature = "json5")]
+        (Format::Json5, IncompatibilityKind::NonFiniteFloat) => false,
+        #[cfg(feature = "toml")]
+        (Format::Toml, IncompatibilityKind::NonTableRoot | IncompatibilityKind::Null) => false,
+        #[cfg(feature = "yaml")]
+        (Format::Yaml, IncompatibilityKind::Bytes) => false,
+        #[cfg(feature = "ini")]
+        (Format::Ini, IncompatibilityKind::NonTableRoot | IncompatibilityKind::Bytes) => false,
+        #[cfg(feature = "properties")]
+        (Format::Properties, IncompatibilityKind::NonTableRoot | IncompatibilityKind::Bytes) => {
+            false
+        }
+        #[cfg(feature = "dotenv")]
+        (Format::Dotenv, IncompatibilityKind::NonTableRoot | IncompatibilityKind::Bytes) => false,
+        #[cfg(feature = "hcl")]
+        (Format::Hcl, IncompatibilityKind::NonTableRoot | IncompatibilityKind::NonFiniteFloat) => {
+            false
+        }
+        #[cfg(feature = "kdl")]
+        (Format::Kdl, IncompatibilityKind::NonTableRoot) => false,
+        _ => true,
+    }
+}
+
+/// Returns whether `format` can represent the given integer
+#[allow(unused_variables)]
+fn supports_integer(format: Format, n: i128) -> bool {
+    match format {
+        #[cfg(feature = "toml")]
+        Format::Toml => i64::try_from(n).is_ok(),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => true,
+        #[cfg(feature = "ini")]
+        Format::Ini => true,
+        #[cfg(feature = "properties")]
+        Format::Properties => true,
+        #[cfg(feature = "dotenv")]
+        Format::Dotenv => true,
+        #[cfg(feature = "kdl")]
+        Format::Kdl => true,
+        #[allow(unreachable_patterns)]
+        _ => i64::try_from(n).is_ok() || u64::try_from(n).is_ok(),
+    }
+}
+
+/// Returns whether `format`'s serializer may serialize a value more than once
+/// (as `toml`'s does in order to decide how to lay out tables, and as the
+/// properties serializer does in order to decide whether to join a sequence
+/// with commas), in which case the whole document must be deserialized before
+/// it can be serialized
+#[allow(unused_variables)]
+fn needs_buffering(format: Format) -> bool {
+    match format {
+        #[cfg(feature = "toml")]
+        Format::Toml => true,
+        #[cfg(feature = "properties")]
+        Format::Properties => true,
+        #[allow(unreachable_patterns)]
+        _ => false,
+    }
+}
+
+/// Error type returned by [`transcode()`], generic over the type of
+/// deserialization errors
+#[derive(Debug)]
+pub(crate) enum Error<E> {
+    Deserialize(E),
+    Serialize(SerializeError),
+    Incompatible(Incompatibility),
+}
+
+impl<E> Error<E> {
+    /// Convert the deserialization error with `f`
+    pub(crate) fn map_deserialize<E2, F: FnOnce(E) -> E2>(self, f: F) -> Error<E2> {
+        match self {
+            Error::Deserialize(e) => Error::Deserialize(f(e)),
+            Error::Serialize(e) => Error::Serialize(e),
+            Error::Incompatible(e) => Error::Incompatible(e),
+        }
+    }
+}
+
+impl Error<DeserializeError> {
+    /// Convert to a [`TranscodeError`] for transcoding `input` from `from` to
+    /// `to`
+    pub(crate) fn into_transcode_error(
+        self,
+        from: Format,
+        to: Format,
+        input: &str,
+    ) -> TranscodeError {
+        match self {
+            Error::Deserialize(e) => {
+                // Deserialization errors that occur below the top level are
+                // only passed back up through the serializer as strings, so
+                // the error we get here may lack the original location.  To
+                // get a proper error, deserialize the input again on its own.
+                let source = from.load_from_str::<Value>(input).err().unwrap_or(e);
+                TranscodeError::Deserialize {
+                    format: from,
+                    source: Box::new(source),
+                }
+            }
+            Error::Serialize(e) => TranscodeError::Serialize {
+                format: to,
+                source: Box::new(e),
+            },
+            Error::Incompatible(e) => TranscodeError::Incompatible(e),
+        }
+    }
+}
+
+/// Deserialize a document from `de` and serialize it to a string in `format`
+/// as it is deserialized
+pub(crate) fn transcode<'de, D: Deserializer<'de>>(
+    de: D,
+    format: Format,
+) -> Result<String, Error<PathError<D::Error>>> {
+    let state = State {
+        format,
+        failure: Cell::new(None),
+        path: RefCell::new(None),
+    };
+    let mut track = Track::new();
+    let (r, de_error) = {
+        let de = serde_path_to_error::Deserializer::new(de, &mut track);
+        if needs_buffering(format) {
+            match Value::deserialize(de) {
+                Ok(value) => {
+                    let checked = Checked {
+                        value: &value,
+                        state: &state,
+                        path: Vec::new(),
+                    };
+                    (format.dump_to_string(&checked), None)
+                }
+                Err(e) => {
+                    state.fail(Failure::Deserialize);
+                    (Ok(String::new()), Some(e))
+                }
+            }
+        } else {
+            let transcoder = Transcoder::new(de, &state, true);
+            let r = format.dump_to_string(&transcoder);
+            (r, transcoder.error.take())
+        }
+    };
+    match (r, state.failure.get(), de_error) {
+        (_, Some(Failure::Deserialize), Some(e)) => {
+            Err(Error::Deserialize(PathError::new(track.path(), e)))
+        }
+        (Ok(s), _, _) => Ok(s),
+        (Err(e), Some(Failure::Incompatible(kind)), _) => {
+            Err(Error::Incompatible(Incompatibility {
+                format,
+                kind,
+                path: state
+                    .path
+                    .take()
+                    .or_else(|| e.path().map(ToString::to_string))
+                    .unwrap_or_else(|| String::from(".")),
+            }))
+        }
+        (Err(e), _, _) => Err(Error::Serialize(e)),
+    }
+}
+
+/// State shared by all of the [`Transcoder`]s for a document
+struct State {
+    /// The format being serialized to
+    format: Format,
+
+    /// The first failure to occur
+    failure: Cell<Option<Failure>>,
+
+    /// The path at which the first failure occurred, if it was an
+    /// incompatibility found by [`Checked`].  (The paths that
+    /// `serde_path_to_error` reports for serialization errors are unreliable
+    /// when the serializer serializes values more than once.)
+    path: RefCell<Option<String>>,
+}
+
+impl State {
+    /// Record the first failure to occur
+    fn fail(&self, failure: Failure) {
+        if self.failure.get().is_none() {
+            self.failure.set(Some(failure));
+        }
+    }
+
+    /// Convert a serialization error to a deserialization error so that it
+    /// can be passed back through the deserializer
+    fn ser_error<E: de::Error, T: fmt::Display>(&self, e: T) -> E {
+        self.fail(Failure::Serialize);
+        E::custom(e)
+    }
+
+    /// Fail if the output format cannot represent values of the given kind
+    fn check(&self, kind: IncompatibilityKind) -> Result<(), String> {
+        if supports(self.format, kind) {
+            Ok(())
+        } else {
+            self.fail(Failure::Incompatible(kind));
+            Err(format!("{} cannot represent {kind}", self.format))
+        }
+    }
+
+    /// Fail if the output format cannot represent the given integer
+    fn check_integer(&self, n: i128) -> Result<(), String> {
+        if supports_integer(self.format, n) {
+            Ok(())
+        } else {
+            self.fail(Failure::Incompatible(IncompatibilityKind::Integer));
+            Err(format!("{} cannot represent integer {n}", self.format))
+        }
+    }
+
+    /// Fail if `root` is true and the output format requires the top level
+    /// of a document to be a table
+    fn check_root(&self, root: bool) -> Result<(), String> {
+        if root {
+            self.check(IncompatibilityKind::NonTableRoot)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The possible reasons for transcoding to fail
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Failure {
+    Deserialize,
+    Serialize,
+    Incompatible(IncompatibilityKind),
+}
+
+/// A [`Serialize`] implementation that serializes whatever `de` deserializes
+struct Transcoder<'a, 'de, D: Deserializer<'de>> {
+    de: Cell<Option<D>>,
+    state: &'a State,
+    /// Whether this is the top level of the document
+    root: bool,
+    /// The error returned by `de`, if any
+    error: Cell<Option<D::Error>>,
+}
+
+impl<'a, 'de, D: Deserializer<'de>> Transcoder<'a, 'de, D> {
+    fn new(de: D, state: &'a State, root: bool) -> Self {
+        Transcoder {
+            de: Cell::new(Some(de)),
+            state,
+            root,
+            error: Cell::new(None),
+        }
+    }
+}
+
+impl<'de, D: Deserializer<'de>> Serialize for Transcoder<'_, 'de, D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(de) = self.de.take() else {
+            return Err(ser::Error::custom("value was already transcoded"));
+        };
+        let visitor = TranscodeVisitor {
+            ser: serializer,
+            state: self.state,
+            root: self.root,
+        };
+        de.deserialize_any(visitor).map_err(|e| {
+            self.state.fail(Failure::Deserialize);
+            let msg = e.to_string();
+            self.error.set(Some(e));
+            ser::Error::custom(msg)
+        })
+    }
+}
+
+/// A [`Visitor`] that passes everything it visits to a [`Serializer`]
+struct TranscodeVisitor<'a, S> {
+    ser: S,
+    state: &'a State,
+    root: bool,
+}
+
+impl<S: Serializer> TranscodeVisitor<'_, S> {
+    fn check_root<E: de::Error>(&self) -> Result<(), E> {
+        self.state.check_root(self.root).map_err(E::custom)
+    }
+
+    fn check<E: de::Error>(&self, kind: IncompatibilityKind) -> Result<(), E> {
+        self.state.check(kind).map_err(E::custom)
+    }
+
+    fn serialize_integer<E: de::Error>(self, n: i128) -> Result<S::Ok, E> {
+        self.check_root()?;
+        self.state.check_integer(n).map_err(E::custom)?;
+        let r = if let Ok(i) = i64::try_from(n) {
+            self.ser.serialize_i64(i)
+        } else if let Ok(u) = u64::try_from(n) {
+            self.ser.serialize_u64(u)
+        } else {
+            self.ser.serialize_i128(n)
+        };
+        r.map_err(|e| self.state.ser_error(e))
+    }
+}
+
+impl<'de, S: Serializer> Visitor<'de> for TranscodeVisitor<'_, S> {
+    type Value = S::Ok;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<S::Ok, E> {
+        self.check_root()?;
+        self.ser
+            .serialize_bool(v)
+            .map_err(|e| self.state.ser_error(e))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<S::Ok, E> {
+        self.serialize_integer(v.into())
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<S::Ok, E> {
+        self.serialize_integer(v)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<S::Ok, E> {
+        self.serialize_integer(v.into())
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<S::Ok, E> {
+        match i128::try_from(v) {
+            Ok(n) => self.serialize_integer(n),
+            Err(_) if supports_integer(self.state.format, i128::MAX) => {
+                self.check_root()?;
+                self.ser
+                    .serialize_u128(v)
+                    .map_err(|e| self.state.ser_error(e))
+            }
+            Err(_) => {
+                self.state
+                    .fail(Failure::Incompatible(IncompatibilityKind::Integer));
+                Err(E::custom(format_args!(
+                    "{} cannot represent integer {v}",
+                    self.state.format
+                )))
+            }
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<S::Ok, E> {
+        self.check_root()?;
+        if !v.is_finite() {
+            self.check(IncompatibilityKind::NonFiniteFloat)?;
+        }
+        self.ser
+            .serialize_f64(v)
+            .map_err(|e| self.state.ser_error(e))
+    }
+
+    fn visit_char<E: de::Error>(self, v: char) -> Result<S::Ok, E> {
+        self.check_root()?;
+        self.ser
+            .serialize_char(v)
+            .map_err(|e| self.state.ser_error(e))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<S::Ok, E> {
+        self.check_root()?;
+        self.ser
+            .serialize_str(v)
+            .map_err(|e| self.state.ser_error(e))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<S::Ok, E> {
+        self.check_root()?;
+        self.check(IncompatibilityKind::Bytes)?;
+        self.ser
+            .serialize_bytes(v)
+            .map_err(|e| self.state.ser_error(e))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<S::Ok, E> {
+        self.check_root()?;
+        self.check(IncompatibilityKind::Null)?;
+        self.ser
+            .serialize_none()
+            .map_err(|e| self.state.ser_error(e))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<S::Ok, D::Error> {
+        let inner = Transcoder::new(deserializer, self.state, self.root);
+        self.ser
+            .serialize_some(&inner)
+            .map_err(|e| self.state.ser_error(e))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<S::Ok, E> {
+        self.check_root()?;
+        self.check(IncompatibilityKind::Null)?;
+        self.ser
+            .serialize_unit()
+            .map_err(|e| self.state.ser_error(e))
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<S::Ok, D::Error> {
+        Transcoder::new(deserializer, self.state, self.root)
+            .serialize(self.ser)
+            .map_err(|e| self.state.ser_error(e))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<S::Ok, A::Error> {
+        self.check_root()?;
+        let mut out = self
+            .ser
+            .serialize_seq(seq.size_hint())
+            .map_err(|e| self.state.ser_error(e))?;
+        while seq
+            .next_element_seed(ElementSeed {
+                ser: &mut out,
+                state: self.state,
+            })?
+            .is_some()
+        {}
+        out.end().map_err(|e| self.state.ser_error(e))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<S::Ok, A::Error> {
+        let Some(first) = map.next_key_seed(KeySeed)? else {
+            let out = self
+                .ser
+                .serialize_map(Some(0))
+                .map_err(|e| self.state.ser_error(e))?;
+            return out.end().map_err(|e| self.state.ser_error(e));
+        };
+        if first == TOML_DATETIME_FIELD {
+            self.check_root()?;
+            return Datetime::new(map.next_value::<String>()?)
+                .serialize(self.ser)
+                .map_err(|e| self.state.ser_error(e));
+        }
+        let mut out = self
+            .ser
+            .serialize_map(map.size_hint())
+            .map_err(|e| self.state.ser_error(e))?;
+        let mut key = Some(first);
+        while let Some(k) = key {
+            out.serialize_key(&k).map_err(|e| self.state.ser_error(e))?;
+            map.next_value_seed(ValueSeed {
+                ser: &mut out,
+                state: self.state,
+            })?;
+            key = map.next_key_seed(KeySeed)?;
+        }
+        out.end().map_err(|e| self.state.ser_error(e))
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<S::Ok, A::Error> {
+        // Transcode enums (such as YAML's tagged values) as single-entry maps,
+        // the same way that `Value` represents them
+        let (variant, access) = data.variant_seed(KeySeed)?;
+        let mut out = self
+            .ser
+            .serialize_map(Some(1))
+            .map_err(|e| self.state.ser_error(e))?;
+        out.serialize_key(&variant)
+            .map_err(|e| self.state.ser_error(e))?;
+        de::VariantAccess::newtype_variant_seed(
+            access,
+            ValueSeed {
+                ser: &mut out,
+                state: self.state,
+            },
+        )?;
+        out.end().map_err(|e| self.state.ser_error(e))
+    }
+}
+
+/// A [`DeserializeSeed`] that transcodes a sequence element
+struct ElementSeed<'a, S> {
+    ser: &'a mut S,
+    state: &'a State,
+}
+
+impl<'de, S: SerializeSeq> DeserializeSeed<'de> for ElementSeed<'_, S> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        self.ser
+            .serialize_element(&Transcoder::new(deserializer, self.state, false))
+            .map_err(|e| self.state.ser_error(e))
+    }
+}
+
+/// A [`DeserializeSeed`] that transcodes a map value
+struct ValueSeed<'a, S> {
+    ser: &'a mut S,
+    state: &'a State,
+}
+
+impl<'de, S: SerializeMap> DeserializeSeed<'de> for ValueSeed<'_, S> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        self.ser
+            .serialize_value(&Transcoder::new(deserializer, self.state, false))
+            .map_err(|e| self.state.ser_error(e))
+    }
+}
+
+/// A [`Serialize`] implementation that serializes a [`Value`] after checking
+/// that the output format can represent it.  Unlike [`Transcoder`], this can
+/// be serialized more than once.
+struct Checked<'a> {
+    value: &'a Value,
+    state: &'a State,
+    /// The path to `value` within the document
+    path: Vec<Segment>,
+}
+
+impl Checked<'_> {
+    /// Fail if the output format cannot represent `value` itself (not
+    /// including its children)
+    fn check(&self) -> Result<(), String> {
+        if !matches!(self.value, Value::Map(_)) {
+            self.state.check_root(self.path.is_empty())?;
+        }
+        match self.value {
+            Value::Null => self.state.check(IncompatibilityKind::Null),
+            Value::Integer(n) => self.state.check_integer(*n),
+            Value::Float(f) if !f.is_finite() => {
+                self.state.check(IncompatibilityKind::NonFiniteFloat)
+            }
+            Value::Bytes(_) => self.state.check(IncompatibilityKind::Bytes),
+            _ => Ok(()),
+        }
+    }
+
+    /// Return a `Checked` for a child of `value`
+    fn child<'b>(&'b self, value: &'b Value, seg: Segment) -> Checked<'b> {
+        let mut path = self.path.clone();
+        path.push(seg);
+        Checked {
+            value,
+            state: self.state,
+            path,
+        }
+    }
+}
+
+impl Serialize for Checked<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Err(msg) = self.check() {
+            self.state
+                .path
+                .borrow_mut()
+                .get_or_insert_with(|| display_path(&self.path));
+            return Err(ser::Error::custom(msg));
+        }
+        match self.value {
+            Value::Seq(values) => serializer.collect_seq(
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| self.child(v, Segment::Index(i))),
+            ),
+            Value::Map(values) => serializer.collect_map(
+                values
+                    .iter()
+                    .map(|(k, v)| (k, self.child(v, Segment::Key(k.clone())))),
+            ),
+            value => value.serialize(serializer),
+        }
+    }
+}
@@ -0,0 +1,884 @@
+//! An untyped, format-independent tree of deserialized data
+use crate::locate::Location;
+use crate::scalar::ScalarError;
+use indexmap::IndexMap;
+use serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::cell::Cell;
+use std::fmt;
+use thiserror::Error;
+
+/// The struct name that `toml` uses when (de)serializing datetimes
+const TOML_DATETIME_NAME: &str = "$__toml_private_Datetime";
+
+/// The field name that `toml` uses when (de)serializing datetimes
+pub(crate) const TOML_DATETIME_FIELD: &str = "$__toml_private_datetime";
+
+thread_local! {
+    /// Whether [`Datetime`]s should currently be serialized in the form that
+    /// `toml` recognizes rather than as strings
+    static TOML_DATETIMES: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Call `f` with [`Datetime`]s serialized as TOML datetimes rather than as
+/// strings
+#[cfg(feature = "toml")]
+pub(crate) fn with_toml_datetimes<R, F: FnOnce() -> R>(f: F) -> R {
+    /// Restores the previous setting on drop, even if `f` panics
+    struct Reset(bool);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            TOML_DATETIMES.set(self.0);
+        }
+    }
+
+    let _reset = Reset(TOML_DATETIMES.replace(true));
+    f()
+}
+
+/// A map of string keys to [`Value`]s that preserves insertion order
+pub type Map = IndexMap<String, Value>;
+
+/// A format-independent, dynamically-typed value.
+///
+/// Every [`Format`][crate::Format] can load into and dump from a `Value`,
+/// making it possible to inspect or manipulate configuration without defining
+/// a type for it.  Map keys are always strings; when a format allows other
+/// scalars (such as numbers or booleans) as keys, they are converted to
+/// strings on deserialization.  Unit values and `None` are both deserialized
+/// as [`Value::Null`], and characters are deserialized as strings.
+///
+/// A `Value` can itself be deserialized into any type implementing
+/// [`serde::Deserialize`], e.g., with `T::deserialize(value)`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value {
+    /// A null or unit value
+    #[default]
+    Null,
+    /// A boolean
+    Bool(bool),
+    /// An integer
+    Integer(i128),
+    /// A floating-point number
+    Float(f64),
+    /// A string
+    String(String),
+    /// A byte string
+    Bytes(Vec<u8>),
+    /// A sequence of values
+    Seq(Vec<Value>),
+    /// A map from string keys to values, in the order in which they appeared
+    Map(Map),
+    /// A date and/or time (currently only produced by TOML)
+    Datetime(Datetime),
+}
+
+impl Value {
+    /// Returns `true` if the value is [`Value::Null`]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// If the value is a boolean, return it
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// If the value is an integer, return it
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// If the value is a floating-point number, return it
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// If the value is a string, return it
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// If the value is a byte string, return it
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bs) => Some(bs),
+            _ => None,
+        }
+    }
+
+    /// If the value is a sequence, return its elements
+    pub fn as_seq(&self) -> Option<&[Value]> {
+        match self {
+            Value::Seq(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// If the value is a map, return it
+    pub fn as_map(&self) -> Option<&Map> {
+        match self {
+            Value::Map(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// If the value is a datetime, return it
+    pub fn as_datetime(&self) -> Option<&Datetime> {
+        match self {
+            Value::Datetime(dt) => Some(dt),
+            _ => None,
+        }
+    }
+
+    /// If the value is a map containing `key`, return the value for that key
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_map()?.get(key)
+    }
+
+    /// Return a description of the value's type for use in error messages
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match self {
+            Value::Null => de::Unexpected::Unit,
+            Value::Bool(b) => de::Unexpected::Bool(*b),
+            Value::Integer(i) => match i64::try_from(*i) {
+                Ok(i) => de::Unexpected::Signed(i),
+                Err(_) => de::Unexpected::Other("integer"),
+            },
+            Value::Float(f) => de::Unexpected::Float(*f),
+            Value::String(s) => de::Unexpected::Str(s),
+            Value::Bytes(bs) => de::Unexpected::Bytes(bs),
+            Value::Seq(_) => de::Unexpected::Seq,
+            Value::Map(_) => de::Unexpected::Map,
+            Value::Datetime(_) => de::Unexpected::Other("datetime"),
+        }
+    }
+}
+
+/// Generate `From` impls for [`Value`] that wrap the source value in the given
+/// variant
+macro_rules! impl_from {
+    ($($t:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$t> for Value {
+                fn from(value: $t) -> Value {
+                    Value::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from! {
+    bool => Bool,
+    i32 => Integer,
+    i64 => Integer,
+    i128 => Integer,
+    u32 => Integer,
+    u64 => Integer,
+    f64 => Float,
+    String => String,
+    &str => String,
+    Vec<u8> => Bytes,
+    Vec<Value> => Seq,
+    Map => Map,
+    Datetime => Datetime,
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Integer(i) => {
+                if let Ok(i) = i64::try_from(*i) {
+                    serializer.serialize_i64(i)
+                } else if let Ok(u) = u64::try_from(*i) {
+                    serializer.serialize_u64(u)
+                } else {
+                    serializer.serialize_i128(*i)
+                }
+            }
+            Value::Float(f) => serializer.serialize_f64(*f),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Bytes(bs) => serializer.serialize_bytes(bs),
+            Value::Seq(values) => serializer.collect_seq(values),
+            Value::Map(values) => serializer.collect_map(values),
+            Value::Datetime(dt) => dt.serialize(serializer),
+        }
+    }
+}
+
+/// A date, time, or date & time, as found in TOML documents.
+///
+/// The datetime is stored in the string form in which it was written (e.g.,
+/// `1979-05-27T07:32:00Z`, `1979-05-27`, or `07:32:00`); it is not validated
+/// or normalized.  When dumped as TOML, a `Datetime` is written as a TOML
+/// datetime; in all other formats, it is written as a string.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Datetime(String);
+
+impl Datetime {
+    /// Create a `Datetime` from its string form
+    pub fn new<S: Into<String>>(s: S) -> Datetime {
+        Datetime(s.into())
+    }
+
+    /// Return the datetime's string form
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Datetime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Datetime> for String {
+    fn from(value: Datetime) -> String {
+        value.0
+    }
+}
+
+impl Serialize for Datetime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if TOML_DATETIMES.get() {
+            let mut s = serializer.serialize_struct(TOML_DATETIME_NAME, 1)?;
+            s.serialize_field(TOML_DATETIME_FIELD, &self.0)?;
+            s.end()
+        } else {
+            serializer.serialize_str(&self.0)
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Datetime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Datetime, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::String(s) => Ok(Datetime(s)),
+            Value::Datetime(dt) => Ok(dt),
+            v => Err(de::Error::invalid_type(v.unexpected(), &"a datetime")),
+        }
+    }
+}
+
+/// Error type for deserializing from an untyped value tree
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+#[error("{0}")]
+pub struct ValueError(String);
+
+impl de::Error for ValueError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ValueError(msg.to_string())
+    }
+}
+
+impl ScalarError for ValueError {
+    /// Values in a tree have no positions, so this does nothing
+    fn at(self, _location: Location) -> ValueError {
+        self
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<Value, E> {
+        Ok(Value::Integer(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Value, E> {
+        i128::try_from(v)
+            .map(Value::Integer)
+            .map_err(|_| E::custom("integer out of range"))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Bytes(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(v) = seq.next_element()? {
+            values.push(v);
+        }
+        Ok(Value::Seq(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut values = Map::with_capacity(map.size_hint().unwrap_or_default());
+        while let Some(key) = map.next_key_seed(KeySeed)? {
+            if values.is_empty() && key == TOML_DATETIME_FIELD {
+                return Ok(Value::Datetime(Datetime(map.next_value()?)));
+            }
+            let value = map.next_value()?;
+            values.insert(key, value);
+        }
+        Ok(Value::Map(values))
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Value, A::Error> {
+        // Represent enums (such as YAML's tagged values) the same way as the
+        // externally tagged enums of most formats
+        let (variant, access) = data.variant_seed(KeySeed)?;
+        let value = access.newtype_variant()?;
+        Ok(Value::Map(Map::from([(variant, value)])))
+    }
+}
+
+/// Deserializes a map key as a string, stringifying scalar keys
+pub(crate) struct KeySeed;
+
+impl<'de> DeserializeSeed<'de> for KeySeed {
+    type Value = String;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<String, D::Error> {
+        deserializer.deserialize_any(KeyVisitor)
+    }
+}
+
+struct KeyVisitor;
+
+impl Visitor<'_> for KeyVisitor {
+    type Value = String;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a string, number, or boolean map key")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<String, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<String, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<String, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<String, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<String, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<String, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_char<E>(self, v: char) -> Result<String, E> {
+        Ok(v.to_string())
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<String, E> {
+        Ok(v.to_owned())
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<String, E> {
+        Ok(v)
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = ValueError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Integer(i) => {
+                if let Ok(i) = i64::try_from(i) {
+                    visitor.visit_i64(i)
+                } else if let Ok(u) = u64::try_from(i) {
+                    visitor.visit_u64(u)
+                } else {
+                    visitor.visit_i128(i)
+                }
+            }
+            Value::Float(f) => visitor.visit_f64(f),
+            Value::String(s) => visitor.visit_string(s),
+            Value::Bytes(bs) => visitor.visit_byte_buf(bs),
+            Value::Seq(values) => visit_seq(values, visitor),
+            Value::Map(values) => visit_map(values, visitor),
+            Value::Datetime(dt) => visitor.visit_string(dt.0),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        match self {
+            // Let `toml::value::Datetime` deserialize from a `Value::Datetime`
+            Value::Datetime(dt) if name == TOML_DATETIME_NAME => visit_map(
+                Map::from([(String::from(TOML_DATETIME_FIELD), Value::String(dt.0))]),
+                visitor,
+            ),
+            v => v.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            v => visitor.visit_some(v),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Seq(values) if values.is_empty() => visitor.visit_unit(),
+            v => Err(de::Error::invalid_type(v.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        match self {
+            Value::String(s) => visitor.visit_string(s),
+            Value::Bytes(bs) => visitor.visit_byte_buf(bs),
+            Value::Seq(values) => visit_seq(values, visitor),
+            v => Err(de::Error::invalid_type(v.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        match self {
+            Value::String(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: None,
+            }),
+            Value::Map(values) if values.len() == 1 => {
+                let Some((variant, value)) = values.into_iter().next() else {
+                    unreachable!("map of length 1 should have an entry");
+                };
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            v => Err(de::Error::invalid_type(
+                v.unexpected(),
+                &"string or map with a single key",
+            )),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        seq tuple tuple_struct map identifier
+    }
+}
+
+impl IntoDeserializer<'_, ValueError> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+fn visit_seq<'de, V: Visitor<'de>>(values: Vec<Value>, visitor: V) -> Result<V::Value, ValueError> {
+    let mut deserializer = de::value::SeqDeserializer::new(values.into_iter());
+    let seq = visitor.visit_seq(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(seq)
+}
+
+fn visit_map<'de, V: Visitor<'de>>(values: Map, visitor: V) -> Result<V::Value, ValueError> {
+    let mut deserializer =
+        de::value::MapDeserializer::new(values.into_iter().map(|(k, v)| (KeyDeserializer(k), v)));
+    let map = visitor.visit_map(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(map)
+}
+
+/// Deserializer for map keys that parses the key string as a number or
+/// boolean when one is requested
+pub(crate) struct KeyDeserializer(pub(crate) String);
+
+impl IntoDeserializer<'_, ValueError> for KeyDeserializer {
+    type Deserializer = KeyDeserializer;
+
+    fn into_deserializer(self) -> KeyDeserializer {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+                match self.0.parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => Err(de::Error::invalid_type(
+                        de::Unexpected::Str(&self.0),
+                        &visitor,
+                    )),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for KeyDeserializer {
+    type Error = ValueError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        Value::String(self.0).deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf option unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: Option<Value>,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = ValueError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantDeserializer), ValueError> {
+        let variant = seed.deserialize(Value::String(self.variant))?;
+        Ok((variant, VariantDeserializer(self.value)))
+    }
+}
+
+struct VariantDeserializer(Option<Value>);
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = ValueError;
+
+    fn unit_variant(self) -> Result<(), ValueError> {
+        match self.0 {
+            None | Some(Value::Null) => Ok(()),
+            Some(v) => Err(de::Error::invalid_type(v.unexpected(), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, ValueError> {
+        match self.0 {
+            Some(v) => seed.deserialize(v),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        match self.0 {
+            Some(Value::Seq(values)) => visit_seq(values, visitor),
+            Some(v) => Err(de::Error::invalid_type(v.unexpected(), &"tuple variant")),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        match self.0 {
+            Some(Value::Map(values)) => visit_map(values, visitor),
+            Some(v) => Err(de::Error::invalid_type(v.unexpected(), &"struct variant")),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    struct Config {
+        name: String,
+        size: Option<u32>,
+        ratio: f64,
+        mode: Mode,
+        shapes: Vec<Shape>,
+        ports: BTreeMap<u16, String>,
+    }
+
+    #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Mode {
+        Fast,
+        Slow,
+    }
+
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    enum Shape {
+        Circle(f64),
+        Rect { w: u32, h: u32 },
+        Point,
+    }
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn deserialize_from_value() {
+        let value = map([
+            ("name", Value::String(String::from("Example"))),
+            ("size", Value::Null),
+            ("ratio", Value::Integer(2)),
+            ("mode", Value::String(String::from("slow"))),
+            (
+                "shapes",
+                Value::Seq(vec![
+                    map([("Circle", Value::Float(1.5))]),
+                    map([(
+                        "Rect",
+                        map([("w", Value::Integer(2)), ("h", Value::Integer(3))]),
+                    )]),
+                    Value::String(String::from("Point")),
+                ]),
+            ),
+            ("ports", map([("80", Value::String(String::from("http")))])),
+        ]);
+        assert_eq!(
+            Config::deserialize(value).unwrap(),
+            Config {
+                name: String::from("Example"),
+                size: None,
+                ratio: 2.0,
+                mode: Mode::Slow,
+                shapes: vec![Shape::Circle(1.5), Shape::Rect { w: 2, h: 3 }, Shape::Point],
+                ports: BTreeMap::from([(80, String::from("http"))]),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_type() {
+        let r = u32::deserialize(Value::String(String::from("42")));
+        assert_eq!(
+            r.unwrap_err().to_string(),
+            "invalid type: string \"42\", expected u32"
+        );
+    }
+
+    #[test]
+    fn invalid_key() {
+        let r = BTreeMap::<u16, bool>::deserialize(map([("http", Value::Bool(true))]));
+        assert_eq!(
+            r.unwrap_err().to_string(),
+            "invalid type: string \"http\", expected u16"
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn deserialize_value() {
+        let value = serde_json::from_str::<Value>(
+            r#"{"a": [1, -2, 3.5, "x", null, true], "b": {"1": {}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            map([
+                (
+                    "a",
+                    Value::Seq(vec![
+                        Value::Integer(1),
+                        Value::Integer(-2),
+                        Value::Float(3.5),
+                        Value::String(String::from("x")),
+                        Value::Null,
+                        Value::Bool(true),
+                    ])
+                ),
+                ("b", map([("1", map([]))])),
+            ])
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn deserialize_value_scalar_keys() {
+        let value = serde_yaml::from_str::<Value>("1: a\ntrue: b\n").unwrap();
+        assert_eq!(
+            value,
+            map([
+                ("1", Value::String(String::from("a"))),
+                ("true", Value::String(String::from("b"))),
+            ])
+        );
+    }
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "toml")]
+use cfgfifo::*;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn atomic_dump_new_file() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    let cfg = Cfgfifo::new().atomic(true);
+    cfg.dump(&path, &BTreeMap::from([("key", "value")]))
+        .unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "key = \"value\"\n");
+    assert_eq!(fs::read_dir(tmpdir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn atomic_dump_failure_keeps_original() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    fs::write(&path, "key = \"original\"\n").unwrap();
+    let cfg = Cfgfifo::new().atomic(true);
+    let r = cfg.dump(&path, &vec![1, 2, 3]);
+    assert!(
+        matches!(r, Err(DumpError::Serialize { .. })),
+        "dump() did not fail with Serialize error: {r:?}"
+    );
+    assert_eq!(fs::read_to_string(&path).unwrap(), "key = \"original\"\n");
+    assert_eq!(fs::read_dir(tmpdir.path()).unwrap().count(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn atomic_dump_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    fs::write(&path, "key = \"original\"\n").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    let cfg = Cfgfifo::new().atomic(true);
+    cfg.dump(&path, &BTreeMap::from([("key", "new")])).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "key = \"new\"\n");
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+}
+
+#[cfg(unix)]
+#[test]
+fn atomic_dump_through_symlink() {
+    let tmpdir = tempdir().unwrap();
+    let target = tmpdir.path().join("real.toml");
+    let link = tmpdir.path().join("config.toml");
+    fs::write(&target, "key = \"original\"\n").unwrap();
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+    let cfg = Cfgfifo::new().atomic(true);
+    cfg.dump(&link, &BTreeMap::from([("key", "new")])).unwrap();
+    assert!(
+        fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+    assert_eq!(fs::read_to_string(&target).unwrap(), "key = \"new\"\n");
+}
+
+#[cfg(unix)]
+#[rstest]
+fn dump_through_dangling_symlink(#[values(false, true)] atomic: bool) {
+    let tmpdir = tempdir().unwrap();
+    fs::create_dir(tmpdir.path().join("real")).unwrap();
+    let link = tmpdir.path().join("config.toml");
+    std::os::unix::fs::symlink("real/config.toml", &link).unwrap();
+    let cfg = Cfgfifo::new().atomic(atomic);
+    cfg.dump(&link, &BTreeMap::from([("key", "new")])).unwrap();
+    assert!(
+        fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+    assert_eq!(
+        fs::read_to_string(tmpdir.path().join("real").join("config.toml")).unwrap(),
+        "key = \"new\"\n"
+    );
+}
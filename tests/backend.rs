@@ -0,0 +1,109 @@
+#![cfg(feature = "json")]
+use cfgfifo::*;
+use pretty_assertions::assert_eq;
+use serde::{Deserialize, Serialize};
+use std::io::{Seek, Write, read_to_string};
+use tempfile::Builder;
+
+/// JSON, but single-line and with a custom file extension
+#[derive(Debug)]
+struct Jsonl;
+
+impl Backend for Jsonl {
+    fn name(&self) -> &'static str {
+        "JSONL"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["jsonl"]
+    }
+
+    fn load_from_str(&self, s: &str, sink: LoadSink<'_>) -> Result<(), BackendError> {
+        let mut de = serde_json::Deserializer::from_str(s);
+        sink.deserialize(&mut de)?;
+        de.end()?;
+        Ok(())
+    }
+
+    fn dump_to_string(&self, value: DumpSource<'_>) -> Result<String, BackendError> {
+        Ok(serde_json::to_string(&value)?)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[test]
+fn identify() {
+    let cfg = Cfgfifo::new().backend(Jsonl);
+    let fmt = cfg.identify("file.JSONL").unwrap();
+    assert_eq!(fmt.name(), "JSONL");
+    assert_eq!(fmt.to_string(), "JSONL");
+    assert_eq!(fmt.builtin(), None);
+    assert_eq!(cfg.identify("file.json").unwrap(), Format::Json);
+    assert!(Cfgfifo::new().identify("file.jsonl").is_err());
+}
+
+#[test]
+fn custom_is_not_builtin() {
+    let cfg = Cfgfifo::new().formats([AnyFormat::custom(Jsonl)]);
+    assert!(cfg.identify("file.json").is_err());
+    assert!(cfg.identify("file.jsonl").is_ok());
+}
+
+#[test]
+fn custom_equality() {
+    let cfg = Cfgfifo::new().backend(Jsonl);
+    assert_eq!(cfg.clone(), cfg);
+    assert_ne!(Cfgfifo::new().backend(Jsonl), cfg);
+    let fmt = AnyFormat::custom(Jsonl);
+    assert_eq!(fmt.clone(), fmt);
+    assert_ne!(AnyFormat::custom(Jsonl), fmt);
+}
+
+#[test]
+fn load_from_file() {
+    let mut file = Builder::new().suffix(".jsonl").tempfile().unwrap();
+    writeln!(file, r#"{{"x": 1, "y": -2}}"#).unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new().backend(Jsonl);
+    let r = cfg.load::<Point, _>(&file);
+    assert_eq!(r.unwrap(), Point { x: 1, y: -2 });
+}
+
+#[test]
+fn dump_to_file() {
+    let mut file = Builder::new().suffix(".jsonl").tempfile().unwrap();
+    let cfg = Cfgfifo::new().backend(Jsonl);
+    cfg.dump(&file, &Point { x: 1, y: -2 }).unwrap();
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, "{\"x\":1,\"y\":-2}\n");
+}
+
+#[test]
+fn deserialize_error() {
+    let fmt = AnyFormat::custom(Jsonl);
+    let r = fmt.load_from_str::<Point>(r#"{"x": 1, "y": "two"}"#);
+    let Err(DeserializeError::Custom(e)) = r else {
+        panic!("load_from_str() did not fail with Custom error: {r:?}");
+    };
+    assert_eq!(e.path().to_string(), "y");
+    assert_eq!(
+        e.to_string(),
+        "y: invalid type: string \"two\", expected i32 at line 1 column 19"
+    );
+}
+
+#[test]
+fn trailing_error() {
+    let fmt = AnyFormat::custom(Jsonl);
+    let r = fmt.load_from_str::<Point>(r#"{"x": 1, "y": 2} 3"#);
+    let Err(DeserializeError::Custom(e)) = r else {
+        panic!("load_from_str() did not fail with Custom error: {r:?}");
+    };
+    assert_eq!(e.to_string(), "trailing characters at line 1 column 18");
+}
@@ -0,0 +1,92 @@
+#![cfg(all(
+    feature = "json",
+    any(feature = "gzip", feature = "xz", feature = "zstd")
+))]
+use cfgfifo::*;
+use pretty_assertions::assert_eq;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tempfile::tempdir;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[test]
+fn identify() {
+    for c in Compression::iter() {
+        for ext in c.extensions() {
+            let path = format!("file.json.{ext}");
+            assert_eq!(Compression::identify(&path), Some(c));
+            assert_eq!(Cfgfifo::new().identify(&path).unwrap(), Format::Json);
+        }
+    }
+    assert_eq!(Compression::identify("file.json"), None);
+}
+
+#[test]
+fn identify_custom_filename() {
+    for c in Compression::iter() {
+        let path = format!(".myapprc.{}", c.extensions()[0]);
+        let cfg = Cfgfifo::new().filename(".myapprc", Format::Json);
+        assert_eq!(cfg.identify(&path).unwrap(), Format::Json);
+    }
+}
+
+#[test]
+fn identify_bare_compression() {
+    for c in Compression::iter() {
+        let path = format!("file.{}", c.extensions()[0]);
+        assert!(Cfgfifo::new().identify(&path).is_err());
+    }
+}
+
+#[test]
+fn roundtrip() {
+    let tmpdir = tempdir().unwrap();
+    for c in Compression::iter() {
+        let path = tmpdir
+            .path()
+            .join(format!("point.json.{}", c.extensions()[0]));
+        let point = Point { x: 1, y: -2 };
+        dump(&path, &point).unwrap();
+        let raw = fs::read(&path).unwrap();
+        assert!(
+            !raw.starts_with(b"{"),
+            "{c} file was not compressed: {raw:?}"
+        );
+        assert_eq!(load::<Point, _>(&path).unwrap(), point);
+    }
+}
+
+#[test]
+fn sniff_compressed() {
+    let tmpdir = tempdir().unwrap();
+    for c in Compression::iter() {
+        let ext = c.extensions()[0];
+        let src = tmpdir.path().join(format!("point.json.{ext}"));
+        dump(&src, &Point { x: 3, y: 4 }).unwrap();
+        let path = tmpdir.path().join(format!("point.{ext}"));
+        fs::rename(src, &path).unwrap();
+        let cfg = Cfgfifo::new().sniff(true);
+        assert_eq!(cfg.load::<Point, _>(&path).unwrap(), Point { x: 3, y: 4 });
+    }
+}
+
+#[test]
+fn load_corrupt() {
+    let tmpdir = tempdir().unwrap();
+    for c in Compression::iter() {
+        let path = tmpdir
+            .path()
+            .join(format!("point.json.{}", c.extensions()[0]));
+        fs::write(&path, "{\"x\": 1, \"y\": 2}").unwrap();
+        let r = load::<Point, _>(&path);
+        assert!(
+            matches!(r, Err(LoadError::Deserialize { .. })),
+            "{c}: loading uncompressed data did not fail: {r:?}"
+        );
+    }
+}
@@ -2,6 +2,7 @@ mod formats;
 use cfgfifo::*;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::io::Write;
 use tempfile::Builder;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -129,19 +130,96 @@ impl Person {
 #[test]
 fn load_unknown() {
     let file = Builder::new().suffix(".unk").tempfile().unwrap();
-    let r = load::<Config, _>(file);
-    let Err(LoadError::Identify(e)) = r else {
+    let r = load::<Config, _>(&file);
+    let Err(LoadError::Identify { path, source }) = r else {
         panic!("load() did not fail with Identify error: {r:?}");
     };
-    assert_eq!(e, IdentifyError::Unknown(String::from("unk")));
+    assert_eq!(path, file.path());
+    assert_eq!(source, IdentifyError::Unknown(String::from("unk")));
 }
 
 #[test]
 fn dump_unknown() {
     let file = Builder::new().suffix(".unk").tempfile().unwrap();
     let r = dump(&file, &Config::get());
-    let Err(DumpError::Identify(e)) = r else {
+    let Err(e @ DumpError::Identify { .. }) = r else {
         panic!("dump() did not fail with Identify error: {r:?}");
     };
-    assert_eq!(e, IdentifyError::Unknown(String::from("unk")));
+    assert_eq!(
+        e.to_string(),
+        format!(
+            "failed to identify file format of {}",
+            file.path().display()
+        )
+    );
+    let DumpError::Identify { path, source } = e else {
+        unreachable!();
+    };
+    assert_eq!(path, file.path());
+    assert_eq!(source, IdentifyError::Unknown(String::from("unk")));
+}
+
+#[test]
+fn sniff_unknown() {
+    let mut file = Builder::new().suffix(".unk").tempfile().unwrap();
+    writeln!(file, "Lorem ipsum dolor sit amet").unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new().sniff(true);
+    let r = cfg.load::<Config, _>(&file);
+    let Err(LoadError::Identify { path, source }) = r else {
+        panic!("load() did not fail with Identify error: {r:?}");
+    };
+    assert_eq!(path, file.path());
+    assert_eq!(source, IdentifyError::Unknown(String::from("unk")));
+}
+
+#[cfg(all(feature = "json", feature = "toml"))]
+#[test]
+fn try_each_load() {
+    let mut file = Builder::new().tempfile().unwrap();
+    writeln!(file, "[primitives]\nsome = 1").unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new()
+        .formats([Format::Json, Format::Toml])
+        .try_each(true);
+    let r = cfg.load::<BTreeMap<String, BTreeMap<String, u32>>, _>(&file);
+    assert_eq!(
+        r.unwrap(),
+        BTreeMap::from([(
+            String::from("primitives"),
+            BTreeMap::from([(String::from("some"), 1)])
+        )])
+    );
+}
+
+#[cfg(all(feature = "json", feature = "toml"))]
+#[test]
+fn try_each_all_failed() {
+    let mut file = Builder::new().suffix(".unk").tempfile().unwrap();
+    writeln!(file, "Lorem ipsum dolor sit amet").unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new()
+        .formats([Format::Toml, Format::Json])
+        .try_each(true);
+    let r = cfg.load::<Config, _>(&file);
+    let Err(LoadError::AllFailed { path, failures }) = r else {
+        panic!("load() did not fail with AllFailed error: {r:?}");
+    };
+    assert_eq!(path, file.path());
+    let formats = failures.iter().map(|(f, _)| f.clone()).collect::<Vec<_>>();
+    assert_eq!(formats, [Format::Toml, Format::Json]);
+    assert!(matches!(failures[0].1, DeserializeError::TomlParse { .. }));
+    assert!(matches!(failures[1].1, DeserializeError::Json(_)));
+}
+
+#[test]
+fn try_each_no_formats() {
+    let file = Builder::new().suffix(".unk").tempfile().unwrap();
+    let cfg = Cfgfifo::new().formats(Vec::<Format>::new()).try_each(true);
+    let r = cfg.load::<Config, _>(&file);
+    let Err(LoadError::Identify { path, source }) = r else {
+        panic!("load() did not fail with Identify error: {r:?}");
+    };
+    assert_eq!(path, file.path());
+    assert_eq!(source, IdentifyError::Unknown(String::from("unk")));
 }
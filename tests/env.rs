@@ -0,0 +1,175 @@
+#![cfg(all(feature = "toml", feature = "yaml"))]
+use cfgfifo::*;
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use std::fs;
+use tempfile::tempdir;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct Config {
+    name: String,
+    #[serde(default)]
+    debug: bool,
+    server: Server,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+const CONFIG: &str = "name = \"app\"\n\n[server]\nhost = \"localhost\"\nport = 8080\n";
+
+#[test]
+fn load_with_env() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    fs::write(&path, CONFIG).unwrap();
+    let env = EnvSource::new("APP_").list_separator(",").vars([
+        ("APP_SERVER__PORT", "9000"),
+        ("APP_DEBUG", "true"),
+        ("APP_TAGS", "a,b"),
+        ("OTHER_NAME", "ignored"),
+    ]);
+    let cfg = Cfgfifo::new().env(env).load::<Config, _>(&path).unwrap();
+    assert_eq!(
+        cfg,
+        Config {
+            name: String::from("app"),
+            debug: true,
+            server: Server {
+                host: String::from("localhost"),
+                port: 9000,
+            },
+            tags: vec![String::from("a"), String::from("b")],
+        }
+    );
+}
+
+#[test]
+fn load_empty_file_with_env() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.yaml");
+    fs::write(&path, "").unwrap();
+    let env = EnvSource::new("APP_").vars([
+        ("APP_NAME", "app"),
+        ("APP_SERVER__HOST", "example.com"),
+        ("APP_SERVER__PORT", "80"),
+    ]);
+    let cfg = Cfgfifo::new().env(env).load::<Config, _>(&path).unwrap();
+    assert_eq!(cfg.server.host, "example.com");
+}
+
+#[test]
+fn env_error_names_var() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    fs::write(&path, CONFIG).unwrap();
+    let env = EnvSource::new("APP_").vars([("APP_SERVER__PORT", "high")]);
+    let r = Cfgfifo::new().env(env).load::<Config, _>(&path);
+    let Err(e @ LoadError::Overlay { .. }) = r else {
+        panic!("load() did not fail with Overlay error: {r:?}");
+    };
+    assert_eq!(
+        e.to_string(),
+        "failed to deserialize value of environment variable APP_SERVER__PORT"
+    );
+    let LoadError::Overlay { var, source } = e else {
+        unreachable!();
+    };
+    assert_eq!(var.as_deref(), Some("APP_SERVER__PORT"));
+    assert_eq!(
+        source.to_string(),
+        "server.port: invalid value: string \"high\", expected u16"
+    );
+}
+
+#[test]
+fn env_strings_converted_as_requested() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    fs::write(&path, CONFIG).unwrap();
+    for parse in [true, false] {
+        let env = EnvSource::new("APP_").parse_values(parse).vars([
+            ("APP_NAME", "123"),
+            ("APP_SERVER__HOST", "true"),
+            ("APP_SERVER__PORT", "9000"),
+            ("APP_DEBUG", "yes"),
+        ]);
+        let cfg = Cfgfifo::new().env(env).load::<Config, _>(&path).unwrap();
+        assert_eq!(
+            cfg,
+            Config {
+                name: String::from("123"),
+                debug: true,
+                server: Server {
+                    host: String::from("true"),
+                    port: 9000,
+                },
+                tags: Vec::new(),
+            }
+        );
+    }
+}
+
+#[test]
+fn file_strings_not_converted_with_env() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    fs::write(&path, CONFIG.replace("port = 8080", "port = \"8080\"")).unwrap();
+    let env = EnvSource::new("APP_").vars([("APP_NAME", "app")]);
+    let r = Cfgfifo::new().env(env).load::<Config, _>(&path);
+    let Err(LoadError::Overlay { var, source }) = r else {
+        panic!("load() did not fail with Overlay error: {r:?}");
+    };
+    assert_eq!(var, None);
+    assert_eq!(
+        source.to_string(),
+        "server.port: invalid type: string \"8080\", expected u16"
+    );
+}
+
+#[test]
+fn file_error_with_env() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    fs::write(&path, "name = \"app\"\n\n[server]\nhost = \"localhost\"\n").unwrap();
+    let env = EnvSource::new("APP_").vars([("APP_DEBUG", "true")]);
+    let r = Cfgfifo::new().env(env).load::<Config, _>(&path);
+    let Err(LoadError::Overlay { var, source }) = r else {
+        panic!("load() did not fail with Overlay error: {r:?}");
+    };
+    assert_eq!(var, None);
+    assert_eq!(source.to_string(), "server: missing field `port`");
+}
+
+#[test]
+fn env_layer() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    fs::write(&path, CONFIG).unwrap();
+    let (cfg, provenance) = Layers::new()
+        .file(&path)
+        .env(EnvSource::new("APP_").vars([("APP_NAME", "123"), ("APP_SERVER__PORT", "9000")]))
+        .load_with_provenance::<Config>()
+        .unwrap();
+    assert_eq!(cfg.name, "123");
+    assert_eq!(cfg.server.port, 9000);
+    assert_eq!(
+        provenance.get("server.port"),
+        Some(&Origin::Env {
+            var: String::from("APP_SERVER__PORT")
+        })
+    );
+    assert_eq!(
+        provenance.get("server.port").unwrap().to_string(),
+        "environment variable APP_SERVER__PORT"
+    );
+    assert_eq!(
+        provenance.get("server.host").unwrap().to_string(),
+        format!("{}:4:8", path.display())
+    );
+}
@@ -0,0 +1,313 @@
+#![cfg(feature = "dotenv")]
+use cfgfifo::*;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Seek, Write, read_to_string};
+use tempfile::{Builder, tempdir, tempfile};
+
+/// dotenv files are flat, so they get their own config type
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+struct EnvConfig {
+    name: String,
+    integer: u32,
+    float: f64,
+    boolean: bool,
+    text: String,
+    #[serde(default)]
+    none: Option<u32>,
+    some: Option<u32>,
+    list: Vec<u32>,
+    color: Color,
+    database_url: String,
+}
+
+impl EnvConfig {
+    fn get() -> EnvConfig {
+        EnvConfig {
+            name: String::from("Example"),
+            integer: 42,
+            float: 1.618,
+            boolean: true,
+            text: String::from(
+                "This is test text.\nThis is a new line.\n\tThis is an indented line.\nThis is a snowman with a goat: \u{2603}\u{1F410}.",
+            ),
+            none: None,
+            some: Some(17),
+            list: vec![1, 2, 6, 15, 36],
+            color: Color::Green,
+            database_url: String::from("postgres://localhost:5432/app"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+static DOTENV: &str = indoc! {r#"
+    NAME=Example
+    INTEGER=42
+    FLOAT=1.618
+    BOOLEAN=true
+    TEXT="This is test text.\nThis is a new line.\n\tThis is an indented line.\nThis is a snowman with a goat: ☃🐐."
+    SOME=17
+    LIST="1, 2, 6, 15, 36"
+    COLOR=green
+    DATABASE_URL=postgres://localhost:5432/app
+"#};
+
+#[test]
+fn identify() {
+    assert_eq!(Format::from_extension("env"), Some(Format::Dotenv));
+    assert_eq!(Format::identify(".env").unwrap(), Format::Dotenv);
+    assert_eq!(Format::identify("path/to/.env").unwrap(), Format::Dotenv);
+    assert_eq!(
+        Format::identify("path/to/production.env").unwrap(),
+        Format::Dotenv
+    );
+    assert_eq!(
+        Cfgfifo::default().identify("path/to/.env").unwrap(),
+        Format::Dotenv
+    );
+    assert_eq!("dotenv".parse::<Format>().unwrap(), Format::Dotenv);
+}
+
+#[test]
+fn load_from_str() {
+    let r = Format::Dotenv.load_from_str::<EnvConfig>(DOTENV);
+    assert_eq!(r.unwrap(), EnvConfig::get());
+}
+
+#[test]
+fn dump_to_string() {
+    let r = Format::Dotenv.dump_to_string(&EnvConfig::get());
+    assert_eq!(r.unwrap(), DOTENV);
+}
+
+#[test]
+fn load_from_reader() {
+    let mut file = tempfile().unwrap();
+    file.write_all(DOTENV.as_bytes()).unwrap();
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let r = Format::Dotenv.load_from_reader::<_, EnvConfig>(file);
+    assert_eq!(r.unwrap(), EnvConfig::get());
+}
+
+#[test]
+fn dump_to_writer() {
+    let mut file = tempfile().unwrap();
+    let r = Format::Dotenv.dump_to_writer(&file, &EnvConfig::get());
+    assert!(r.is_ok());
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, DOTENV);
+}
+
+#[test]
+fn load_from_file() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join(".env");
+    std::fs::write(&path, DOTENV).unwrap();
+    let r = load::<EnvConfig, _>(&path);
+    assert_eq!(r.unwrap(), EnvConfig::get());
+}
+
+#[test]
+fn dump_to_file() {
+    let mut file = Builder::new().suffix(".env").tempfile().unwrap();
+    let r = dump(&file, &EnvConfig::get());
+    assert!(r.is_ok());
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, DOTENV);
+}
+
+#[test]
+fn sniff_load() {
+    let mut file = Builder::new().tempfile().unwrap();
+    file.write_all(DOTENV.as_bytes()).unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new().sniff(true);
+    let r = cfg.load::<EnvConfig, _>(&file);
+    assert_eq!(r.unwrap(), EnvConfig::get());
+}
+
+#[test]
+fn load_syntax() {
+    let s = indoc! {r#"
+        # A comment
+        export NAME=Example
+        INTEGER = 42 # The answer
+        FLOAT='1.618'
+        BOOLEAN=yes
+        TEXT="This is test text.
+        This is a new line.
+        	This is an indented line.
+        This is a snowman with a goat: ☃🐐."
+        NONE= # nothing
+        SOME="17"  # quoted
+        LIST=1,2,6,15,36
+        COLOR=red
+        COLOR=green
+        DATABASE_URL=postgres://localhost:5432/app
+    "#};
+    let r = Format::Dotenv.load_from_str::<EnvConfig>(s);
+    assert_eq!(r.unwrap(), EnvConfig::get());
+}
+
+#[test]
+fn load_string_map() {
+    let s = "PORT=8080\nDEBUG=true\nGREETING='Hello, world!'\n";
+    let value = Format::Dotenv
+        .load_from_str::<BTreeMap<String, String>>(s)
+        .unwrap();
+    assert_eq!(
+        value,
+        BTreeMap::from([
+            (String::from("DEBUG"), String::from("true")),
+            (String::from("GREETING"), String::from("Hello, world!")),
+            (String::from("PORT"), String::from("8080")),
+        ])
+    );
+}
+
+#[test]
+fn load_value() {
+    let s = "NAME=Example\nPORT=8080\nDEBUG=true\n";
+    let value = Format::Dotenv.load_from_str::<Value>(s).unwrap();
+    let expected = Value::Map(Map::from([
+        (String::from("NAME"), Value::from("Example")),
+        (String::from("PORT"), Value::Integer(8080)),
+        (String::from("DEBUG"), Value::Bool(true)),
+    ]));
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn expansion() {
+    let s = indoc! {r#"
+        HOST=localhost
+        PORT=8080
+        URL=http://${HOST}:${PORT}/
+        QUOTED="${HOST} \${HOST}"
+        LITERAL='${HOST}'
+        DEFAULT=${CFGFIFO_TEST_UNSET_VARIABLE:-fallback}
+        UNSET=${CFGFIFO_TEST_UNSET_VARIABLE}
+    "#};
+    let value = Format::Dotenv
+        .load_from_str::<BTreeMap<String, String>>(s)
+        .unwrap();
+    assert_eq!(value["URL"], "http://localhost:8080/");
+    assert_eq!(value["QUOTED"], "localhost ${HOST}");
+    assert_eq!(value["LITERAL"], "${HOST}");
+    assert_eq!(value["DEFAULT"], "fallback");
+    assert_eq!(value["UNSET"], "");
+}
+
+#[test]
+fn no_expansion() {
+    let s = "HOST=localhost\nURL=http://${HOST}/\n";
+    let value = DotenvOptions::new()
+        .expand(false)
+        .load_from_str::<BTreeMap<String, String>>(s)
+        .unwrap();
+    assert_eq!(value["URL"], "http://${HOST}/");
+}
+
+#[test]
+fn dump_quoting() {
+    let value = BTreeMap::from([
+        (String::from("EMPTY"), String::new()),
+        (String::from("PATH"), String::from("/usr/bin:/bin")),
+        (String::from("PRICE"), String::from("$5 \"each\"")),
+        (String::from("SPACED"), String::from(" padded ")),
+    ]);
+    let s = Format::Dotenv.dump_to_string(&value).unwrap();
+    assert_eq!(
+        s,
+        indoc! {r#"
+            EMPTY=
+            PATH=/usr/bin:/bin
+            PRICE="\$5 \"each\""
+            SPACED=" padded "
+        "#}
+    );
+    let back = Format::Dotenv
+        .load_from_str::<BTreeMap<String, String>>(&s)
+        .unwrap();
+    assert_eq!(back, value);
+}
+
+#[test]
+fn dump_nested() {
+    let value = BTreeMap::from([(
+        String::from("SERVER"),
+        BTreeMap::from([(String::from("PORT"), 8080)]),
+    )]);
+    let e = Format::Dotenv.dump_to_string(&value).unwrap_err();
+    assert_eq!(e.to_string(), "SERVER: maps cannot be nested this deeply");
+}
+
+#[test]
+fn syntax_error() {
+    let s = "NAME=Example\nGREETING=\"Hello\nPORT=8080\n";
+    let e = Format::Dotenv.load_from_str::<Value>(s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(2, 10)));
+    assert_eq!(
+        e.to_string(),
+        "unterminated quoted value at line 2 column 10"
+    );
+}
+
+#[test]
+fn error_location() {
+    let s = DOTENV.replace("INTEGER=42", "INTEGER=3.14");
+    let e = Format::Dotenv.load_from_str::<EnvConfig>(&s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(2, 9)));
+    assert_eq!(
+        e.render(&s),
+        indoc! {r#"
+            INTEGER: invalid value: string "3.14", expected u32
+             --> 2:9
+              |
+            1 | NAME=Example
+            2 | INTEGER=3.14
+              |         ^
+        "#}
+        .trim_end()
+    );
+}
+
+#[test]
+fn load_error_location() {
+    let mut file = Builder::new().suffix(".env").tempfile().unwrap();
+    file.write_all(b"NAME=Example\nFLOAT=true\n").unwrap();
+    file.flush().unwrap();
+    let e = load::<EnvConfig, _>(&file).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        format!("failed to deserialize {} as DOTENV", file.path().display())
+    );
+    assert_eq!(e.location(), Some(Location::new(2, 7)));
+}
+
+#[test]
+fn custom_options() {
+    let options = DotenvOptions::new().expand(false);
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join(".env");
+    std::fs::write(&path, "HOST=localhost\nURL=${HOST}\n").unwrap();
+    let cfg = Cfgfifo::new().extension("env", AnyFormat::custom(options));
+    let value = cfg.load::<BTreeMap<String, String>, _>(&path).unwrap();
+    assert_eq!(value["URL"], "${HOST}");
+}
@@ -0,0 +1,340 @@
+#![cfg(feature = "hcl")]
+use crate::Config;
+use cfgfifo::*;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use std::collections::BTreeMap;
+use std::io::{Seek, Write, read_to_string};
+use tempfile::{Builder, tempfile};
+
+static HCL: &str = indoc! {r#"
+    primitives {
+      integer = 42
+      float = 1.618
+      boolean = true
+      text = "This is test text.\nThis is a new line.\n\tThis is an indented line.\nThis is a snowman with a goat: ☃🐐."
+      none = null
+      some = 17
+      list = [
+        1,
+        2,
+        6,
+        15,
+        36
+      ]
+
+      dict {
+        hello = "goodbye"
+        strange = "charmed"
+        up = "down"
+      }
+    }
+
+    enums {
+      color = "green"
+
+      msg {
+        type = "Response"
+        id = 60069
+        value = "Foobar"
+      }
+    }
+
+    people = [
+      {
+        id = 1
+        given_name = "Alice"
+        family_name = "Alison"
+      },
+      {
+        id = 2
+        given_name = "Bob"
+        family_name = "Bobson"
+      },
+      {
+        id = 3
+        given_name = "Charlie"
+        family_name = "McCharles"
+      }
+    ]
+"#};
+
+static TERRAFORM: &str = indoc! {r#"
+    region = "us-east-1"
+
+    provider "aws" {
+      profile = "default"
+    }
+
+    resource "aws_instance" "web" {
+      ami = "ami-123"
+      tags = {
+        Name = "web"
+      }
+    }
+
+    resource "aws_instance" "db" {
+      ami = "ami-456"
+    }
+"#};
+
+fn terraform_value() -> Value {
+    Value::Map(Map::from([
+        (String::from("region"), Value::from("us-east-1")),
+        (
+            String::from("provider"),
+            Value::Map(Map::from([(
+                String::from("aws"),
+                Value::Map(Map::from([(
+                    String::from("profile"),
+                    Value::from("default"),
+                )])),
+            )])),
+        ),
+        (
+            String::from("resource"),
+            Value::Map(Map::from([(
+                String::from("aws_instance"),
+                Value::Map(Map::from([
+                    (
+                        String::from("web"),
+                        Value::Map(Map::from([
+                            (String::from("ami"), Value::from("ami-123")),
+                            (
+                                String::from("tags"),
+                                Value::Map(Map::from([(String::from("Name"), Value::from("web"))])),
+                            ),
+                        ])),
+                    ),
+                    (
+                        String::from("db"),
+                        Value::Map(Map::from([(String::from("ami"), Value::from("ami-456"))])),
+                    ),
+                ])),
+            )])),
+        ),
+    ]))
+}
+
+#[test]
+fn identify() {
+    assert_eq!(Format::from_extension("hcl"), Some(Format::Hcl));
+    assert_eq!(Format::identify("path/to/main.hcl").unwrap(), Format::Hcl);
+    assert_eq!("hcl".parse::<Format>().unwrap(), Format::Hcl);
+}
+
+#[test]
+fn load_from_str() {
+    let r = Format::Hcl.load_from_str::<Config>(HCL);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn dump_to_string() {
+    let r = Format::Hcl.dump_to_string(&Config::get());
+    assert_eq!(r.unwrap(), HCL);
+}
+
+#[test]
+fn load_from_reader() {
+    let mut file = tempfile().unwrap();
+    file.write_all(HCL.as_bytes()).unwrap();
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let r = Format::Hcl.load_from_reader::<_, Config>(file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn dump_to_writer() {
+    let mut file = tempfile().unwrap();
+    let r = Format::Hcl.dump_to_writer(&file, &Config::get());
+    assert!(r.is_ok());
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, HCL);
+}
+
+#[test]
+fn load_from_file() {
+    let mut file = Builder::new().suffix(".hcl").tempfile().unwrap();
+    file.write_all(HCL.as_bytes()).unwrap();
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let r = load::<Config, _>(file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn dump_to_file() {
+    let mut file = Builder::new().suffix(".hcl").tempfile().unwrap();
+    let r = dump(&file, &Config::get());
+    assert!(r.is_ok());
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, HCL);
+}
+
+#[test]
+fn sniff_load() {
+    let mut file = Builder::new().tempfile().unwrap();
+    file.write_all(HCL.as_bytes()).unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new().sniff(true);
+    let r = cfg.load::<Config, _>(&file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn load_labeled_blocks() {
+    let value = Format::Hcl.load_from_str::<Value>(TERRAFORM).unwrap();
+    assert_eq!(value, terraform_value());
+}
+
+#[test]
+fn dump_labeled_blocks() {
+    let s = Format::Hcl.dump_to_string(&terraform_value()).unwrap();
+    assert_eq!(
+        s,
+        indoc! {r#"
+            region = "us-east-1"
+
+            provider "aws" {
+              profile = "default"
+            }
+
+            resource "aws_instance" "web" {
+              ami = "ami-123"
+
+              tags {
+                Name = "web"
+              }
+            }
+
+            resource "aws_instance" "db" {
+              ami = "ami-456"
+            }
+        "#}
+    );
+    let back = Format::Hcl.load_from_str::<Value>(&s).unwrap();
+    assert_eq!(back, terraform_value());
+}
+
+#[test]
+fn load_repeated_blocks() {
+    let s = indoc! {"
+        listener {
+          port = 80
+        }
+
+        listener {
+          port = 443
+        }
+    "};
+    let value = Format::Hcl
+        .load_from_str::<BTreeMap<String, Vec<BTreeMap<String, u16>>>>(s)
+        .unwrap();
+    assert_eq!(
+        value,
+        BTreeMap::from([(
+            String::from("listener"),
+            vec![
+                BTreeMap::from([(String::from("port"), 80)]),
+                BTreeMap::from([(String::from("port"), 443)]),
+            ]
+        )])
+    );
+}
+
+#[test]
+fn load_expressions() {
+    let s = "name = var.name\nurl = \"https://${var.host}/\"\n";
+    let value = Format::Hcl
+        .load_from_str::<BTreeMap<String, String>>(s)
+        .unwrap();
+    assert_eq!(value["name"], "${var.name}");
+    assert_eq!(value["url"], "https://${var.host}/");
+}
+
+#[test]
+fn dump_non_identifier_keys() {
+    let value = BTreeMap::from([(
+        String::from("labels"),
+        BTreeMap::from([(String::from("app.kubernetes.io/name"), String::from("web"))]),
+    )]);
+    let s = Format::Hcl.dump_to_string(&value).unwrap();
+    assert_eq!(
+        s,
+        indoc! {r#"
+            labels = {
+              "app.kubernetes.io/name" = "web"
+            }
+        "#}
+    );
+    let back = Format::Hcl
+        .load_from_str::<BTreeMap<String, BTreeMap<String, String>>>(&s)
+        .unwrap();
+    assert_eq!(back, value);
+}
+
+#[test]
+fn dump_bad_top_level_key() {
+    let value = BTreeMap::from([(String::from("not an identifier"), 42)]);
+    let e = Format::Hcl.dump_to_string(&value).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "key \"not an identifier\" is not a valid HCL identifier"
+    );
+}
+
+#[test]
+fn dump_non_map() {
+    let e = Format::Hcl.dump_to_string(&[1, 2, 3]).unwrap_err();
+    assert_eq!(e.to_string(), "top-level value must be a map");
+}
+
+#[test]
+fn syntax_error() {
+    let s = "name = \"Example\"\nports = [80,\nenabled = true\n";
+    let e = Format::Hcl.load_from_str::<Value>(s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(3, 9)));
+    assert_eq!(
+        e.render(s),
+        indoc! {"
+            expected `]`
+             --> 3:9
+              |
+            2 | ports = [80,
+            3 | enabled = true
+              |         ^
+        "}
+        .trim_end()
+    );
+}
+
+#[test]
+fn error_path() {
+    let s = HCL.replace("integer = 42", "integer = 3.14");
+    let e = Format::Hcl.load_from_str::<Config>(&s).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "primitives.integer: invalid type: floating point `3.14`, expected u32"
+    );
+    assert_eq!(e.location(), None);
+}
+
+#[test]
+fn load_error_location() {
+    let mut file = Builder::new().suffix(".hcl").tempfile().unwrap();
+    file.write_all(b"primitives {\n  integer = 42\n  float == 1.618\n}\n")
+        .unwrap();
+    file.flush().unwrap();
+    let e = load::<Config, _>(&file).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        format!("failed to deserialize {} as HCL", file.path().display())
+    );
+    assert_eq!(e.location(), Some(Location::new(3, 10)));
+}
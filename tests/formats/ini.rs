@@ -0,0 +1,337 @@
+#![cfg(feature = "ini")]
+use cfgfifo::*;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Seek, Write, read_to_string};
+use tempfile::{Builder, tempfile};
+
+/// INI cannot represent the nesting of the common `Config`, so it gets its
+/// own
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct IniConfig {
+    name: String,
+    verbose: bool,
+    primitives: Primitives,
+    dict: BTreeMap<String, String>,
+}
+
+impl IniConfig {
+    fn get() -> IniConfig {
+        IniConfig {
+            name: String::from("Example"),
+            verbose: false,
+            primitives: Primitives {
+                integer: 42,
+                float: 1.618,
+                boolean: true,
+                text: String::from("This is a snowman with a goat: \u{2603}\u{1F410}."),
+                none: None,
+                some: Some(17),
+                list: vec![1, 2, 6, 15, 36],
+                color: Color::Green,
+            },
+            dict: BTreeMap::from([
+                (String::from("hello"), String::from("goodbye")),
+                (String::from("strange"), String::from("charmed")),
+                (String::from("up"), String::from("down")),
+            ]),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct Primitives {
+    integer: u32,
+    float: f64,
+    boolean: bool,
+    text: String,
+    #[serde(default)]
+    none: Option<u32>,
+    some: Option<u32>,
+    list: Vec<u32>,
+    color: Color,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+static INI: &str = indoc! {"
+    name = Example
+    verbose = false
+
+    [primitives]
+    integer = 42
+    float = 1.618
+    boolean = true
+    text = This is a snowman with a goat: ☃🐐.
+    some = 17
+    list = 1, 2, 6, 15, 36
+    color = green
+
+    [dict]
+    hello = goodbye
+    strange = charmed
+    up = down
+"};
+
+#[test]
+fn identify() {
+    assert_eq!(Format::from_extension("ini"), Some(Format::Ini));
+    assert_eq!(Format::identify("path/to/file.INI").unwrap(), Format::Ini);
+    assert_eq!("ini".parse::<Format>().unwrap(), Format::Ini);
+}
+
+#[test]
+fn load_from_str() {
+    let r = Format::Ini.load_from_str::<IniConfig>(INI);
+    assert_eq!(r.unwrap(), IniConfig::get());
+}
+
+#[test]
+fn dump_to_string() {
+    let r = Format::Ini.dump_to_string(&IniConfig::get());
+    assert_eq!(r.unwrap(), INI);
+}
+
+#[test]
+fn load_from_reader() {
+    let mut file = tempfile().unwrap();
+    file.write_all(INI.as_bytes()).unwrap();
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let r = Format::Ini.load_from_reader::<_, IniConfig>(file);
+    assert_eq!(r.unwrap(), IniConfig::get());
+}
+
+#[test]
+fn dump_to_writer() {
+    let mut file = tempfile().unwrap();
+    let r = Format::Ini.dump_to_writer(&file, &IniConfig::get());
+    assert!(r.is_ok());
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, INI);
+}
+
+#[test]
+fn load_from_file() {
+    let mut file = Builder::new().suffix(".ini").tempfile().unwrap();
+    file.write_all(INI.as_bytes()).unwrap();
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let r = load::<IniConfig, _>(file);
+    assert_eq!(r.unwrap(), IniConfig::get());
+}
+
+#[test]
+fn dump_to_file() {
+    let mut file = Builder::new().suffix(".ini").tempfile().unwrap();
+    let r = dump(&file, &IniConfig::get());
+    assert!(r.is_ok());
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, INI);
+}
+
+#[test]
+fn sniff_load() {
+    let mut file = Builder::new().tempfile().unwrap();
+    file.write_all(INI.as_bytes()).unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new().sniff(true);
+    let r = cfg.load::<IniConfig, _>(&file);
+    assert_eq!(r.unwrap(), IniConfig::get());
+}
+
+#[test]
+fn load_syntax() {
+    let s = indoc! {"
+        ; A comment
+        # Another comment
+        name: Example
+        verbose = NO
+
+        [dict]
+          hello=goodbye
+        [primitives]
+        integer = 42
+        float = 1.618
+        boolean = On
+        text = This is a snowman with a goat: ☃🐐.
+        none =
+        some = 17
+        list = 1,2,6 , 15,36
+        color = green
+        [dict]
+        strange = charmed
+        up = sideways
+        up = down
+    "};
+    let r = Format::Ini.load_from_str::<IniConfig>(s);
+    assert_eq!(r.unwrap(), IniConfig::get());
+}
+
+#[test]
+fn load_value() {
+    let s = "name = Example\nport = 8080\n\n[server]\nhost = localhost\ndebug = true\n";
+    let value = Format::Ini.load_from_str::<Value>(s).unwrap();
+    let expected = Value::Map(Map::from([
+        (String::from("name"), Value::from("Example")),
+        (String::from("port"), Value::Integer(8080)),
+        (
+            String::from("server"),
+            Value::Map(Map::from([
+                (String::from("host"), Value::from("localhost")),
+                (String::from("debug"), Value::Bool(true)),
+            ])),
+        ),
+    ]));
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn load_value_unparsed() {
+    let s = "port = 8080\n";
+    let value = IniOptions::new()
+        .parse_values(false)
+        .load_from_str::<Value>(s)
+        .unwrap();
+    assert_eq!(
+        value,
+        Value::Map(Map::from([(String::from("port"), Value::from("8080"))]))
+    );
+}
+
+#[test]
+fn global_section_named() {
+    let options = IniOptions::new().global_section(GlobalSection::Named(String::from("general")));
+    let s = "name = Example\n\n[server]\nport = 8080\n\n[general]\nverbose = yes\n";
+    let value = options
+        .load_from_str::<BTreeMap<String, BTreeMap<String, String>>>(s)
+        .unwrap();
+    assert_eq!(
+        value,
+        BTreeMap::from([
+            (
+                String::from("general"),
+                BTreeMap::from([
+                    (String::from("name"), String::from("Example")),
+                    (String::from("verbose"), String::from("yes")),
+                ])
+            ),
+            (
+                String::from("server"),
+                BTreeMap::from([(String::from("port"), String::from("8080"))])
+            ),
+        ])
+    );
+    let e = options.dump_to_string(&IniConfig::get()).unwrap_err();
+    assert_eq!(e.to_string(), "name: top-level values must be maps");
+}
+
+#[test]
+fn global_section_reject() {
+    let options = IniOptions::new().global_section(GlobalSection::Reject);
+    let e = options.load_from_str::<Value>(INI).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(1, 1)));
+    assert_eq!(
+        e.to_string(),
+        "key outside of any section at line 1 column 1"
+    );
+}
+
+#[test]
+fn syntax_error() {
+    let s = "[server]\nport = 8080\nhost\n";
+    let e = Format::Ini.load_from_str::<Value>(s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(3, 1)));
+    assert_eq!(
+        e.render(s),
+        indoc! {"
+            expected `key = value` or `[section]`
+             --> 3:1
+              |
+            2 | port = 8080
+            3 | host
+              | ^
+        "}
+        .trim_end()
+    );
+}
+
+#[test]
+fn dump_too_deep() {
+    #[derive(Serialize)]
+    struct Outer {
+        inner: BTreeMap<String, BTreeMap<String, u32>>,
+    }
+
+    let value = Outer {
+        inner: BTreeMap::from([(String::from("a"), BTreeMap::from([(String::from("b"), 1)]))]),
+    };
+    let e = Format::Ini.dump_to_string(&value).unwrap_err();
+    assert_eq!(e.to_string(), "inner.a: maps cannot be nested this deeply");
+}
+
+#[test]
+fn dump_multiline() {
+    let value = BTreeMap::from([(String::from("text"), String::from("foo\nbar"))]);
+    let e = Format::Ini.dump_to_string(&value).unwrap_err();
+    assert_eq!(e.to_string(), "text: strings cannot contain line breaks");
+}
+
+#[test]
+fn error_location() {
+    let s = INI.replace("integer = 42", "integer = 3.14");
+    let e = Format::Ini.load_from_str::<IniConfig>(&s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(5, 11)));
+    assert_eq!(
+        e.render(&s),
+        indoc! {r#"
+            primitives.integer: invalid value: string "3.14", expected u32
+             --> 5:11
+              |
+            4 | [primitives]
+            5 | integer = 3.14
+              |           ^
+        "#}
+        .trim_end()
+    );
+}
+
+#[test]
+fn load_error_location() {
+    let mut file = Builder::new().suffix(".ini").tempfile().unwrap();
+    file.write_all(b"[primitives]\ninteger = 42\nfloat = true\n")
+        .unwrap();
+    file.flush().unwrap();
+    let e = load::<IniConfig, _>(&file).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        format!("failed to deserialize {} as INI", file.path().display())
+    );
+    assert_eq!(e.location(), Some(Location::new(3, 9)));
+}
+
+#[test]
+fn custom_options() {
+    let options = IniOptions::new().global_section(GlobalSection::Reject);
+    let mut file = Builder::new().suffix(".ini").tempfile().unwrap();
+    file.write_all(b"name = Example\n").unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new().extension("ini", AnyFormat::custom(options));
+    let e = cfg.load::<Value, _>(&file).unwrap_err();
+    assert!(matches!(
+        e,
+        LoadError::Deserialize { ref source, .. } if matches!(**source, DeserializeError::Custom(_))
+    ));
+}
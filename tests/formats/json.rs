@@ -1,5 +1,5 @@
 #![cfg(feature = "json")]
-use crate::Config;
+use crate::{Color, Config};
 use cfgfifo::*;
 use indoc::indoc;
 use pretty_assertions::assert_eq;
@@ -160,3 +160,46 @@ fn deserialize_error() {
         "primitives.integer: invalid type: floating point `3.14`, expected u32 at line 3 column 19"
     );
 }
+
+#[test]
+fn sniff_load() {
+    let mut file = Builder::new().tempfile().unwrap();
+    file.write_all(JSON.as_bytes()).unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new().sniff(true);
+    let r = cfg.load::<Config, _>(&file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn update_file() {
+    let mut file = Builder::new().suffix(".json").tempfile().unwrap();
+    writeln!(file, "{JSON}").unwrap();
+    file.flush().unwrap();
+    let r = Cfgfifo::new().update(&file, |cfg: &mut Config| cfg.enums.color = Color::Blue);
+    let mut expected = Config::get();
+    expected.enums.color = Color::Blue;
+    assert_eq!(r.unwrap(), expected);
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, format!("{}\n", JSON.replace("\"green\"", "\"blue\"")));
+}
+
+#[test]
+fn error_location() {
+    let s = JSON.replace("\"integer\": 42", "\"integer\": 3.14");
+    let e = Format::Json.load_from_str::<Config>(&s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(3, 19)));
+    assert_eq!(
+        e.render(&s),
+        indoc! {r#"
+            primitives.integer: invalid type: floating point `3.14`, expected u32 at line 3 column 19
+             --> 3:19
+              |
+            2 |   "primitives": {
+            3 |     "integer": 3.14,
+              |                   ^
+        "#}
+        .trim_end()
+    );
+}
@@ -0,0 +1,537 @@
+#![cfg(feature = "kdl")]
+use crate::Config;
+use cfgfifo::*;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::{Seek, Write, read_to_string};
+use tempfile::{Builder, tempfile};
+
+static KDL: &str = indoc! {r#"
+    primitives {
+        integer 42
+        float 1.618
+        boolean #true
+        text "This is test text.\nThis is a new line.\n\tThis is an indented line.\nThis is a snowman with a goat: ☃🐐."
+        none #null
+        some 17
+        list 1 2 6 15 36
+        dict {
+            hello goodbye
+            strange charmed
+            up down
+        }
+    }
+    enums {
+        color green
+        msg {
+            type Response
+            id 60069
+            value Foobar
+        }
+    }
+    people {
+        - id=1 given_name=Alice family_name=Alison
+        - id=2 given_name=Bob family_name=Bobson
+        - id=3 given_name=Charlie family_name=McCharles
+    }
+"#};
+
+#[test]
+fn identify() {
+    assert_eq!(Format::from_extension("kdl"), Some(Format::Kdl));
+    assert_eq!(Format::identify("path/to/config.kdl").unwrap(), Format::Kdl);
+    assert_eq!("kdl".parse::<Format>().unwrap(), Format::Kdl);
+}
+
+#[test]
+fn load_from_str() {
+    let r = Format::Kdl.load_from_str::<Config>(KDL);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn dump_to_string() {
+    let r = Format::Kdl.dump_to_string(&Config::get());
+    assert_eq!(r.unwrap(), KDL);
+}
+
+#[test]
+fn load_from_reader() {
+    let mut file = tempfile().unwrap();
+    file.write_all(KDL.as_bytes()).unwrap();
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let r = Format::Kdl.load_from_reader::<_, Config>(file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn dump_to_writer() {
+    let mut file = tempfile().unwrap();
+    let r = Format::Kdl.dump_to_writer(&file, &Config::get());
+    assert!(r.is_ok());
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, KDL);
+}
+
+#[test]
+fn load_from_file() {
+    let mut file = Builder::new().suffix(".kdl").tempfile().unwrap();
+    file.write_all(KDL.as_bytes()).unwrap();
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let r = load::<Config, _>(file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn dump_to_file() {
+    let mut file = Builder::new().suffix(".kdl").tempfile().unwrap();
+    let r = dump(&file, &Config::get());
+    assert!(r.is_ok());
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, KDL);
+}
+
+#[test]
+fn sniff_load() {
+    let mut file = Builder::new().tempfile().unwrap();
+    file.write_all(KDL.as_bytes()).unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new().sniff(true);
+    let r = cfg.load::<Config, _>(&file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn load_syntax() {
+    let s = indoc! {r##"
+        // A comment
+        primitives {
+            integer 0x2a
+            float (f64)1.618
+            boolean #true; none
+            text """
+                This is test text.
+                This is a new line.
+                \tThis is an indented line.
+                This is a snowman with a goat: \u{2603}🐐.
+                """
+            /-some 42
+            some 17 /* inline */
+            list 1 2 \
+                6 15 36
+            dict hello=goodbye strange=charmed up=wrong up=down
+        }
+        enums color=green {
+            msg type=Response id=60_069 value=#"Foobar"#
+        }
+        people id=1 given_name=Alice family_name=Alison
+        people id=2 given_name=Bob family_name=Bobson
+        "people" id=3 given_name=Charlie family_name=McCharles
+        unused 1
+        unused 2
+    "##};
+    let r = Format::Kdl.load_from_str::<Config>(s);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn load_value() {
+    let s = indoc! {"
+        name Example
+        ports 80 443
+        debug #false
+        timeout #null
+        server host=localhost {
+            port 8080
+        }
+        hosts {
+            - a
+            - b
+        }
+        user name=alice
+        user name=bob
+    "};
+    let value = Format::Kdl.load_from_str::<Value>(s).unwrap();
+    let user = |name: &str| Value::Map(Map::from([(String::from("name"), Value::from(name))]));
+    let expected = Value::Map(Map::from([
+        (String::from("name"), Value::from("Example")),
+        (
+            String::from("ports"),
+            Value::Seq(vec![Value::Integer(80), Value::Integer(443)]),
+        ),
+        (String::from("debug"), Value::Bool(false)),
+        (String::from("timeout"), Value::Null),
+        (
+            String::from("server"),
+            Value::Map(Map::from([
+                (String::from("host"), Value::from("localhost")),
+                (String::from("port"), Value::Integer(8080)),
+            ])),
+        ),
+        (
+            String::from("hosts"),
+            Value::Seq(vec![Value::from("a"), Value::from("b")]),
+        ),
+        (
+            String::from("user"),
+            Value::Seq(vec![user("alice"), user("bob")]),
+        ),
+    ]));
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn load_enums() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Rect { width: u32, height: u32 },
+    }
+
+    let s = "a point\nb circle=1.5\nc {\n    rect width=3 height=4\n}\n";
+    let value = Format::Kdl
+        .load_from_str::<BTreeMap<String, Shape>>(s)
+        .unwrap();
+    assert_eq!(
+        value,
+        BTreeMap::from([
+            (String::from("a"), Shape::Point),
+            (String::from("b"), Shape::Circle(1.5)),
+            (
+                String::from("c"),
+                Shape::Rect {
+                    width: 3,
+                    height: 4
+                }
+            ),
+        ])
+    );
+}
+
+#[test]
+fn dump_nested() {
+    let value = Value::Map(Map::from([
+        (
+            String::from("matrix"),
+            Value::Seq(vec![
+                Value::Seq(vec![Value::Integer(1), Value::Integer(2)]),
+                Value::Seq(vec![Value::Integer(3), Value::Integer(4)]),
+            ]),
+        ),
+        (
+            String::from("servers"),
+            Value::Seq(vec![Value::Map(Map::from([
+                (String::from("name"), Value::from("alpha")),
+                (
+                    String::from("ports"),
+                    Value::Seq(vec![Value::Integer(80), Value::Integer(443)]),
+                ),
+            ]))]),
+        ),
+        (String::from("empty"), Value::Map(Map::new())),
+    ]));
+    let s = Format::Kdl.dump_to_string(&value).unwrap();
+    assert_eq!(
+        s,
+        indoc! {"
+            matrix {
+                - 1 2
+                - 3 4
+            }
+            servers {
+                - {
+                    name alpha
+                    ports 80 443
+                }
+            }
+            empty {}
+        "}
+    );
+    let back = Format::Kdl.load_from_str::<Value>(&s).unwrap();
+    assert_eq!(back, value);
+}
+
+#[test]
+fn dump_quoting() {
+    let value = BTreeMap::from([
+        (String::from("app.kubernetes.io/name"), String::from("web")),
+        (String::from("empty"), String::new()),
+        (String::from("keyword"), String::from("true")),
+        (String::from("number"), String::from("42")),
+        (String::from("path"), String::from("C:\\Program Files")),
+        (String::from("quote"), String::from("say \"hi\"")),
+    ]);
+    let s = Format::Kdl.dump_to_string(&value).unwrap();
+    assert_eq!(
+        s,
+        indoc! {r#"
+            "app.kubernetes.io/name" web
+            empty ""
+            keyword "true"
+            number "42"
+            path "C:\\Program Files"
+            quote "say \"hi\""
+        "#}
+    );
+    let back = Format::Kdl
+        .load_from_str::<BTreeMap<String, String>>(&s)
+        .unwrap();
+    assert_eq!(back, value);
+}
+
+#[test]
+fn dump_non_finite_floats() {
+    let value = BTreeMap::from([
+        (String::from("a"), f64::INFINITY),
+        (String::from("b"), f64::NEG_INFINITY),
+        (String::from("c"), f64::NAN),
+    ]);
+    let s = Format::Kdl.dump_to_string(&value).unwrap();
+    assert_eq!(s, "a #inf\nb #-inf\nc #nan\n");
+    let back = Format::Kdl
+        .load_from_str::<BTreeMap<String, f64>>(&s)
+        .unwrap();
+    assert!(back["a"].is_infinite() && back["a"].is_sign_positive());
+    assert!(back["b"].is_infinite() && back["b"].is_sign_negative());
+    assert!(back["c"].is_nan());
+}
+
+#[test]
+fn dump_non_map() {
+    let e = Format::Kdl.dump_to_string(&[1, 2, 3]).unwrap_err();
+    assert_eq!(e.to_string(), "top-level value must be a map");
+}
+
+#[test]
+fn dump_dash_keys() {
+    let value = BTreeMap::from([(String::from("c"), BTreeMap::from([(String::from("-"), 1)]))]);
+    let e = Format::Kdl.dump_to_string(&value).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "c: maps whose keys are all \"-\" cannot be represented in KDL"
+    );
+    let e = Format::Kdl
+        .dump_to_string(&BTreeMap::from([(String::from("-"), 1)]))
+        .unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "maps whose keys are all \"-\" cannot be represented in KDL"
+    );
+}
+
+#[test]
+fn mixed_node() {
+    let s = "name Example\nserver main port=80\n";
+    let e = Format::Kdl.load_from_str::<Value>(s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(2, 1)));
+    assert_eq!(e.span(), Some(13..32));
+    assert_eq!(&s[13..32], "server main port=80");
+    assert_eq!(
+        e.to_string(),
+        "server: node cannot have both arguments and properties or children at line 2 column 1"
+    );
+}
+
+#[test]
+fn duplicate_node() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Server {
+        port: u16,
+    }
+
+    let s = "port 80\nport 443\n";
+    let e = Format::Kdl.load_from_str::<Server>(s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(2, 1)));
+    assert_eq!(
+        e.to_string(),
+        "port: duplicate node \"port\" at line 2 column 1"
+    );
+}
+
+#[test]
+fn syntax_error() {
+    let s = "name \"Example\nport 8080\n";
+    let e = Format::Kdl.load_from_str::<Value>(s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(1, 6)));
+    assert_eq!(e.span(), Some(5..13));
+    assert_eq!(
+        e.render(s),
+        indoc! {r#"
+            unterminated string
+             --> 1:6
+              |
+            1 | name "Example
+              |      ^
+        "#}
+        .trim_end()
+    );
+}
+
+#[test]
+fn error_location() {
+    let s = KDL.replace("integer 42", "integer 3.14");
+    let e = Format::Kdl.load_from_str::<Config>(&s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(2, 13)));
+    assert_eq!(e.span(), Some(25..29));
+    assert_eq!(
+        e.render(&s),
+        indoc! {"
+            primitives.integer: invalid type: floating point `3.14`, expected u32
+             --> 2:13
+              |
+            1 | primitives {
+            2 |     integer 3.14
+              |             ^
+        "}
+        .trim_end()
+    );
+}
+
+#[test]
+fn load_error_location() {
+    let mut file = Builder::new().suffix(".kdl").tempfile().unwrap();
+    file.write_all(b"primitives {\n    integer 42\n    float #yes\n}\n")
+        .unwrap();
+    file.flush().unwrap();
+    let e = load::<Config, _>(&file).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        format!("failed to deserialize {} as KDL", file.path().display())
+    );
+    assert_eq!(e.location(), Some(Location::new(3, 11)));
+    assert_eq!(e.span(), Some(38..42));
+}
+
+/// Assert that each KDL document in `cases` deserializes to the same value as
+/// its expected equivalent, in the manner of the KDL specification's test
+/// suite
+fn assert_equivalent(cases: &[(&str, &str)]) {
+    for &(input, expected) in cases {
+        let actual = Format::Kdl.load_from_str::<Value>(input);
+        let expected = Format::Kdl
+            .load_from_str::<Value>(expected)
+            .expect("expected document should be valid");
+        assert_eq!(actual.ok(), Some(expected), "input: {input:?}");
+    }
+}
+
+#[test]
+fn spec_strings() {
+    assert_equivalent(&[
+        (r##"node #"\n"#"##, r#"node "\\n""#),
+        (
+            r###"node ##"hello"#world"##"###,
+            r##"node "hello\"#world""##,
+        ),
+        (r#"node "\u{1F410}""#, "node 🐐"),
+        (
+            r#"node "\"\\\b\f\n\r\t\s""#,
+            "node \"\\\"\\\\\\u{8}\\u{C}\\u{A}\\u{D}\\u{9} \"",
+        ),
+        (r#"node "Hello\     World""#, "node HelloWorld"),
+        ("node \"Hello\\\n    World\"", "node HelloWorld"),
+        (
+            indoc! {r#"
+                node """
+                    hey
+                   everyone
+                     how goes?
+                  """
+            "#},
+            r#"node "  hey\n everyone\n   how goes?""#,
+        ),
+        (
+            "node \"\"\"\n    a\n\n      \n    b\n    \"\"\"\n",
+            r#"node "a\n\n\nb""#,
+        ),
+        (
+            indoc! {r#"
+                node """
+                  \tfoo\
+                    bar
+                  """
+            "#},
+            r#"node "\tfoobar""#,
+        ),
+        (
+            indoc! {r##"
+                node #"""
+                    a\b "c"
+                    """#
+            "##},
+            r#"node "a\\b \"c\"""#,
+        ),
+        ("node foo-bar", r#"node "foo-bar""#),
+        (r#""node name" 1"#, r##"#"node name"# 1"##),
+    ]);
+}
+
+#[test]
+fn spec_slashdash() {
+    assert_equivalent(&[
+        ("/-node 1\nother 2", "other 2"),
+        ("/- node 1\nother 2", "other 2"),
+        ("/-node {\n    child 1\n}\nother 2", "other 2"),
+        ("/-parent {\n    /-child 1\n}\nother 2", "other 2"),
+        ("node 1 /-2 3", "node 1 3"),
+        ("node 1 /-\n    2", "node 1"),
+        ("node /-key=1 other=2", "node other=2"),
+        ("node /-(type)1 2", "node 2"),
+        ("node 1 /-{\n    child 1\n}", "node 1"),
+        ("node /-{ a 1; } { b 2; } /-{ c 3; }", "node { b 2; }"),
+        (
+            "parent {\n    /-child 1\n    other 2\n}",
+            "parent { other 2; }",
+        ),
+        ("node 1 /* block /* nested */ comment */ 2", "node 1 2"),
+    ]);
+}
+
+#[test]
+fn spec_type_annotations() {
+    assert_equivalent(&[
+        ("(type)node 1", "node 1"),
+        (r#"node (u8)1 (str)"two""#, "node 1 two"),
+        ("node key=(type)value", "node key=value"),
+        ("(t)parent {\n    (t)child (t)1\n}", "parent { child 1; }"),
+        (r##"node ("quoted type")1 (#"raw type"#)2"##, "node 1 2"),
+        ("node ( spaced )1", "node 1"),
+        ("node (type) 1", "node 1"),
+        ("(type) node 1", "node 1"),
+    ]);
+}
+
+#[test]
+fn spec_invalid() {
+    for input in [
+        r#"node "\x""#,
+        r#"node "\u{110000}""#,
+        r#"node "unterminated"#,
+        r##"node ##"raw"#"##,
+        r#"node """single line""""#,
+        "node \"\"\"\n    foo\n  bar\n    \"\"\"",
+        "node (type)key=1",
+        "node 1 { child 1; } 2",
+        r#"node"arg""#,
+        "(a)(b)node",
+        "node #nope",
+        "node 1.2.3",
+        "node /* unterminated",
+        "node {",
+        "}",
+    ] {
+        let r = Format::Kdl.load_from_str::<Value>(input);
+        assert!(r.is_err(), "input: {input:?}");
+    }
+}
@@ -1,5 +1,10 @@
+mod dotenv;
+mod hcl;
+mod ini;
 mod json;
 mod json5;
+mod kdl;
+mod properties;
 mod ron;
 mod toml;
 mod yaml;
@@ -0,0 +1,274 @@
+#![cfg(feature = "properties")]
+use crate::Config;
+use cfgfifo::*;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use std::collections::BTreeMap;
+use std::io::{Seek, Write, read_to_string};
+use tempfile::{Builder, tempfile};
+
+static PROPERTIES: &str = indoc! {r"
+primitives.integer=42
+primitives.float=1.618
+primitives.boolean=true
+primitives.text=This is test text.\nThis is a new line.\n\tThis is an indented line.\nThis is a snowman with a goat: \u2603\uD83D\uDC10.
+primitives.some=17
+primitives.list=1, 2, 6, 15, 36
+primitives.dict.hello=goodbye
+primitives.dict.strange=charmed
+primitives.dict.up=down
+enums.color=green
+enums.msg.type=Response
+enums.msg.id=60069
+enums.msg.value=Foobar
+people.0.id=1
+people.0.given_name=Alice
+people.0.family_name=Alison
+people.1.id=2
+people.1.given_name=Bob
+people.1.family_name=Bobson
+people.2.id=3
+people.2.given_name=Charlie
+people.2.family_name=McCharles
+"};
+
+#[test]
+fn identify() {
+    assert_eq!(
+        Format::from_extension("properties"),
+        Some(Format::Properties)
+    );
+    assert_eq!(
+        Format::identify("path/to/app.properties").unwrap(),
+        Format::Properties
+    );
+    assert_eq!("properties".parse::<Format>().unwrap(), Format::Properties);
+}
+
+#[test]
+fn load_from_str() {
+    let r = Format::Properties.load_from_str::<Config>(PROPERTIES);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn dump_to_string() {
+    let r = Format::Properties.dump_to_string(&Config::get());
+    assert_eq!(r.unwrap(), PROPERTIES);
+}
+
+#[test]
+fn load_from_reader() {
+    let mut file = tempfile().unwrap();
+    file.write_all(PROPERTIES.as_bytes()).unwrap();
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let r = Format::Properties.load_from_reader::<_, Config>(file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn dump_to_writer() {
+    let mut file = tempfile().unwrap();
+    let r = Format::Properties.dump_to_writer(&file, &Config::get());
+    assert!(r.is_ok());
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, PROPERTIES);
+}
+
+#[test]
+fn load_from_file() {
+    let mut file = Builder::new().suffix(".properties").tempfile().unwrap();
+    file.write_all(PROPERTIES.as_bytes()).unwrap();
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let r = load::<Config, _>(file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn dump_to_file() {
+    let mut file = Builder::new().suffix(".properties").tempfile().unwrap();
+    let r = dump(&file, &Config::get());
+    assert!(r.is_ok());
+    file.flush().unwrap();
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(s, PROPERTIES);
+}
+
+#[test]
+fn sniff_load() {
+    let mut file = Builder::new().tempfile().unwrap();
+    file.write_all(PROPERTIES.as_bytes()).unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new().sniff(true);
+    let r = cfg.load::<Config, _>(&file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn load_syntax() {
+    let s = indoc! {r"
+        # A comment
+        ! Another comment
+        greeting = Hello, \
+                   world!
+        key\ with\ spaces:value
+        colon\:key value after a space
+        empty
+        snowman=☃
+        goat==🐐
+          indented    =    \  leading spaces
+        escapes=\t\r\n\f\q\\
+        greeting=Hello again
+    "};
+    let value = Format::Properties
+        .load_from_str::<BTreeMap<String, String>>(s)
+        .unwrap();
+    assert_eq!(
+        value,
+        BTreeMap::from([
+            (String::from("greeting"), String::from("Hello again")),
+            (String::from("key with spaces"), String::from("value")),
+            (
+                String::from("colon:key"),
+                String::from("value after a space")
+            ),
+            (String::from("empty"), String::new()),
+            (String::from("snowman"), String::from("\u{2603}")),
+            (String::from("goat"), String::from("=\u{1F410}")),
+            (String::from("indented"), String::from("  leading spaces")),
+            (String::from("escapes"), String::from("\t\r\n\x0Cq\\")),
+        ])
+    );
+}
+
+#[test]
+fn load_continuation() {
+    let s = "list = 1, 2, \\\n       6, 15, \\\n       36\nname = trailing\\\\\nnext = value\n";
+    let value = Format::Properties.load_from_str::<Value>(s).unwrap();
+    assert_eq!(
+        value,
+        Value::Map(Map::from([
+            (String::from("list"), Value::from("1, 2, 6, 15, 36")),
+            (String::from("name"), Value::from("trailing\\")),
+            (String::from("next"), Value::from("value")),
+        ]))
+    );
+}
+
+#[test]
+fn load_value() {
+    let s = "name=Example\nserver.port=8080\nserver.debug=true\n";
+    let value = Format::Properties.load_from_str::<Value>(s).unwrap();
+    let expected = Value::Map(Map::from([
+        (String::from("name"), Value::from("Example")),
+        (
+            String::from("server"),
+            Value::Map(Map::from([
+                (String::from("port"), Value::Integer(8080)),
+                (String::from("debug"), Value::Bool(true)),
+            ])),
+        ),
+    ]));
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn dump_escapes() {
+    let value = BTreeMap::from([
+        (String::from("key with spaces=:"), String::from(" leading")),
+        (String::from("#comment"), String::from("back\\slash\r")),
+        (
+            String::from("caf\u{E9}"),
+            String::from("#! \u{2603}\u{1F410}"),
+        ),
+    ]);
+    let s = Format::Properties.dump_to_string(&value).unwrap();
+    assert_eq!(
+        s,
+        indoc! {r"
+            \#comment=back\\slash\r
+            caf\u00E9=\#! \u2603\uD83D\uDC10
+            key\ with\ spaces\=\:=\ leading
+        "}
+    );
+    let back = Format::Properties
+        .load_from_str::<BTreeMap<String, String>>(&s)
+        .unwrap();
+    assert_eq!(back, value);
+}
+
+#[test]
+fn dump_dotted_key() {
+    let value = BTreeMap::from([(String::from("a.b"), 1)]);
+    let e = Format::Properties.dump_to_string(&value).unwrap_err();
+    assert_eq!(e.to_string(), "key \"a.b\" cannot contain \".\"");
+}
+
+#[test]
+fn conflicting_keys() {
+    let s = "server=localhost\nserver.port=8080\n";
+    let e = Format::Properties.load_from_str::<Value>(s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(2, 1)));
+    assert_eq!(
+        e.to_string(),
+        "key \"server.port\" conflicts with a key that has both a value and subkeys at line 2 column 1"
+    );
+}
+
+#[test]
+fn bad_unicode_escape() {
+    let s = "name=Example\ntext=snow\\u26x3man\n";
+    let e = Format::Properties.load_from_str::<Value>(s).unwrap_err();
+    assert_eq!(
+        e.render(s),
+        indoc! {r"
+            malformed \u escape
+             --> 2:10
+              |
+            1 | name=Example
+            2 | text=snow\u26x3man
+              |          ^
+        "}
+        .trim_end()
+    );
+}
+
+#[test]
+fn error_location() {
+    let s = PROPERTIES.replace("primitives.integer=42", "primitives.integer=3.14");
+    let e = Format::Properties.load_from_str::<Config>(&s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(1, 20)));
+    assert_eq!(
+        e.render(&s),
+        indoc! {r#"
+            primitives.integer: invalid value: string "3.14", expected u32
+             --> 1:20
+              |
+            1 | primitives.integer=3.14
+              |                    ^
+        "#}
+        .trim_end()
+    );
+}
+
+#[test]
+fn load_error_location() {
+    let mut file = Builder::new().suffix(".properties").tempfile().unwrap();
+    file.write_all(b"primitives.integer=42\nprimitives.float=true\n")
+        .unwrap();
+    file.flush().unwrap();
+    let e = load::<Config, _>(&file).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        format!(
+            "failed to deserialize {} as PROPERTIES",
+            file.path().display()
+        )
+    );
+    assert_eq!(e.location(), Some(Location::new(2, 18)));
+}
@@ -109,3 +109,74 @@ fn dump_to_file() {
     assert_eq!(s, TOML);
     assert!(s.ends_with("\"\n"));
 }
+
+#[test]
+fn sniff_load() {
+    let mut file = Builder::new().tempfile().unwrap();
+    file.write_all(TOML.as_bytes()).unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new().sniff(true);
+    let r = cfg.load::<Config, _>(&file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn update_file() {
+    let commented = format!("# Configuration for the test suite\n\n{TOML}");
+    let commented = commented.replace("integer = 42", "integer = 42  # The answer");
+    let mut file = Builder::new().suffix(".toml").tempfile().unwrap();
+    file.write_all(commented.as_bytes()).unwrap();
+    file.flush().unwrap();
+    let r = Cfgfifo::new().update(&file, |cfg: &mut Config| cfg.primitives.integer = 23);
+    let mut expected = Config::get();
+    expected.primitives.integer = 23;
+    assert_eq!(r.unwrap(), expected);
+    file.rewind().unwrap();
+    let s = read_to_string(file).unwrap();
+    assert_eq!(
+        s,
+        commented.replace("integer = 42  # The answer", "integer = 23  # The answer")
+    );
+}
+
+#[test]
+fn error_location() {
+    let s = TOML.replace("integer = 42", "integer = 3.14");
+    let e = Format::Toml.load_from_str::<Config>(&s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(2, 11)));
+    assert_eq!(
+        e.render(&s),
+        indoc! {"
+            primitives.integer: invalid type: floating point `3.14`, expected u32
+             --> 2:11
+              |
+            1 | [primitives]
+            2 | integer = 3.14
+              |           ^
+        "}
+        .trim_end()
+    );
+}
+
+#[test]
+fn load_error_location() {
+    let mut file = Builder::new().suffix(".toml").tempfile().unwrap();
+    file.write_all(b"[primitives]\ninteger = 42\nfloat = true\n")
+        .unwrap();
+    file.flush().unwrap();
+    let e = load::<Config, _>(&file).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        format!("failed to deserialize {} as TOML", file.path().display())
+    );
+    assert_eq!(e.location(), Some(Location::new(3, 9)));
+}
+
+#[test]
+fn syntax_error_location() {
+    let s = "[primitives]\ntext = \"☃🐐\" oops\n";
+    let e = Format::Toml.load_from_str::<Config>(s).unwrap_err();
+    assert!(matches!(e, DeserializeError::TomlParse { .. }));
+    assert_eq!(e.location(), Some(Location::new(2, 13)));
+    assert_eq!(e.span().map(|span| span.start), Some(30));
+}
@@ -97,3 +97,20 @@ fn dump_to_file() {
     assert_eq!(s, YAML);
     assert!(s.ends_with("McCharles\n"));
 }
+
+#[test]
+fn sniff_load() {
+    let mut file = Builder::new().tempfile().unwrap();
+    file.write_all(YAML.as_bytes()).unwrap();
+    file.flush().unwrap();
+    let cfg = Cfgfifo::new().sniff(true);
+    let r = cfg.load::<Config, _>(&file);
+    assert_eq!(r.unwrap(), Config::get());
+}
+
+#[test]
+fn error_location() {
+    let s = YAML.replace("integer: 42", "integer: 3.14");
+    let e = Format::Yaml.load_from_str::<Config>(&s).unwrap_err();
+    assert_eq!(e.location(), Some(Location::new(2, 12)));
+}
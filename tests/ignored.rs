@@ -0,0 +1,195 @@
+#![cfg(all(
+    feature = "json",
+    feature = "json5",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml"
+))]
+use cfgfifo::*;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tempfile::tempdir;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct Config {
+    name: String,
+    #[serde(default)]
+    enable_foo: bool,
+    server: Server,
+    #[serde(default)]
+    users: Vec<User>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct Server {
+    host: String,
+    #[serde(default)]
+    port: u16,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct User {
+    name: String,
+    #[serde(default)]
+    admin: bool,
+}
+
+impl Config {
+    fn get() -> Config {
+        Config {
+            name: String::from("app"),
+            enable_foo: false,
+            server: Server {
+                host: String::from("localhost"),
+                port: 0,
+            },
+            users: vec![
+                User {
+                    name: String::from("alice"),
+                    admin: false,
+                },
+                User {
+                    name: String::from("bob"),
+                    admin: false,
+                },
+            ],
+        }
+    }
+}
+
+#[rstest]
+#[case("config.json", indoc! {r#"
+    {
+      "name": "app",
+      "enalbe_foo": true,
+      "server": {"host": "localhost", "timeout": 30},
+      "users": [{"name": "alice"}, {"name": "bob", "admn": true}]
+    }
+"#})]
+#[case("config.json5", indoc! {"
+    {
+      name: 'app',
+      enalbe_foo: true,
+      server: {host: 'localhost', timeout: 30},
+      users: [{name: 'alice'}, {name: 'bob', admn: true}],
+    }
+"})]
+#[case("config.ron", indoc! {r#"
+    (
+        name: "app",
+        enalbe_foo: true,
+        server: (host: "localhost", timeout: 30),
+        users: [(name: "alice"), (name: "bob", admn: true)],
+    )
+"#})]
+#[case("config.toml", indoc! {r#"
+    name = "app"
+    enalbe_foo = true
+
+    [server]
+    host = "localhost"
+    timeout = 30
+
+    [[users]]
+    name = "alice"
+
+    [[users]]
+    name = "bob"
+    admn = true
+"#})]
+#[case("config.yaml", indoc! {"
+    name: app
+    enalbe_foo: true
+    server:
+      host: localhost
+      timeout: 30
+    users:
+      - name: alice
+      - name: bob
+        admn: true
+"})]
+fn load_with_ignored(#[case] filename: &str, #[case] content: &str) {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join(filename);
+    fs::write(&path, content).unwrap();
+    let (cfg, ignored) = Cfgfifo::new()
+        .load_with_ignored::<Config, _>(&path)
+        .unwrap();
+    assert_eq!(cfg, Config::get());
+    assert_eq!(ignored, ["enalbe_foo", "server.timeout", "users[1].admn"]);
+    let r = Cfgfifo::new().strict(true).load::<Config, _>(&path);
+    let Err(LoadError::Deserialize { source, .. }) = r else {
+        panic!("load() did not fail with Deserialize error: {r:?}");
+    };
+    assert_eq!(
+        source.to_string(),
+        "unknown keys: enalbe_foo, server.timeout, users[1].admn"
+    );
+}
+
+#[test]
+fn nothing_ignored() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.json");
+    dump(&path, &Config::get()).unwrap();
+    let (cfg, ignored) = Cfgfifo::new()
+        .strict(true)
+        .load_with_ignored::<Config, _>(&path)
+        .unwrap();
+    assert_eq!(cfg, Config::get());
+    assert!(ignored.is_empty());
+}
+
+#[test]
+fn strict_error_message() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    fs::write(
+        &path,
+        "name = \"app\"\nenalbe_foo = true\n\n[server]\nhost = \"localhost\"\n",
+    )
+    .unwrap();
+    let e = Cfgfifo::new()
+        .strict(true)
+        .load::<Config, _>(&path)
+        .unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        format!("failed to deserialize {} as TOML", path.display())
+    );
+    assert_eq!(e.location(), None);
+}
+
+#[test]
+fn ignored_env_vars() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.yaml");
+    fs::write(&path, "name: app\nserver:\n  host: localhost\n").unwrap();
+    let env = EnvSource::new("APP_").vars([("APP_SERVER__PROT", "80"), ("APP_ENABLE_FOO", "true")]);
+    let (cfg, ignored) = Cfgfifo::new()
+        .env(env)
+        .load_with_ignored::<Config, _>(&path)
+        .unwrap();
+    assert!(cfg.enable_foo);
+    assert_eq!(cfg.server.port, 0);
+    assert_eq!(ignored, ["server.prot"]);
+}
+
+#[test]
+fn strict_update_leaves_file() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    let content = "name = \"app\"\nenalbe_foo = true\n\n[server]\nhost = \"localhost\"\n";
+    fs::write(&path, content).unwrap();
+    let r = Cfgfifo::new()
+        .strict(true)
+        .update(&path, |cfg: &mut Config| cfg.server.port = 80);
+    assert!(
+        matches!(r, Err(UpdateError::Load(LoadError::Deserialize { .. }))),
+        "update() did not fail with Deserialize error: {r:?}"
+    );
+    assert_eq!(fs::read_to_string(&path).unwrap(), content);
+}
@@ -0,0 +1,205 @@
+#![cfg(all(feature = "json", feature = "toml", feature = "yaml"))]
+use cfgfifo::*;
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use std::fs;
+use tempfile::tempdir;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct Config {
+    name: String,
+    #[serde(default)]
+    debug: bool,
+    server: Server,
+    #[serde(default)]
+    plugins: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+#[test]
+fn load_layers() {
+    let tmpdir = tempdir().unwrap();
+    let system = tmpdir.path().join("system.toml");
+    fs::write(
+        &system,
+        concat!(
+            "name = \"system\"\n",
+            "plugins = [\"core\"]\n",
+            "\n",
+            "[server]\n",
+            "host = \"0.0.0.0\"\n",
+            "port = 80\n",
+        ),
+    )
+    .unwrap();
+    let user = tmpdir.path().join("user.yaml");
+    fs::write(&user, "server:\n  port: 8080\nplugins: [extra]\n").unwrap();
+    let project = tmpdir.path().join("project.json");
+    fs::write(&project, r#"{"debug": true}"#).unwrap();
+    let layers = Layers::new()
+        .file(&system)
+        .optional_file(&user)
+        .optional_file(tmpdir.path().join("missing.toml"))
+        .file(&project);
+    assert_eq!(
+        layers.load::<Config>().unwrap(),
+        Config {
+            name: String::from("system"),
+            debug: true,
+            server: Server {
+                host: String::from("0.0.0.0"),
+                port: 8080,
+            },
+            plugins: vec![String::from("extra")],
+        }
+    );
+    assert_eq!(
+        layers
+            .arrays(ArrayMerge::Append)
+            .load::<Config>()
+            .unwrap()
+            .plugins,
+        [String::from("core"), String::from("extra")]
+    );
+}
+
+#[test]
+fn null_layer() {
+    let tmpdir = tempdir().unwrap();
+    let base = tmpdir.path().join("base.json");
+    fs::write(
+        &base,
+        r#"{"name": "base", "server": {"host": "localhost", "port": 1}}"#,
+    )
+    .unwrap();
+    let empty = tmpdir.path().join("empty.yaml");
+    fs::write(&empty, "").unwrap();
+    let cfg = Layers::new()
+        .file(&base)
+        .file(&empty)
+        .load::<Config>()
+        .unwrap();
+    assert_eq!(cfg.name, "base");
+}
+
+#[test]
+fn remove_nulls() {
+    let tmpdir = tempdir().unwrap();
+    let base = tmpdir.path().join("base.json");
+    fs::write(
+        &base,
+        r#"{"name": "base", "debug": true, "server": {"host": "localhost", "port": 1}}"#,
+    )
+    .unwrap();
+    let over = tmpdir.path().join("over.yaml");
+    fs::write(&over, "debug: null\n").unwrap();
+    let layers = Layers::new().file(&base).file(&over);
+    assert!(layers.load::<Config>().is_err());
+    let cfg = layers.nulls(NullMerge::Remove).load::<Config>().unwrap();
+    assert!(!cfg.debug);
+}
+
+#[test]
+fn missing_required_layer() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("missing.toml");
+    let r = Layers::new().file(&path).load::<Config>();
+    let Err(LayerError::Load {
+        path: errpath,
+        source,
+    }) = r
+    else {
+        panic!("load() did not fail with Load error: {r:?}");
+    };
+    assert_eq!(errpath, path);
+    assert!(matches!(*source, LoadError::Open { .. }));
+}
+
+#[test]
+fn deserialize_error() {
+    let tmpdir = tempdir().unwrap();
+    let base = tmpdir.path().join("base.toml");
+    fs::write(
+        &base,
+        "name = \"base\"\n\n[server]\nhost = \"localhost\"\nport = 1\n",
+    )
+    .unwrap();
+    let over = tmpdir.path().join("over.json");
+    fs::write(&over, r#"{"server": {"port": "high"}}"#).unwrap();
+    let r = Layers::new().file(&base).file(&over).load::<Config>();
+    let Err(LayerError::Deserialize(e)) = r else {
+        panic!("load() did not fail with Deserialize error: {r:?}");
+    };
+    assert_eq!(
+        e.to_string(),
+        "server.port: invalid type: string \"high\", expected u16"
+    );
+}
+
+#[test]
+fn no_layers() {
+    #[derive(Debug, Default, Deserialize, Eq, PartialEq)]
+    struct Defaults {
+        #[serde(default)]
+        debug: bool,
+    }
+
+    let cfg = Layers::new().load::<Defaults>().unwrap();
+    assert_eq!(cfg, Defaults::default());
+}
+
+#[test]
+fn provenance() {
+    let tmpdir = tempdir().unwrap();
+    let system = tmpdir.path().join("system.toml");
+    fs::write(
+        &system,
+        concat!(
+            "name = \"system\"\n",
+            "plugins = [\"core\"]\n",
+            "\n",
+            "[server]\n",
+            "host = \"0.0.0.0\"\n",
+            "port = 80\n",
+        ),
+    )
+    .unwrap();
+    let user = tmpdir.path().join("user.yaml");
+    fs::write(&user, "server:\n  port: 8080\nplugins: [extra]\n").unwrap();
+    let project = tmpdir.path().join("project.json");
+    fs::write(&project, "{\n  \"debug\": true\n}\n").unwrap();
+    let (cfg, provenance) = Layers::new()
+        .file(&system)
+        .file(&user)
+        .file(&project)
+        .arrays(ArrayMerge::Append)
+        .load_with_provenance::<Config>()
+        .unwrap();
+    assert_eq!(cfg.server.port, 8080);
+    let origins = provenance
+        .iter()
+        .map(|(path, origin)| (path, origin.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        origins,
+        [
+            ("name", format!("{}:1:8", system.display())),
+            ("plugins[0]", format!("{}:2:12", system.display())),
+            ("plugins[1]", format!("{}:3:11", user.display())),
+            ("server.host", format!("{}:5:8", system.display())),
+            ("server.port", format!("{}:2:9", user.display())),
+            ("debug", format!("{}:2:12", project.display())),
+        ]
+    );
+    let Some(Origin::File { path, format, .. }) = provenance.get("server.port") else {
+        panic!("server.port should have a file origin");
+    };
+    assert_eq!(path, &user);
+    assert_eq!(format, &Format::Yaml);
+    assert!(provenance.get("server").is_none());
+}
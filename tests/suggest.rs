@@ -0,0 +1,88 @@
+#![cfg(all(
+    feature = "json",
+    feature = "json5",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml"
+))]
+use cfgfifo::*;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use serde::Deserialize;
+use std::fs;
+use tempfile::tempdir;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct AppConfig {
+    #[serde(default)]
+    enable_foo: bool,
+    #[serde(default)]
+    bar_type: BarType,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+enum BarType {
+    #[default]
+    Open,
+    Closed,
+    Clopen,
+}
+
+#[rstest]
+#[case(Format::Json, r#"{"enalbe_foo": true}"#, "enable_foo")]
+#[case(Format::Json, r#"{"bar_type": "Clopn"}"#, "Clopen")]
+#[case(Format::Json5, "{enalbe_foo: true}", "enable_foo")]
+#[case(Format::Json5, "{bar_type: 'Clopn'}", "Clopen")]
+#[case(Format::Ron, "(enalbe_foo: true)", "enable_foo")]
+#[case(Format::Ron, "(bar_type: Clopn)", "Clopen")]
+#[case(Format::Toml, "enalbe_foo = true\n", "enable_foo")]
+#[case(Format::Toml, "bar_type = \"Clopn\"\n", "Clopen")]
+#[case(Format::Yaml, "enalbe_foo: true\n", "enable_foo")]
+#[case(Format::Yaml, "bar_type: Clopn\n", "Clopen")]
+fn suggestion(#[case] fmt: Format, #[case] input: &str, #[case] name: &str) {
+    let e = fmt.load_from_str::<AppConfig>(input).unwrap_err();
+    assert_eq!(e.suggestion().as_deref(), Some(name));
+    assert!(
+        e.render(input)
+            .ends_with(&format!("\nhelp: did you mean `{name}`?")),
+        "{fmt}: suggestion not rendered"
+    );
+}
+
+#[rstest]
+#[case(Format::Json, r#"{"flavor": "vanilla"}"#)]
+#[case(Format::Ron, "(bar_type: Ajar)")]
+#[case(Format::Yaml, "enable_foo: maybe\n")]
+fn no_suggestion(#[case] fmt: Format, #[case] input: &str) {
+    let e = fmt.load_from_str::<AppConfig>(input).unwrap_err();
+    assert_eq!(e.suggestion(), None);
+    assert!(!e.render(input).contains("help:"));
+}
+
+#[test]
+fn render_with_suggestion() {
+    let input = "enable_foo = true\nbar_type = \"Clopn\"\n";
+    let e = Format::Toml.load_from_str::<AppConfig>(input).unwrap_err();
+    assert_eq!(
+        e.render(input),
+        indoc! {"
+            bar_type: unknown variant `Clopn`, expected one of `Open`, `Closed`, `Clopen`
+             --> 2:12
+              |
+            1 | enable_foo = true
+            2 | bar_type = \"Clopn\"
+              |            ^
+            help: did you mean `Clopen`?"}
+    );
+}
+
+#[test]
+fn load_error_suggestion() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    fs::write(&path, "bar_type = \"closed\"\n").unwrap();
+    let e = load::<AppConfig, _>(&path).unwrap_err();
+    assert_eq!(e.suggestion().as_deref(), Some("Closed"));
+}
@@ -0,0 +1,422 @@
+#![cfg(all(
+    feature = "json",
+    feature = "json5",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml"
+))]
+use cfgfifo::*;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use std::fs;
+use tempfile::tempdir;
+
+fn sample(fmt: Format) -> &'static str {
+    match fmt {
+        Format::Json => indoc! {r#"
+            {
+              "name": "app",
+              "ratio": 0.5,
+              "server": {"host": "localhost", "port": 8080},
+              "tags": ["a", "b"],
+              "users": [{"name": "alice", "admin": true}]
+            }
+        "#},
+        Format::Json5 => indoc! {"
+            {
+              name: 'app',
+              ratio: 0.5,
+              server: {host: 'localhost', port: 8080},
+              tags: ['a', 'b'],
+              users: [{name: 'alice', admin: true}],
+            }
+        "},
+        Format::Ron => indoc! {r#"
+            (
+                name: "app",
+                ratio: 0.5,
+                server: (host: "localhost", port: 8080),
+                tags: ["a", "b"],
+                users: [(name: "alice", admin: true)],
+            )
+        "#},
+        Format::Toml => indoc! {r#"
+            name = "app"
+            ratio = 0.5
+            tags = ["a", "b"]
+
+            [server]
+            host = "localhost"
+            port = 8080
+
+            [[users]]
+            name = "alice"
+            admin = true
+        "#},
+        Format::Yaml => indoc! {"
+            name: app
+            ratio: 0.5
+            server:
+              host: localhost
+              port: 8080
+            tags: [a, b]
+            users:
+              - name: alice
+                admin: true
+        "},
+        _ => unreachable!(),
+    }
+}
+
+#[rstest]
+fn transcode_all(
+    #[values(Format::Json, Format::Json5, Format::Ron, Format::Toml, Format::Yaml)] from: Format,
+    #[values(Format::Json, Format::Json5, Format::Ron, Format::Toml, Format::Yaml)] to: Format,
+) {
+    let output = Format::transcode(from, to, sample(from)).unwrap();
+    let expected = Format::Yaml
+        .load_from_str::<Value>(sample(Format::Yaml))
+        .unwrap();
+    let Value::Map(mut expected) = expected else {
+        panic!("sample is not a map");
+    };
+    let Value::Map(mut got) = to.load_from_str::<Value>(&output).unwrap() else {
+        panic!("output is not a map");
+    };
+    // TOML sorts plain keys before tables, so only compare contents
+    expected.sort_keys();
+    got.sort_keys();
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn transcode_preserves_order() {
+    let yaml = "zeta: 1\nalpha:\n  y: true\n  x: false\nmid: [1, two]\n";
+    assert_eq!(
+        Format::transcode(Format::Yaml, Format::Json, yaml).unwrap(),
+        indoc! {r#"
+            {
+              "zeta": 1,
+              "alpha": {
+                "y": true,
+                "x": false
+              },
+              "mid": [
+                1,
+                "two"
+              ]
+            }"#}
+    );
+}
+
+#[test]
+fn transcode_toml_datetimes() {
+    let toml = "created = 1979-05-27T07:32:00Z\nday = 1979-05-27\n";
+    assert_eq!(
+        Format::transcode(Format::Toml, Format::Toml, toml).unwrap(),
+        toml
+    );
+    assert_eq!(
+        Format::transcode(Format::Toml, Format::Yaml, toml).unwrap(),
+        "created: 1979-05-27T07:32:00Z\nday: 1979-05-27\n"
+    );
+}
+
+#[test]
+fn transcode_yaml_tags_and_keys() {
+    let yaml = "shape: !Circle {radius: 2}\n1: one\ntrue: yes\n";
+    assert_eq!(
+        Format::transcode(Format::Yaml, Format::Toml, yaml).unwrap(),
+        indoc! {r#"
+            1 = "one"
+            true = "yes"
+
+            [shape.Circle]
+            radius = 2
+        "#}
+    );
+}
+
+#[test]
+fn transcode_ron_options() {
+    let ron = "(a: Some(1), b: None, c: 'x')";
+    assert_eq!(
+        Format::transcode(Format::Ron, Format::Yaml, ron).unwrap(),
+        "a: 1\nb: null\nc: 'x'\n"
+    );
+    assert_eq!(
+        Format::transcode(Format::Ron, Format::Ron, ron).unwrap(),
+        "{\n    \"a\": Some(1),\n    \"b\": None,\n    \"c\": 'x',\n}"
+    );
+}
+
+#[cfg(feature = "ini")]
+#[rstest]
+#[case(Format::Json, r#"{"a": "1", "b": "two"}"#, "a = 1\nb = two\n")]
+#[case(Format::Yaml, "s:\n  a: 1\n  b: [x, y]\n", "[s]\na = 1\nb = x, y\n")]
+fn transcode_to_ini(#[case] from: Format, #[case] input: &str, #[case] output: &str) {
+    assert_eq!(Format::transcode(from, Format::Ini, input).unwrap(), output);
+}
+
+#[cfg(feature = "properties")]
+#[rstest]
+#[case(Format::Json, r#"{"a": [1, 2], "b": {"c": "d"}}"#, "a=1, 2\nb.c=d\n")]
+#[case(Format::Json, r#"{"e": [{"f": 1}]}"#, "e.0.f=1\n")]
+#[case(Format::Json, r#"{"a": ["w", "x,y"]}"#, "a.0=w\na.1=x,y\n")]
+#[case(Format::Yaml, "a:\n- b: 1\n", "a.0.b=1\n")]
+fn transcode_to_properties(#[case] from: Format, #[case] input: &str, #[case] output: &str) {
+    assert_eq!(
+        Format::transcode(from, Format::Properties, input).unwrap(),
+        output
+    );
+}
+
+#[cfg(feature = "dotenv")]
+#[rstest]
+#[case(
+    Format::Json,
+    r#"{"A": "1", "B": "two words"}"#,
+    "A=1\nB=\"two words\"\n"
+)]
+#[case(Format::Yaml, "HOSTS: [a, b]\nPORT: 80\n", "HOSTS=\"a, b\"\nPORT=80\n")]
+fn transcode_to_dotenv(#[case] from: Format, #[case] input: &str, #[case] output: &str) {
+    assert_eq!(
+        Format::transcode(from, Format::Dotenv, input).unwrap(),
+        output
+    );
+}
+
+#[cfg(feature = "hcl")]
+#[rstest]
+#[case(
+    Format::Json,
+    r#"{"a": "1", "b": [true]}"#,
+    "a = \"1\"\nb = [\n  true\n]\n"
+)]
+#[case(
+    Format::Yaml,
+    "s:\n  a: 1\n  e:\n  - f: 1\n",
+    "s {\n  a = 1\n  e = [\n    {\n      f = 1\n    }\n  ]\n}\n"
+)]
+fn transcode_to_hcl(#[case] from: Format, #[case] input: &str, #[case] output: &str) {
+    assert_eq!(Format::transcode(from, Format::Hcl, input).unwrap(), output);
+}
+
+#[cfg(feature = "kdl")]
+#[rstest]
+#[case(Format::Json, r#"{"a": [1, 2], "b": null}"#, "a 1 2\nb #null\n")]
+#[case(
+    Format::Yaml,
+    "s:\n  e:\n  - f: 1\n  - [x, y]\n",
+    "s {\n    e {\n        - f=1\n        - x y\n    }\n}\n"
+)]
+fn transcode_to_kdl(#[case] from: Format, #[case] input: &str, #[case] output: &str) {
+    assert_eq!(Format::transcode(from, Format::Kdl, input).unwrap(), output);
+}
+
+#[rstest]
+#[case(
+    Format::Json,
+    Format::Toml,
+    "[1, 2]",
+    IncompatibilityKind::NonTableRoot,
+    "."
+)]
+#[case(
+    Format::Yaml,
+    Format::Toml,
+    "hello\n",
+    IncompatibilityKind::NonTableRoot,
+    "."
+)]
+#[case(
+    Format::Json,
+    Format::Toml,
+    r#"{"a": {"b": null}}"#,
+    IncompatibilityKind::Null,
+    "a.b"
+)]
+#[case(
+    Format::Yaml,
+    Format::Toml,
+    "a: [1, ~]\n",
+    IncompatibilityKind::Null,
+    "a[1]"
+)]
+#[case(Format::Ron, Format::Toml, "(a: ())", IncompatibilityKind::Null, "a")]
+#[case(
+    Format::Json,
+    Format::Toml,
+    r#"{"big": 18446744073709551615}"#,
+    IncompatibilityKind::Integer,
+    "big"
+)]
+#[case(
+    Format::Yaml,
+    Format::Json,
+    "big: 170141183460469231731687303715884105727\n",
+    IncompatibilityKind::Integer,
+    "big"
+)]
+#[case(
+    Format::Ron,
+    Format::Yaml,
+    "(a: [b\"hi\"])",
+    IncompatibilityKind::Bytes,
+    "a[0]"
+)]
+#[case(
+    Format::Toml,
+    Format::Json,
+    "[t]\nx = nan\n",
+    IncompatibilityKind::NonFiniteFloat,
+    "t.x"
+)]
+#[case(
+    Format::Json5,
+    Format::Json,
+    "{x: Infinity}",
+    IncompatibilityKind::NonFiniteFloat,
+    "x"
+)]
+fn transcode_incompatible(
+    #[case] from: Format,
+    #[case] to: Format,
+    #[case] input: &str,
+    #[case] kind: IncompatibilityKind,
+    #[case] path: &str,
+) {
+    let e = Format::transcode(from, to, input).unwrap_err();
+    let TranscodeError::Incompatible(inc) = e else {
+        panic!("expected Incompatible error, got {e:?}");
+    };
+    assert_eq!(
+        inc,
+        Incompatibility {
+            format: to,
+            kind,
+            path: path.to_owned(),
+        }
+    );
+}
+
+#[test]
+fn incompatibility_display() {
+    let e = Format::transcode(Format::Json, Format::Toml, r#"{"a": [null]}"#).unwrap_err();
+    assert_eq!(e.to_string(), "TOML cannot represent null values (at a[0])");
+    let e = Format::transcode(Format::Json, Format::Toml, "true").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "TOML cannot represent a top-level value that is not a table"
+    );
+}
+
+#[rstest]
+#[case(Format::Json, "{\"a\": [1, {\"b\": tru}]}", "a[1].b", 1, 20)]
+#[case(Format::Json, "{\"a\": 1} x", ".", 1, 10)]
+#[case(Format::Yaml, "a:\n  - 1\n  - [2\n", "a[1]", 4, 1)]
+#[case(Format::Toml, "a = 1\nb = \n", ".", 2, 5)]
+#[case(Format::Ron, "(a: [1, 2)", "a", 1, 10)]
+fn transcode_invalid_input(
+    #[case] from: Format,
+    #[case] input: &str,
+    #[case] path: &str,
+    #[case] line: usize,
+    #[case] column: usize,
+) {
+    for to in [Format::Json, Format::Toml, Format::Yaml] {
+        let e = Format::transcode(from, to, input).unwrap_err();
+        let TranscodeError::Deserialize { format, source } = e else {
+            panic!("expected Deserialize error, got {e:?}");
+        };
+        assert_eq!(format, from);
+        assert_eq!(
+            source
+                .path()
+                .map_or_else(|| String::from("."), ToString::to_string),
+            path,
+            "{from} -> {to}"
+        );
+        assert_eq!(
+            source.location(),
+            Some(Location::new(line, column)),
+            "{from} -> {to}"
+        );
+    }
+}
+
+#[test]
+fn convert() {
+    let tmpdir = tempdir().unwrap();
+    let src = tmpdir.path().join("config.yaml");
+    let dst = tmpdir.path().join("config.json");
+    fs::write(&src, "name: app\nports: [80, 443]\n").unwrap();
+    Cfgfifo::new().convert(&src, &dst).unwrap();
+    assert_eq!(
+        fs::read_to_string(&dst).unwrap(),
+        "{\n  \"name\": \"app\",\n  \"ports\": [\n    80,\n    443\n  ]\n}\n"
+    );
+}
+
+#[cfg(feature = "properties")]
+#[test]
+fn convert_to_properties() {
+    let tmpdir = tempdir().unwrap();
+    let src = tmpdir.path().join("config.json");
+    let dst = tmpdir.path().join("config.properties");
+    fs::write(&src, r#"{"users": [{"name": "alice"}, {"name": "bob"}]}"#).unwrap();
+    Cfgfifo::new().convert(&src, &dst).unwrap();
+    assert_eq!(
+        fs::read_to_string(&dst).unwrap(),
+        "users.0.name=alice\nusers.1.name=bob\n"
+    );
+}
+
+#[test]
+fn convert_incompatible() {
+    let tmpdir = tempdir().unwrap();
+    let src = tmpdir.path().join("config.json");
+    let dst = tmpdir.path().join("config.toml");
+    fs::write(&src, r#"{"name": null}"#).unwrap();
+    let e = Cfgfifo::new().convert(&src, &dst).unwrap_err();
+    assert!(matches!(
+        e,
+        ConvertError::Incompatible {
+            source: Incompatibility {
+                kind: IncompatibilityKind::Null,
+                ..
+            },
+            ..
+        }
+    ));
+    assert!(!dst.exists());
+}
+
+#[test]
+fn convert_invalid_source() {
+    let tmpdir = tempdir().unwrap();
+    let src = tmpdir.path().join("config.json");
+    let dst = tmpdir.path().join("config.toml");
+    fs::write(&src, r#"{"name": }"#).unwrap();
+    let e = Cfgfifo::new().convert(&src, &dst).unwrap_err();
+    let ConvertError::Load(LoadError::Deserialize { path, format, .. }) = e else {
+        panic!("expected Load(Deserialize) error, got {e:?}");
+    };
+    assert_eq!(path, src);
+    assert_eq!(format, Format::Json);
+    assert!(!dst.exists());
+}
+
+#[test]
+fn convert_unknown_destination() {
+    let tmpdir = tempdir().unwrap();
+    let src = tmpdir.path().join("config.json");
+    let dst = tmpdir.path().join("config.xyz");
+    fs::write(&src, "{}").unwrap();
+    let e = Cfgfifo::new().convert(&src, &dst).unwrap_err();
+    assert!(matches!(
+        e,
+        ConvertError::Dump(DumpError::Identify { ref path, .. }) if path == &dst
+    ));
+}
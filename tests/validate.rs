@@ -0,0 +1,261 @@
+#![cfg(all(
+    feature = "json",
+    feature = "json5",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml"
+))]
+use cfgfifo::*;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use serde::Deserialize;
+use std::fs;
+use tempfile::tempdir;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct Config {
+    name: String,
+    port: u16,
+    mode: Mode,
+    server: Server,
+    users: Vec<User>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+enum Mode {
+    Fast,
+    Slow,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct Server {
+    host: String,
+    timeout: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct User {
+    name: String,
+    admin: bool,
+}
+
+/// Return the path of each error in a `LoadError::Invalid`, checking that
+/// every error has a location
+fn error_paths(r: Result<Config, LoadError>) -> Vec<String> {
+    let Err(LoadError::Invalid { errors, .. }) = r else {
+        panic!("validate() did not fail with Invalid error: {r:?}");
+    };
+    errors
+        .iter()
+        .map(|e| {
+            assert!(e.location().is_some(), "error without location: {e}");
+            e.path().map(ToString::to_string).unwrap_or_default()
+        })
+        .collect()
+}
+
+#[rstest]
+#[case("config.json", indoc! {r#"
+    {
+      "name": "app",
+      "port": "eighty",
+      "mode": "Medium",
+      "server": {"timeout": -1},
+      "users": [{"name": "alice", "admn": true}, {"name": "bob", "admin": false}]
+    }
+"#})]
+#[case("config.json5", indoc! {"
+    {
+      name: 'app',
+      port: 'eighty',
+      mode: 'Medium',
+      server: {timeout: -1},
+      users: [{name: 'alice', admn: true}, {name: 'bob', admin: false}],
+    }
+"})]
+#[case("config.ron", indoc! {r#"
+    (
+        name: "app",
+        port: "eighty",
+        mode: Medium,
+        server: (timeout: -1),
+        users: [(name: "alice", admn: true), (name: "bob", admin: false)],
+    )
+"#})]
+#[case("config.toml", indoc! {r#"
+    name = "app"
+    port = "eighty"
+    mode = "Medium"
+
+    [server]
+    timeout = -1
+
+    [[users]]
+    name = "alice"
+    admn = true
+
+    [[users]]
+    name = "bob"
+    admin = false
+"#})]
+#[case("config.yaml", indoc! {"
+    name: app
+    port: eighty
+    mode: Medium
+    server:
+      timeout: -1
+    users:
+      - name: alice
+        admn: true
+      - name: bob
+        admin: false
+"})]
+fn validate_collects_errors(#[case] filename: &str, #[case] content: &str) {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join(filename);
+    fs::write(&path, content).unwrap();
+    let mut paths = error_paths(Cfgfifo::new().validate::<Config, _>(&path));
+    // Formats differ in the order in which they visit keys
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "mode",
+            "port",
+            "server",
+            "server.timeout",
+            "users[0]",
+            "users[0].admn"
+        ]
+    );
+}
+
+#[test]
+fn validate_error_details() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.json");
+    fs::write(
+        &path,
+        indoc! {r#"
+            {
+              "name": "app",
+              "port": "eighty",
+              "mode": "Fast",
+              "server": {"host": "localhost"},
+              "users": [{"name": "alice", "admin": "yes"}, {"admin": true}]
+            }
+        "#},
+    )
+    .unwrap();
+    let e = Cfgfifo::new().validate::<Config, _>(&path).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        format!(
+            "failed to deserialize {} as JSON: found 4 errors",
+            path.display()
+        )
+    );
+    let LoadError::Invalid { errors, .. } = e else {
+        panic!("validate() did not fail with Invalid error: {e:?}");
+    };
+    let details = errors
+        .iter()
+        .map(|e| (e.to_string(), e.location().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        details,
+        [
+            (
+                String::from(
+                    "port: invalid type: string \"eighty\", expected u16 at line 3 column 18"
+                ),
+                Location::new(3, 18)
+            ),
+            (
+                String::from("server: missing field `timeout` at line 5 column 33"),
+                Location::new(5, 33)
+            ),
+            (
+                String::from(
+                    "users[0].admin: invalid type: string \"yes\", expected a boolean at line 6 column 44"
+                ),
+                Location::new(6, 44)
+            ),
+            (
+                String::from("users[1]: missing field `name` at line 6 column 62"),
+                Location::new(6, 62)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn validate_valid() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.yaml");
+    fs::write(
+        &path,
+        indoc! {"
+            name: app
+            port: 80
+            mode: Slow
+            server:
+              host: localhost
+              timeout: 30
+            users: []
+        "},
+    )
+    .unwrap();
+    let cfg = Cfgfifo::new().validate::<Config, _>(&path).unwrap();
+    assert_eq!(cfg, load::<Config, _>(&path).unwrap());
+}
+
+#[test]
+fn validate_stops_at_syntax_error() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.json");
+    fs::write(&path, r#"{"name": "app", "port": true, "mode": Fast}"#).unwrap();
+    let r = Cfgfifo::new().validate::<Config, _>(&path);
+    assert!(
+        matches!(r, Err(LoadError::Deserialize { .. })),
+        "validate() did not fail with Deserialize error: {r:?}"
+    );
+}
+
+#[test]
+fn validate_strict() {
+    let tmpdir = tempdir().unwrap();
+    let path = tmpdir.path().join("config.toml");
+    fs::write(
+        &path,
+        indoc! {r#"
+            name = "app"
+            port = 80
+            mode = "Fast"
+            users = []
+
+            [server]
+            host = "localhost"
+            timeout = "30"
+            retries = 3
+        "#},
+    )
+    .unwrap();
+    let r = Cfgfifo::new().strict(true).validate::<Config, _>(&path);
+    let Err(LoadError::Invalid { errors, .. }) = r else {
+        panic!("validate() did not fail with Invalid error: {r:?}");
+    };
+    let msgs = errors
+        .iter()
+        .map(|e| e.to_string().lines().last().unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        msgs,
+        [
+            "invalid type: string \"30\", expected u32",
+            "unknown keys: server.retries"
+        ]
+    );
+}
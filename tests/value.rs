@@ -0,0 +1,264 @@
+#![cfg(all(
+    feature = "json",
+    feature = "json5",
+    feature = "ron",
+    feature = "toml",
+    feature = "yaml"
+))]
+use cfgfifo::*;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use serde::Deserialize;
+
+fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::Map(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v))
+            .collect(),
+    )
+}
+
+fn expected() -> Value {
+    map([
+        ("name", Value::from("app")),
+        ("debug", Value::from(true)),
+        ("ratio", Value::from(0.5)),
+        (
+            "server",
+            map([
+                ("host", Value::from("localhost")),
+                ("port", Value::from(8080)),
+            ]),
+        ),
+        (
+            "users",
+            Value::Seq(vec![
+                map([("name", Value::from("alice")), ("admin", Value::from(true))]),
+                map([("name", Value::from("bob")), ("admin", Value::from(false))]),
+            ]),
+        ),
+    ])
+}
+
+#[rstest]
+#[case(Format::Json, indoc! {r#"
+    {
+      "name": "app",
+      "debug": true,
+      "ratio": 0.5,
+      "server": {"host": "localhost", "port": 8080},
+      "users": [{"name": "alice", "admin": true}, {"name": "bob", "admin": false}]
+    }
+"#})]
+#[case(Format::Json5, indoc! {"
+    {
+      name: 'app',
+      debug: true,
+      ratio: 0.5,
+      server: {host: 'localhost', port: 8080},
+      users: [{name: 'alice', admin: true}, {name: 'bob', admin: false}],
+    }
+"})]
+#[case(Format::Ron, indoc! {r#"
+    (
+        name: "app",
+        debug: true,
+        ratio: 0.5,
+        server: (host: "localhost", port: 8080),
+        users: [(name: "alice", admin: true), (name: "bob", admin: false)],
+    )
+"#})]
+#[case(Format::Toml, indoc! {r#"
+    name = "app"
+    debug = true
+    ratio = 0.5
+
+    [server]
+    host = "localhost"
+    port = 8080
+
+    [[users]]
+    name = "alice"
+    admin = true
+
+    [[users]]
+    name = "bob"
+    admin = false
+"#})]
+#[case(Format::Yaml, indoc! {"
+    name: app
+    debug: true
+    ratio: 0.5
+    server:
+      host: localhost
+      port: 8080
+    users:
+      - name: alice
+        admin: true
+      - name: bob
+        admin: false
+"})]
+fn load_and_dump(#[case] fmt: Format, #[case] input: &str) {
+    let value = fmt.load_from_str::<Value>(input).unwrap();
+    assert_eq!(value, expected());
+    assert_eq!(
+        value.get("server").and_then(|v| v.get("port")),
+        Some(&Value::Integer(8080))
+    );
+    let s = fmt.dump_to_string(&value).unwrap();
+    assert_eq!(fmt.load_from_str::<Value>(&s).unwrap(), value);
+}
+
+#[test]
+fn toml_datetimes() {
+    let value = Format::Toml
+        .load_from_str::<Value>("created = 1979-05-27T07:32:00Z\nday = 1979-05-27\n")
+        .unwrap();
+    assert_eq!(
+        value,
+        map([
+            (
+                "created",
+                Value::Datetime(Datetime::new("1979-05-27T07:32:00Z"))
+            ),
+            ("day", Value::Datetime(Datetime::new("1979-05-27"))),
+        ])
+    );
+    assert_eq!(
+        Format::Toml.dump_to_string(&value).unwrap(),
+        "created = 1979-05-27T07:32:00Z\nday = 1979-05-27\n"
+    );
+    assert_eq!(
+        Format::Yaml.dump_to_string(&value).unwrap(),
+        "created: 1979-05-27T07:32:00Z\nday: 1979-05-27\n"
+    );
+    assert_eq!(
+        Format::Json
+            .load_from_str::<Value>(&Format::Json.dump_to_string(&value).unwrap())
+            .unwrap(),
+        map([
+            ("created", Value::from("1979-05-27T07:32:00Z")),
+            ("day", Value::from("1979-05-27")),
+        ])
+    );
+}
+
+#[test]
+fn deserialize_toml_datetime_from_value() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Config {
+        created: toml::value::Datetime,
+        stamp: Datetime,
+    }
+
+    let value = map([
+        (
+            "created",
+            Value::Datetime(Datetime::new("1979-05-27T07:32:00Z")),
+        ),
+        ("stamp", Value::from("2024-01-01")),
+    ]);
+    let cfg = Config::deserialize(value).unwrap();
+    assert_eq!(cfg.created.to_string(), "1979-05-27T07:32:00Z");
+    assert_eq!(cfg.stamp, Datetime::new("2024-01-01"));
+}
+
+#[test]
+fn json_conversions() {
+    let json = serde_json::json!({
+        "a": [1, -2, 3.5, "x", null, true],
+        "b": {"big": u64::MAX},
+    });
+    let value = Value::from(json.clone());
+    assert_eq!(
+        value,
+        map([
+            (
+                "a",
+                Value::Seq(vec![
+                    Value::from(1),
+                    Value::from(-2),
+                    Value::from(3.5),
+                    Value::from("x"),
+                    Value::Null,
+                    Value::from(true),
+                ])
+            ),
+            ("b", map([("big", Value::from(u64::MAX))])),
+        ])
+    );
+    assert_eq!(serde_json::Value::try_from(value).unwrap(), json);
+    assert!(serde_json::Value::try_from(Value::Integer(i128::MIN)).is_err());
+}
+
+#[test]
+fn toml_conversions() {
+    let toml = toml::toml! {
+        title = "Example"
+        when = 1979-05-27T07:32:00Z
+        ports = [80, 443]
+        [limits]
+        ratio = 0.25
+    };
+    let value = Value::from(toml::Value::Table(toml.clone()));
+    assert_eq!(
+        value,
+        map([
+            ("limits", map([("ratio", Value::from(0.25))])),
+            ("ports", Value::Seq(vec![Value::from(80), Value::from(443)])),
+            ("title", Value::from("Example")),
+            (
+                "when",
+                Value::Datetime(Datetime::new("1979-05-27T07:32:00Z"))
+            ),
+        ])
+    );
+    assert_eq!(
+        TryInto::<toml::Value>::try_into(value).unwrap(),
+        toml::Value::Table(toml)
+    );
+    assert!(TryInto::<toml::Value>::try_into(map([("x", Value::Null)])).is_err());
+    assert!(TryInto::<toml::Value>::try_into(Value::Datetime(Datetime::new("yesterday"))).is_err());
+}
+
+#[test]
+fn yaml_conversions() {
+    let yaml = serde_yaml::from_str::<serde_yaml::Value>("a: !Point {x: 1}\n1: one\n").unwrap();
+    let value = Value::try_from(yaml).unwrap();
+    assert_eq!(
+        value,
+        map([
+            ("a", map([("Point", map([("x", Value::from(1))]))])),
+            ("1", Value::from("one")),
+        ])
+    );
+    assert_eq!(
+        serde_yaml::Value::try_from(value).unwrap(),
+        serde_yaml::from_str::<serde_yaml::Value>("a: {Point: {x: 1}}\n'1': one\n").unwrap()
+    );
+    let complex = serde_yaml::from_str::<serde_yaml::Value>("? [1, 2]\n: x\n").unwrap();
+    assert!(Value::try_from(complex).is_err());
+}
+
+#[test]
+fn ron_conversions() {
+    let ron =
+        ron::from_str::<ron::Value>("{\"c\": 'x', \"o\": Some(1), \"n\": None, \"b\": b\"hi\"}")
+            .unwrap();
+    let value = Value::try_from(ron).unwrap();
+    assert_eq!(
+        value,
+        map([
+            ("c", Value::from("x")),
+            ("o", Value::from(1)),
+            ("n", Value::Null),
+            ("b", Value::Bytes(b"hi".to_vec())),
+        ])
+    );
+    assert_eq!(
+        ron::to_string(&ron::Value::try_from(value).unwrap()).unwrap(),
+        "{\"b\":b\"hi\",\"c\":\"x\",\"n\":(),\"o\":1}"
+    );
+}
@@ -0,0 +1,116 @@
+#![cfg(all(feature = "json", feature = "toml", feature = "yaml"))]
+use cfgfifo::*;
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use std::fs;
+use tempfile::tempdir;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct Config {
+    name: String,
+    #[serde(default)]
+    debug: bool,
+    #[serde(default)]
+    level: u32,
+}
+
+#[test]
+fn search_nearest_first() {
+    let tmpdir = tempdir().unwrap();
+    let root = tmpdir.path().canonicalize().unwrap();
+    let deep = root.join("a").join("b").join("c");
+    fs::create_dir_all(&deep).unwrap();
+    fs::write(root.join(".apprc.toml"), "name = \"root\"\n").unwrap();
+    fs::write(root.join("a").join(".apprc.yaml"), "level: 1\n").unwrap();
+    fs::write(deep.join(".apprc.json"), r#"{"debug": true}"#).unwrap();
+    fs::write(deep.join("apprc.toml"), "name = \"ignored\"\n").unwrap();
+    let walk = WalkUp::new(".apprc");
+    let found = walk.search(&deep).unwrap();
+    assert_eq!(
+        found[..3],
+        [
+            deep.join(".apprc.json"),
+            root.join("a").join(".apprc.yaml"),
+            root.join(".apprc.toml"),
+        ]
+    );
+    let cfg = walk.layers(&deep).unwrap().load::<Config>().unwrap();
+    assert_eq!(
+        cfg,
+        Config {
+            name: String::from("root"),
+            debug: true,
+            level: 1,
+        }
+    );
+}
+
+#[test]
+fn stop_at_marker() {
+    let tmpdir = tempdir().unwrap();
+    let root = tmpdir.path().canonicalize().unwrap();
+    let repo = root.join("repo");
+    let subdir = repo.join("sub");
+    fs::create_dir_all(&subdir).unwrap();
+    fs::write(repo.join(".git"), "gitdir: elsewhere\n").unwrap();
+    fs::write(root.join(".apprc.toml"), "name = \"outside\"\n").unwrap();
+    fs::write(repo.join(".apprc.toml"), "name = \"repo\"\n").unwrap();
+    let found = WalkUp::new(".apprc")
+        .marker(".hg")
+        .marker(".git")
+        .search(&subdir)
+        .unwrap();
+    assert_eq!(found, [repo.join(".apprc.toml")]);
+}
+
+#[test]
+fn marker_in_start_dir() {
+    let tmpdir = tempdir().unwrap();
+    let root = tmpdir.path().canonicalize().unwrap();
+    let repo = root.join("repo");
+    fs::create_dir_all(repo.join(".git")).unwrap();
+    fs::write(root.join(".apprc.toml"), "name = \"outside\"\n").unwrap();
+    let found = WalkUp::new(".apprc").marker(".git").search(&repo).unwrap();
+    assert!(found.is_empty());
+}
+
+#[test]
+fn ambiguous() {
+    let tmpdir = tempdir().unwrap();
+    let root = tmpdir.path().canonicalize().unwrap();
+    fs::create_dir(root.join(".git")).unwrap();
+    fs::write(root.join(".apprc.toml"), "name = \"a\"\n").unwrap();
+    fs::write(root.join(".apprc.yml"), "name: b\n").unwrap();
+    let r = WalkUp::new(".apprc").marker(".git").search(&root);
+    let Err(DiscoverError::Ambiguous { dir, paths }) = r else {
+        panic!("search() did not fail with Ambiguous error: {r:?}");
+    };
+    assert_eq!(dir, root);
+    assert_eq!(paths, [root.join(".apprc.toml"), root.join(".apprc.yml")]);
+}
+
+#[test]
+fn restricted_formats() {
+    let tmpdir = tempdir().unwrap();
+    let root = tmpdir.path().canonicalize().unwrap();
+    fs::create_dir(root.join(".git")).unwrap();
+    fs::write(root.join(".apprc.toml"), "name = \"a\"\n").unwrap();
+    fs::write(root.join(".apprc.yml"), "name: b\n").unwrap();
+    let found = WalkUp::new(".apprc")
+        .cfgfifo(Cfgfifo::new().formats([Format::Yaml]))
+        .marker(".git")
+        .search(&root)
+        .unwrap();
+    assert_eq!(found, [root.join(".apprc.yml")]);
+}
+
+#[test]
+fn missing_start() {
+    let tmpdir = tempdir().unwrap();
+    let start = tmpdir.path().join("nonexistent");
+    let r = WalkUp::new(".apprc").search(&start);
+    let Err(DiscoverError::Start { path, .. }) = r else {
+        panic!("search() did not fail with Start error: {r:?}");
+    };
+    assert_eq!(path, start);
+}